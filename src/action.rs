@@ -1,25 +1,45 @@
-use crate::model::Model;
+use crate::model::{accepts_count, Model};
+use crate::parser::ActionDef;
 
-pub type ActionCallback<'m> = fn(&mut Model<'m>);
+use std::collections::BTreeMap;
+
+/// Callback of an action, called with the action's arguments. Returns false if
+/// the action could not be run.
+pub type ActionCallback<'m> = fn(&mut Model<'m>, &[String]) -> bool;
 
 /// Action triggered by the user.
 pub struct Action<'m> {
   pub name: String,
   pub description: String,
+  /// Arguments given to the callback.
+  pub args: Vec<String>,
   callback: ActionCallback<'m>,
 }
 
+/// Actions executed in sequence, e.g. when a key is pressed.
+pub type ActionChain<'m> = Vec<Action<'m>>;
+
 impl<'m> Action<'m> {
   pub fn new(name: &str, description: &str, func: ActionCallback<'m>) -> Action<'m> {
     Action {
       name: name.to_string(),
       description: description.to_string(),
+      args: Vec::new(),
       callback: func,
     }
   }
 
-  pub fn execute(&self, model: &mut Model<'m>) {
-    (self.callback)(model);
+  /// Copy of the action with the given arguments.
+  pub fn with_args(&self, args: &[String]) -> Action<'m> {
+    let mut action = self.clone();
+    action.args = args.to_vec();
+    return action;
+  }
+
+  /// Execute the action. Returns false if it could not be run (e.g. a guard
+  /// such as `require_screen` failed).
+  pub fn execute(&self, model: &mut Model<'m>) -> bool {
+    return (self.callback)(model, &self.args);
   }
 }
 
@@ -27,11 +47,45 @@ impl<'m> Clone for Action<'m> {
   fn clone(&self) -> Action<'m> {
     let name: String = self.name.clone();
     let description: String = self.description.clone();
+    let args: Vec<String> = self.args.clone();
     let callback: ActionCallback<'m> = self.callback;
     return Action {
       name: name,
       description: description,
+      args: args,
       callback: callback,
     };
   }
 }
+
+/// Build a chain of actions from their definitions. Returns None if one of
+/// the actions is unknown, so that the actions guarded by it never run.
+pub fn build_chain<'m>(action_map: &BTreeMap<String, Action<'m>>, defs: &[ActionDef]) -> Option<ActionChain<'m>> {
+  defs
+    .iter()
+    .map(|def| action_map.get(def.name.as_str()).map(|a| a.with_args(&def.args)))
+    .collect::<Option<ActionChain<'m>>>()
+}
+
+/// Execute a chain of actions, stopping at the first action that cannot be
+/// run. Returns whether the whole chain was executed.
+pub fn execute_chain<'m>(chain: &[Action<'m>], model: &mut Model<'m>) -> bool {
+  for action in chain {
+    if !action.execute(model) {
+      return false;
+    }
+  }
+  return true;
+}
+
+/// Execute a chain of actions `count` times. An action alone in its chain
+/// receives the count instead, if it supports it (e.g. `scroll_down`).
+pub fn execute_chain_with_count<'m>(chain: &[Action<'m>], model: &mut Model<'m>, count: u32) -> bool {
+  if chain.len() == 1 && accepts_count(&chain[0].name) {
+    model.set_count(count);
+    let res = chain[0].execute(model);
+    model.set_count(1);
+    return res;
+  }
+  return (0..count).all(|_| execute_chain(chain, model));
+}
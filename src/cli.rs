@@ -1,15 +1,27 @@
 extern crate getopts;
 
 
-use crate::config::{Config, ConfigLoader};
+use crate::config::{Config, ConfigLoader, Diagnostic};
 use getopts::Options;
 use std::path::PathBuf;
+use std::process;
 
 fn print_usage(program: &str, opts: &Options) {
   let brief = format!("Usage: {} [options]...", program);
   print!("{}", opts.usage(&brief));
 }
 
+/// Print the configuration problems, and return the number of errors.
+fn print_diagnostics(diagnostics: &[Diagnostic]) -> usize {
+  for d in diagnostics {
+    println!("{}", d);
+  }
+  let n_errors = diagnostics.iter().filter(|d| d.is_error()).count();
+  let n_warnings = diagnostics.len() - n_errors;
+  println!("{} error(s), {} warning(s)", n_errors, n_warnings);
+  return n_errors;
+}
+
 fn print_version() {
   const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
   println!("rust-ncmpc: {}", VERSION.unwrap_or("0.0.1"));
@@ -22,6 +34,7 @@ pub fn process_cli(args: &[String]) -> Option<Config> {
   opts.optopt("p", "port", "connect to server at port", "arg (=6600)");
   opts.optopt("c", "config", "specify configuration file", "arg (=~/.config/ncmpcpp/config)");
   opts.optopt("b", "bindings", "specify bindings file", "arg (=~/.config/ncmpcpp/bindings)");
+  opts.optflag("", "check-config", "check the configuration files and exit");
   opts.optflag("?", "help", "show help message");
   opts.optflag("v", "version", "display version information");
 
@@ -51,6 +64,14 @@ pub fn process_cli(args: &[String]) -> Option<Config> {
 
   // Load config.
   let config_loader = ConfigLoader::new();
+
+  // Only check the configuration files
+  if matches.opt_present("check-config") {
+    let (_, diagnostics) = config_loader.load_with_diagnostics(&opt_config, &opt_bindings);
+    let n_errors = print_diagnostics(&diagnostics);
+    process::exit(if n_errors > 0 { 1 } else { 0 });
+  }
+
   let mut config = config_loader.load(&opt_config, &opt_bindings);
 
   if matches.opt_present("h") {
@@ -0,0 +1,206 @@
+use crate::parser::parse_command_words;
+
+/// Type of a command argument.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ArgType {
+  /// Integer in a given range.
+  Int(i64, i64),
+  /// Time in seconds, absolute or relative, e.g. "90", "1:30", "+30", "-10".
+  Time,
+  /// Any word (quoted if it contains spaces).
+  Word,
+}
+
+/// Time position given to a command.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Position {
+  Absolute(i64),
+  Relative(i64),
+}
+
+/// Parsed value of a command argument.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Arg {
+  Int(i64),
+  Time(Position),
+  Word(String),
+}
+
+/// Built-in command of the command line.
+pub struct CommandSpec {
+  pub name: &'static str,
+  /// Names and types of the arguments.
+  pub args: &'static [(&'static str, ArgType)],
+  pub help: &'static str,
+}
+
+/// Built-in commands. Actions can also be run from the command line, with
+/// their arguments.
+pub const COMMANDS: &[CommandSpec] = &[
+  CommandSpec {
+    name: "add",
+    args: &[("tag", ArgType::Word), ("value", ArgType::Word)],
+    help: "Add the songs whose tag (e.g. artist) matches a value",
+  },
+  CommandSpec {
+    name: "crossfade",
+    args: &[("seconds", ArgType::Int(0, 3600))],
+    help: "Set the crossfade duration",
+  },
+  CommandSpec {
+    name: "save",
+    args: &[("name", ArgType::Word)],
+    help: "Save the playlist",
+  },
+  CommandSpec {
+    name: "seek",
+    args: &[("[+-]time", ArgType::Time)],
+    help: "Seek in the current song (e.g. 1:30, +30)",
+  },
+  CommandSpec {
+    name: "set",
+    args: &[("option", ArgType::Word), ("value", ArgType::Word)],
+    help: "Change a configuration option",
+  },
+  CommandSpec {
+    name: "theme",
+    args: &[("name", ArgType::Word)],
+    help: "Load a color theme",
+  },
+  CommandSpec {
+    name: "volume",
+    args: &[("level", ArgType::Int(0, 100))],
+    help: "Set the volume",
+  },
+];
+
+/// Command typed in the command line.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Command {
+  pub name: String,
+  pub args: Vec<Arg>,
+}
+
+/// Find a built-in command.
+pub fn find_command(name: &str) -> Option<&'static CommandSpec> {
+  COMMANDS.iter().find(|c| c.name == name)
+}
+
+impl CommandSpec {
+  /// Usage of the command, e.g. "volume <level>".
+  pub fn usage(&self) -> String {
+    self
+      .args
+      .iter()
+      .fold(String::from(self.name), |acc, (name, _)| acc + &format!(" <{}>", name))
+  }
+
+  /// Parse the arguments of the command.
+  pub fn parse(&self, words: &[String]) -> Result<Command, String> {
+    if words.len() < self.args.len() {
+      return Err(format!("missing <{}> (usage: {})", self.args[words.len()].0, self.usage()));
+    }
+    if words.len() > self.args.len() {
+      return Err(format!("too many arguments (usage: {})", self.usage()));
+    }
+
+    let mut args = Vec::new();
+    for (word, &(name, typ)) in words.iter().zip(self.args.iter()) {
+      let arg = parse_arg(word, typ).ok_or_else(|| format!("invalid <{}> \"{}\" (usage: {})", name, word, self.usage()))?;
+      args.push(arg);
+    }
+    return Ok(Command {
+      name: String::from(self.name),
+      args: args,
+    });
+  }
+}
+
+impl Command {
+  /// Integer argument at a given index (0 if it is not an integer).
+  pub fn int(&self, idx: usize) -> i64 {
+    match self.args.get(idx) {
+      Some(Arg::Int(i)) => *i,
+      _ => 0,
+    }
+  }
+
+  /// Word argument at a given index (empty if it is not a word).
+  pub fn word(&self, idx: usize) -> &str {
+    match self.args.get(idx) {
+      Some(Arg::Word(w)) => w,
+      _ => "",
+    }
+  }
+
+  /// Time argument at a given index.
+  pub fn time(&self, idx: usize) -> Position {
+    match self.args.get(idx) {
+      Some(Arg::Time(t)) => *t,
+      _ => Position::Relative(0),
+    }
+  }
+}
+
+/// Parse a time, in seconds ("90") or minutes and seconds ("1:30").
+fn parse_seconds(s: &str) -> Option<i64> {
+  let mut total = 0i64;
+  for part in s.split(':') {
+    if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+      return None;
+    }
+    total = total.checked_mul(60)?.checked_add(part.parse::<i64>().ok()?)?;
+  }
+  return Some(total);
+}
+
+fn parse_arg(word: &str, typ: ArgType) -> Option<Arg> {
+  match typ {
+    ArgType::Int(min, max) => word.parse::<i64>().ok().filter(|i| *i >= min && *i <= max).map(Arg::Int),
+    ArgType::Time => {
+      let pos = if word.starts_with('+') {
+        Position::Relative(parse_seconds(&word[1..])?)
+      } else if word.starts_with('-') {
+        Position::Relative(-parse_seconds(&word[1..])?)
+      } else {
+        Position::Absolute(parse_seconds(word)?)
+      };
+      Some(Arg::Time(pos))
+    }
+    ArgType::Word => Some(Arg::Word(String::from(word))),
+  }
+}
+
+/// Split a command line into words.
+pub fn split_command(input: &str) -> Result<Vec<String>, String> {
+  parse_command_words(input).map_err(|_| String::from("unterminated quote"))
+}
+
+#[test]
+fn test_parse_commands() {
+  let parse = |input: &str| {
+    let words = split_command(input)?;
+    find_command(&words[0]).unwrap().parse(&words[1..])
+  };
+
+  assert_eq!(parse("volume 40").unwrap().args, vec![Arg::Int(40)]);
+  assert_eq!(parse("seek +30").unwrap().time(0), Position::Relative(30));
+  assert_eq!(parse("seek -1:05").unwrap().time(0), Position::Relative(-65));
+  assert_eq!(parse("seek 2:00").unwrap().time(0), Position::Absolute(120));
+  assert_eq!(parse("crossfade 5").unwrap().int(0), 5);
+  let add = parse("add artist \"Boards of Canada\"").unwrap();
+  assert_eq!((add.word(0), add.word(1)), ("artist", "Boards of Canada"));
+  assert_eq!(parse("set cyclic_scrolling yes").unwrap().word(1), "yes");
+
+  assert_eq!(
+    parse("volume 140"),
+    Err(String::from("invalid <level> \"140\" (usage: volume <level>)"))
+  );
+  assert_eq!(
+    parse("seek 1:x"),
+    Err(String::from("invalid <[+-]time> \"1:x\" (usage: seek <[+-]time>)"))
+  );
+  assert_eq!(parse("save"), Err(String::from("missing <name> (usage: save <name>)")));
+  assert_eq!(parse("save a b"), Err(String::from("too many arguments (usage: save <name>)")));
+  assert_eq!(parse("save \"a"), Err(String::from("unterminated quote")));
+}
@@ -0,0 +1,414 @@
+extern crate ncurses;
+extern crate xdg;
+
+use crate::constants::*;
+use ncurses as nc;
+
+use std::fs;
+use std::path::PathBuf;
+
+/// Maximum number of commands kept in the history.
+const HISTORY_SIZE: usize = 100;
+
+/// Result of a key press in the command line.
+#[derive(Debug, PartialEq)]
+pub enum CommandLineEvent {
+  /// The line was edited (or nothing happened).
+  Edited,
+  /// The command was validated.
+  Submit(String),
+  /// The command line was closed.
+  Cancel,
+  /// Completion candidates are requested for the current word.
+  Complete,
+}
+
+/// Completion in progress.
+struct Completion {
+  /// Position of the start of the completed word.
+  start: usize,
+  candidates: Vec<String>,
+  /// Index of the current candidate.
+  index: usize,
+}
+
+/// Line editor used to type commands.
+pub struct CommandLine {
+  /// Whether a command is being typed.
+  active: bool,
+  /// Text displayed before the line, e.g. ":".
+  prompt: String,
+  /// Characters of the line.
+  text: Vec<char>,
+  /// Position of the cursor, in characters.
+  cursor: usize,
+  /// Previous commands, the most recent last.
+  history: Vec<String>,
+  /// Position in the history while browsing it.
+  history_pos: usize,
+  /// Line typed before browsing the history.
+  saved_line: String,
+  completion: Option<Completion>,
+  /// Bytes received so far of a multibyte UTF-8 character.
+  utf8_bytes: Vec<u8>,
+}
+
+fn is_word_separator(c: char) -> bool {
+  c.is_whitespace()
+}
+
+impl CommandLine {
+  pub fn new() -> CommandLine {
+    CommandLine {
+      active: false,
+      prompt: String::new(),
+      text: Vec::new(),
+      cursor: 0,
+      history: Vec::new(),
+      history_pos: 0,
+      saved_line: String::new(),
+      completion: None,
+      utf8_bytes: Vec::new(),
+    }
+  }
+
+  /// Start typing a new line, after the given prompt.
+  pub fn start(&mut self, prompt: &str) {
+    self.active = true;
+    self.prompt = String::from(prompt);
+    self.set_text("");
+    self.history_pos = self.history.len();
+    self.saved_line.clear();
+  }
+
+  pub fn is_active(&self) -> bool {
+    self.active
+  }
+
+  pub fn prompt(&self) -> &str {
+    &self.prompt
+  }
+
+  pub fn text(&self) -> String {
+    self.text.iter().collect()
+  }
+
+  /// Position of the cursor, in characters.
+  pub fn cursor(&self) -> usize {
+    self.cursor
+  }
+
+  pub fn history(&self) -> &[String] {
+    &self.history
+  }
+
+  pub fn set_history(&mut self, history: Vec<String>) {
+    self.history = history;
+    self.history_pos = self.history.len();
+  }
+
+  /// Replace the text of the line, and move the cursor to its end.
+  pub fn set_text(&mut self, text: &str) {
+    self.text = text.chars().collect();
+    self.cursor = self.text.len();
+    self.completion = None;
+  }
+
+  /// Process a key press.
+  pub fn process_key(&mut self, ch: i32) -> CommandLineEvent {
+    // Any other key ends the completion
+    if ch != KEY_TAB {
+      self.completion = None;
+    }
+
+    match ch {
+      c if c == '\n' as i32 || c == nc::KEY_ENTER => return self.submit(),
+      KEY_ESCAPE => {
+        self.active = false;
+        return CommandLineEvent::Cancel;
+      }
+      KEY_BACKSPACE | KEY_CTRL_H | nc::KEY_BACKSPACE => {
+        // Close the command line when there is nothing left to delete
+        if self.text.is_empty() {
+          self.active = false;
+          return CommandLineEvent::Cancel;
+        }
+        self.delete_backward();
+      }
+      KEY_TAB => {
+        if !self.next_candidate() {
+          return CommandLineEvent::Complete;
+        }
+      }
+      nc::KEY_DC => self.delete_forward(),
+      nc::KEY_LEFT => self.cursor = self.cursor.saturating_sub(1),
+      nc::KEY_RIGHT => self.cursor = (self.cursor + 1).min(self.text.len()),
+      nc::KEY_HOME | KEY_CTRL_A => self.cursor = 0,
+      nc::KEY_END | KEY_CTRL_E => self.cursor = self.text.len(),
+      KEY_CTRL_W => self.delete_word_backward(),
+      c if c == KEY_ALT + KEY_BACKSPACE => self.delete_word_backward(),
+      KEY_CTRL_U => {
+        self.text.drain(..self.cursor);
+        self.cursor = 0;
+      }
+      KEY_CTRL_K => self.text.truncate(self.cursor),
+      nc::KEY_UP => self.history_previous(),
+      nc::KEY_DOWN => self.history_next(),
+      c if c >= ' ' as i32 && c < KEY_BACKSPACE => self.insert(c as u8 as char),
+      c if c > KEY_BACKSPACE && c <= 0xff => self.insert_utf8_byte(c as u8),
+      _ => {}
+    }
+    return CommandLineEvent::Edited;
+  }
+
+  pub fn insert(&mut self, c: char) {
+    self.text.insert(self.cursor, c);
+    self.cursor += 1;
+  }
+
+  /// Insert the character ending with the given byte, once all its bytes are
+  /// received (getch returns the UTF-8 encoding one byte at a time).
+  fn insert_utf8_byte(&mut self, byte: u8) {
+    self.utf8_bytes.push(byte);
+    match std::str::from_utf8(&self.utf8_bytes) {
+      Ok(s) => {
+        let chars = s.chars().collect::<Vec<char>>();
+        for c in chars {
+          self.insert(c);
+        }
+        self.utf8_bytes.clear();
+      }
+      // Incomplete character
+      Err(ref e) if e.error_len().is_none() => {}
+      Err(_) => self.utf8_bytes.clear(),
+    }
+  }
+
+  fn delete_backward(&mut self) {
+    if self.cursor > 0 {
+      self.cursor -= 1;
+      self.text.remove(self.cursor);
+    }
+  }
+
+  fn delete_forward(&mut self) {
+    if self.cursor < self.text.len() {
+      self.text.remove(self.cursor);
+    }
+  }
+
+  /// Position of the start of the word before the cursor.
+  fn word_start(&self) -> usize {
+    let mut start = self.cursor;
+    while start > 0 && is_word_separator(self.text[start - 1]) {
+      start -= 1;
+    }
+    while start > 0 && !is_word_separator(self.text[start - 1]) {
+      start -= 1;
+    }
+    return start;
+  }
+
+  fn delete_word_backward(&mut self) {
+    let start = self.word_start();
+    self.text.drain(start..self.cursor);
+    self.cursor = start;
+  }
+
+  fn history_previous(&mut self) {
+    if self.history_pos == 0 {
+      return;
+    }
+    if self.history_pos == self.history.len() {
+      self.saved_line = self.text();
+    }
+    self.history_pos -= 1;
+    let line = self.history[self.history_pos].clone();
+    self.set_text(&line);
+  }
+
+  fn history_next(&mut self) {
+    if self.history_pos >= self.history.len() {
+      return;
+    }
+    self.history_pos += 1;
+    let line = if self.history_pos == self.history.len() {
+      self.saved_line.clone()
+    } else {
+      self.history[self.history_pos].clone()
+    };
+    self.set_text(&line);
+  }
+
+  fn submit(&mut self) -> CommandLineEvent {
+    let line = self.text();
+    self.active = false;
+    if !line.trim().is_empty() && self.history.last() != Some(&line) {
+      self.history.push(line.clone());
+      if self.history.len() > HISTORY_SIZE {
+        self.history.remove(0);
+      }
+    }
+    return CommandLineEvent::Submit(line);
+  }
+
+  /// Words before the word being typed, and the start of the latter.
+  pub fn completion_context(&self) -> (Vec<String>, String) {
+    let start = if self.cursor > 0 && is_word_separator(self.text[self.cursor - 1]) {
+      self.cursor
+    } else {
+      self.word_start()
+    };
+    let before = self.text[..start].iter().collect::<String>();
+    let words = before.split_whitespace().map(String::from).collect();
+    let prefix = self.text[start..self.cursor].iter().collect();
+    return (words, prefix);
+  }
+
+  /// Complete the current word with the first candidate. The next candidates
+  /// are used when pressing tab again.
+  pub fn complete(&mut self, candidates: Vec<String>) {
+    if candidates.is_empty() {
+      return;
+    }
+    let start = self.cursor - self.completion_context().1.chars().count();
+    self.completion = Some(Completion {
+      start: start,
+      candidates: candidates,
+      index: 0,
+    });
+    self.apply_candidate();
+  }
+
+  /// Use the next completion candidate, if a completion is in progress.
+  fn next_candidate(&mut self) -> bool {
+    match self.completion {
+      Some(ref mut c) => c.index = (c.index + 1) % c.candidates.len(),
+      None => return false,
+    }
+    self.apply_candidate();
+    return true;
+  }
+
+  fn apply_candidate(&mut self) {
+    if let Some(ref c) = self.completion {
+      let word = c.candidates[c.index].chars().collect::<Vec<char>>();
+      let len = word.len();
+      self.text.splice(c.start..self.cursor, word);
+      self.cursor = c.start + len;
+    }
+  }
+}
+
+impl Default for CommandLine {
+  fn default() -> CommandLine {
+    CommandLine::new()
+  }
+}
+
+/// Path of the file storing the history of commands.
+pub fn history_path() -> Option<PathBuf> {
+  let dirs = xdg::BaseDirectories::with_prefix("ncmpc").ok()?;
+  dirs.place_data_file("command_history").ok()
+}
+
+/// Load the history of commands (one per line).
+pub fn load_history(path: &PathBuf) -> Vec<String> {
+  match fs::read_to_string(path) {
+    Ok(content) => content.lines().map(String::from).collect(),
+    Err(_) => Vec::new(),
+  }
+}
+
+/// Save the history of commands.
+pub fn save_history(path: &PathBuf, history: &[String]) -> bool {
+  let mut content = history.join("\n");
+  content.push('\n');
+  fs::write(path, content).is_ok()
+}
+
+#[cfg(test)]
+fn type_text(cl: &mut CommandLine, text: &str) {
+  for b in text.bytes() {
+    cl.process_key(i32::from(b));
+  }
+}
+
+#[test]
+fn test_command_line_editing() {
+  let mut cl = CommandLine::new();
+  cl.start(":");
+  type_text(&mut cl, "volme");
+  cl.process_key(nc::KEY_LEFT);
+  cl.process_key(nc::KEY_LEFT);
+  type_text(&mut cl, "u");
+  assert_eq!(cl.text(), "volume");
+  assert_eq!(cl.cursor(), 4);
+
+  cl.process_key(nc::KEY_END);
+  type_text(&mut cl, " 40 50");
+  cl.process_key(KEY_CTRL_W);
+  assert_eq!(cl.text(), "volume 40 ");
+  cl.process_key(KEY_BACKSPACE);
+  cl.process_key(nc::KEY_HOME);
+  cl.process_key(nc::KEY_DC);
+  assert_eq!(cl.text(), "olume 40");
+  cl.process_key(KEY_CTRL_K);
+  assert_eq!(cl.text(), "");
+
+  type_text(&mut cl, "quit");
+  assert_eq!(cl.process_key('\n' as i32), CommandLineEvent::Submit(String::from("quit")));
+  assert!(!cl.is_active());
+
+  // Multibyte characters
+  cl.start("/");
+  type_text(&mut cl, "Beyoncé 🎷");
+  assert_eq!(cl.text(), "Beyoncé 🎷");
+  assert_eq!(cl.cursor(), 9);
+  cl.process_key(KEY_BACKSPACE);
+  cl.process_key(0xff);
+  assert_eq!(cl.text(), "Beyoncé ");
+}
+
+#[test]
+fn test_command_line_history() {
+  let mut cl = CommandLine::new();
+  cl.set_history(vec![String::from("first"), String::from("second")]);
+  cl.start(":");
+  type_text(&mut cl, "third");
+  cl.process_key(nc::KEY_UP);
+  assert_eq!(cl.text(), "second");
+  cl.process_key(nc::KEY_UP);
+  cl.process_key(nc::KEY_UP);
+  assert_eq!(cl.text(), "first");
+  cl.process_key(nc::KEY_DOWN);
+  cl.process_key(nc::KEY_DOWN);
+  assert_eq!(cl.text(), "third");
+
+  cl.process_key('\n' as i32);
+  cl.start(":");
+  cl.process_key(nc::KEY_UP);
+  assert_eq!(cl.process_key('\n' as i32), CommandLineEvent::Submit(String::from("third")));
+  assert_eq!(cl.history().len(), 3);
+}
+
+#[test]
+fn test_command_line_completion() {
+  let mut cl = CommandLine::new();
+  cl.start(":");
+  type_text(&mut cl, "theme da");
+  assert_eq!(cl.completion_context(), (vec![String::from("theme")], String::from("da")));
+  assert_eq!(cl.process_key(KEY_TAB), CommandLineEvent::Complete);
+  cl.complete(vec![String::from("dark"), String::from("dawn")]);
+  assert_eq!(cl.text(), "theme dark");
+  assert_eq!(cl.process_key(KEY_TAB), CommandLineEvent::Edited);
+  assert_eq!(cl.text(), "theme dawn");
+  assert_eq!(cl.process_key(KEY_TAB), CommandLineEvent::Edited);
+  assert_eq!(cl.text(), "theme dark");
+
+  type_text(&mut cl, " ");
+  assert_eq!(
+    cl.completion_context(),
+    (vec![String::from("theme"), String::from("dark")], String::new())
+  );
+}
@@ -1,21 +1,25 @@
 extern crate ncurses;
-extern crate ini;
 extern crate xdg;
 
 use crate::constants::*;
-use crate::format::{Column, generate_columns};
+use crate::find::FindMode;
+use crate::format::{Column, SongFormat, generate_columns};
 
-use ini::Ini;
 use ncurses as nc;
-use crate::parser::parse_bindings_configuration;
+use crate::model::get_action_map;
+use crate::parser::{parse_bindings_file, ActionDef, BindingTarget, ParserError};
+use crate::screen::ScreenSwitcherMode;
+use crate::view::{ActiveWindow, UserInterface};
 use std::char;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::fmt;
-use std::fmt::Debug;
+use std::fs;
 use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::SystemTime;
+use time::{get_time, Duration, Timespec};
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ControlKey {
@@ -24,19 +28,316 @@ pub enum ControlKey {
 }
 
 pub type ControlKeys = Vec<ControlKey>;
-pub type CustomActions = HashMap<i32, Vec<String>>;
+
+/// Options of ncmpcpp's configuration files. They are accepted, even if they
+/// are not supported (yet).
+const NCMPCPP_OPTIONS: &[&str] = &[
+  "allow_for_physical_item_deletion",
+  "ask_before_clearing_playlists",
+  "block_search_constraints_change_if_items_found",
+  "browser_display_mode",
+  "browser_playlist_prefix",
+  "browser_sort_format",
+  "browser_sort_mode",
+  "clock_display_seconds",
+  "colors_enabled",
+  "current_item_inactive_column_prefix",
+  "current_item_inactive_column_suffix",
+  "current_item_prefix",
+  "current_item_suffix",
+  "data_fetching_delay",
+  "default_place_to_search_in",
+  "default_tag_editor_pattern",
+  "discard_colors_if_item_is_selected",
+  "execute_on_song_change",
+  "external_editor",
+  "fetch_lyrics_for_current_song_in_background",
+  "follow_now_playing_lyrics",
+  "generate_win32_compatible_filenames",
+  "incremental_seeking",
+  "lastfm_preferred_language",
+  "lyrics_directory",
+  "media_library_primary_tag",
+  "media_library_sort_by_mtime",
+  "message_delay_time",
+  "modified_item_prefix",
+  "mpd_connection_timeout",
+  "mpd_crossfade_time",
+  "mpd_music_dir",
+  "ncmpcpp_directory",
+  "now_playing_prefix",
+  "now_playing_suffix",
+  "playlist_disable_highlight_delay",
+  "playlist_display_mode",
+  "playlist_editor_display_mode",
+  "playlist_separate_albums",
+  "playlist_show_mpd_host",
+  "progressbar_boldness",
+  "search_engine_default_search_mode",
+  "search_engine_display_mode",
+  "seek_time",
+  "selected_item_prefix",
+  "selected_item_suffix",
+  "show_hidden_files_in_local_browser",
+  "song_library_format",
+  "song_list_format",
+  "song_status_format",
+  "store_lyrics_in_song_dir",
+  "system_encoding",
+  "tag_editor_extended_numeration",
+  "tags_separator",
+  "use_console_editor",
+  "visualizer_color",
+  "visualizer_fifo_path",
+  "visualizer_in_stereo",
+  "visualizer_look",
+  "visualizer_output_name",
+  "visualizer_sample_multiplier",
+  "visualizer_sync_interval",
+  "visualizer_type",
+];
+/// Actions of ncmpcpp, some of which are not supported by ncmpc.
+const NCMPCPP_ACTION_NAMES: &[&str] = &[
+  "add",
+  "add_item_to_playlist",
+  "add_random_items",
+  "add_selected_items",
+  "apply_filter",
+  "change_browse_mode",
+  "clear_main_playlist",
+  "clear_playlist",
+  "crop_main_playlist",
+  "crop_playlist",
+  "delete_browser_items",
+  "delete_playlist_items",
+  "delete_stored_playlist",
+  "dummy",
+  "edit_directory_name",
+  "edit_library_album",
+  "edit_library_tag",
+  "edit_lyrics",
+  "edit_playlist_name",
+  "edit_song",
+  "enter_directory",
+  "execute_command",
+  "fetch_lyrics_in_background",
+  "filter_playlist_on_priorities",
+  "find",
+  "find_item_backward",
+  "find_item_forward",
+  "jump_to_browser",
+  "jump_to_media_library",
+  "jump_to_parent_directory",
+  "jump_to_playing_song",
+  "jump_to_playlist_editor",
+  "jump_to_position_in_song",
+  "jump_to_tag_editor",
+  "master_screen",
+  "mouse_event",
+  "move_end",
+  "move_home",
+  "move_selected_items_down",
+  "move_selected_items_to",
+  "move_selected_items_up",
+  "move_sort_order_down",
+  "move_sort_order_up",
+  "next",
+  "next_column",
+  "next_found_item",
+  "next_screen",
+  "page_down",
+  "page_up",
+  "pause",
+  "play",
+  "play_item",
+  "press_enter",
+  "press_space",
+  "previous",
+  "previous_column",
+  "previous_found_item",
+  "previous_screen",
+  "quit",
+  "refetch_lyrics",
+  "remove_selection",
+  "replay_song",
+  "require_runnable",
+  "require_screen",
+  "reset_search_engine",
+  "reverse_playlist",
+  "reverse_selection",
+  "run_action",
+  "run_external_command",
+  "run_external_console_command",
+  "save_playlist",
+  "save_tag_changes",
+  "scroll_down",
+  "scroll_down_album",
+  "scroll_down_artist",
+  "scroll_up",
+  "scroll_up_album",
+  "scroll_up_artist",
+  "seek_backward",
+  "seek_forward",
+  "select_album",
+  "select_found_items",
+  "select_item",
+  "select_range",
+  "set_crossfade",
+  "set_selected_items_priority",
+  "set_visualizer_sample_multiplier",
+  "set_volume",
+  "show_artist_info",
+  "show_browser",
+  "show_clock",
+  "show_help",
+  "show_lyrics",
+  "show_media_library",
+  "show_outputs",
+  "show_playlist",
+  "show_playlist_editor",
+  "show_search_engine",
+  "show_server_info",
+  "show_song_info",
+  "show_tag_editor",
+  "show_visualizer",
+  "shuffle",
+  "slave_screen",
+  "sort_playlist",
+  "start_searching",
+  "stop",
+  "toggle_add_mode",
+  "toggle_bitrate_visibility",
+  "toggle_browser_sort_mode",
+  "toggle_consume",
+  "toggle_crossfade",
+  "toggle_display_mode",
+  "toggle_fetching_lyrics_in_background",
+  "toggle_find_mode",
+  "toggle_interface",
+  "toggle_library_tag_type",
+  "toggle_lyrics_fetcher",
+  "toggle_lyrics_update_on_song_change",
+  "toggle_media_library_columns_mode",
+  "toggle_media_library_sort_mode",
+  "toggle_mouse",
+  "toggle_output",
+  "toggle_playing_song_centering",
+  "toggle_random",
+  "toggle_repeat",
+  "toggle_replay_gain_mode",
+  "toggle_screen_lock",
+  "toggle_separators_between_albums",
+  "toggle_single",
+  "toggle_visualization_type",
+  "update_database",
+  "update_environment",
+  "volume_down",
+  "volume_up",
+];
+
+/// Actions of ncmpcpp supported by ncmpc, and the name of the equivalent
+/// ncmpc action.
+const NCMPCPP_ACTIONS: &[(&str, &str)] = &[
+  ("add_random_items", "add_random_items"),
+  ("apply_filter", "apply_filter"),
+  ("clear_main_playlist", "playlist_clear"),
+  ("clear_playlist", "playlist_clear"),
+  ("delete_playlist_items", "playlist_delete_items"),
+  ("dummy", "dummy"),
+  ("execute_command", "execute_command"),
+  ("find", "find"),
+  ("find_item_backward", "find_item_backward"),
+  ("find_item_forward", "find_item_forward"),
+  ("jump_to_playing_song", "jump_to_playing_song"),
+  ("master_screen", "master_screen"),
+  ("mouse_event", "process_mouse"),
+  ("move_end", "move_end"),
+  ("move_home", "move_home"),
+  ("next", "playlist_next"),
+  ("next_found_item", "next_found_item"),
+  ("next_screen", "next_screen"),
+  ("page_down", "page_down"),
+  ("page_up", "page_up"),
+  ("pause", "playlist_pause"),
+  ("play", "playlist_play"),
+  ("play_item", "play_selected"),
+  ("press_enter", "play_selected"),
+  ("previous", "playlist_previous"),
+  ("previous_found_item", "previous_found_item"),
+  ("previous_screen", "previous_screen"),
+  ("quit", "quit"),
+  ("require_runnable", "require_runnable"),
+  ("require_screen", "require_screen"),
+  ("run_external_command", "run_external_command"),
+  ("scroll_down", "scroll_down"),
+  ("scroll_up", "scroll_up"),
+  ("set_selected_items_priority", "set_selected_items_priority"),
+  ("show_help", "show_help"),
+  ("show_playlist", "show_playlist"),
+  ("show_server_info", "show_server_info"),
+  ("slave_screen", "slave_screen"),
+  ("stop", "playlist_stop"),
+  ("toggle_bitrate_visibility", "toggle_bitrate_visibility"),
+  ("toggle_find_mode", "toggle_find_mode"),
+  ("toggle_interface", "toggle_interface"),
+  ("toggle_mouse", "toggle_mouse"),
+  ("toggle_playing_song_centering", "toggle_playing_song_centering"),
+  ("toggle_random", "toggle_random"),
+  ("toggle_repeat", "toggle_repeat"),
+  ("toggle_screen_lock", "toggle_screen_lock"),
+  ("volume_down", "volume_down"),
+  ("volume_up", "volume_up"),
+];
+
+/// Name of the ncmpc action equivalent to an ncmpcpp action. Other names (e.g.
+/// of actions specific to ncmpc) are kept.
+pub fn translate_action(name: &str) -> &str {
+  match NCMPCPP_ACTIONS.iter().find(|&&(ncmpcpp, _)| ncmpcpp == name) {
+    Some(&(_, ncmpc)) => ncmpc,
+    None => name,
+  }
+}
+
+/// Keycodes of a key sequence, e.g. "g g".
+pub type KeySequence = Vec<i32>;
+
+/// Chains of actions bound to key sequences, for all screens (`None`) or for a
+/// given screen. When keys are bound to several chains, they are tried in order
+/// until one of them can be run.
+pub type CustomActions = HashMap<(Option<String>, KeySequence), Vec<Vec<ActionDef>>>;
+
+/// Command defined with `def_command`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct UserCommand {
+  /// Whether the command runs as soon as its name is typed.
+  pub immediate: bool,
+  /// Chain of actions.
+  pub actions: Vec<ActionDef>,
+}
+
+pub type UserCommands = BTreeMap<String, UserCommand>;
 
 /// Key bindings configuration.
 #[derive(Clone, PartialEq, Debug, Default)]
 pub struct KeyConfig {
+  pub apply_filter: ControlKeys,
   pub execute_command: ControlKeys,
   pub clear: ControlKeys,
   pub delete: ControlKeys,
+  pub find_item_backward: ControlKeys,
+  pub find_item_forward: ControlKeys,
+  pub jump_to_playing_song: ControlKeys,
+  pub master_screen: ControlKeys,
   pub next: ControlKeys,
+  pub next_found_item: ControlKeys,
+  pub next_screen: ControlKeys,
   pub play_pause: ControlKeys,
   pub press_enter: ControlKeys,
   pub previous: ControlKeys,
+  pub previous_found_item: ControlKeys,
+  pub previous_screen: ControlKeys,
   pub quit: ControlKeys,
+  pub reload_config: ControlKeys,
   pub scroll_down: ControlKeys,
   pub scroll_up: ControlKeys,
   pub page_down: ControlKeys,
@@ -46,19 +347,33 @@ pub struct KeyConfig {
   pub show_help: ControlKeys,
   pub show_playlist: ControlKeys,
   pub show_server_info: ControlKeys,
+  pub slave_screen: ControlKeys,
   pub stop: ControlKeys,
   pub toggle_bitrate_visibility: ControlKeys,
+  pub toggle_find_mode: ControlKeys,
+  pub toggle_interface: ControlKeys,
+  pub toggle_mouse: ControlKeys,
+  pub toggle_playing_song_centering: ControlKeys,
   pub toggle_random: ControlKeys,
   pub toggle_repeat: ControlKeys,
+  pub toggle_screen_lock: ControlKeys,
   pub volume_down: ControlKeys,
   pub volume_up: ControlKeys,
   pub custom: CustomActions,
+  pub commands: UserCommands,
 }
 
+/// Colors configuration (ncmpcpp keys, e.g. `main_window_color`).
 #[derive(Clone, Copy, PartialEq, Debug, Default)]
 pub struct ColorConfig {
+  /// Color of the key names in the help screen.
   pub color1: Color,
+  /// Color of the section titles in the help screen.
   pub color2: Color,
+  pub active_column: Color,
+  pub active_window_border: Color,
+  pub alternative_ui_separator: Color,
+  pub empty_tag: Color,
   pub header_window: Color,
   pub main_window: Color,
   pub main_window_highlight: Color,
@@ -73,58 +388,163 @@ pub struct ColorConfig {
 
 #[derive(Clone, PartialEq, Debug, Default)]
 pub struct ParamConfig {
+  pub alternative_header_first_line_format: SongFormat,
+  pub alternative_header_second_line_format: SongFormat,
+  /// Whether the width of the locked screen is asked when locking it.
+  pub ask_for_locked_screen_width_part: bool,
+  /// Whether the playlist follows the playing song when it changes.
+  pub autocenter_mode: bool,
+  /// Whether the selected row is kept in the middle of the screen.
+  pub centered_cursor: bool,
   pub cyclic_scrolling: bool,
+  pub default_find_mode: FindMode,
   pub display_bitrate: bool,
   pub display_remaining_time: bool,
   pub display_volume_level: bool,
+  pub empty_tag_marker: String,
+  /// Whether the title of the terminal shows the playing song.
+  pub enable_window_title: bool,
   pub header_text_scrolling: bool,
+  pub header_visibility: bool,
+  /// Whether a leading "The" is ignored when finding items.
+  pub ignore_leading_the: bool,
+  pub jump_to_now_playing_song_at_start: bool,
+  /// Time (in milliseconds) to wait for the next key of a key sequence.
+  pub key_sequence_timeout: i32,
+  /// Number of lines scrolled by the mouse wheel.
+  pub lines_scrolled: i32,
+  /// Width of the locked screen (in %).
+  pub locked_screen_width_part: i32,
+  pub mouse_list_scroll_whole_page: bool,
+  pub mouse_support: bool,
   pub mpd_host: String,
   pub mpd_port: u16,
+  /// Whether the lengths shown in the playlist header are shortened.
+  pub playlist_shorten_total_times: bool,
+  /// Whether the playlist header shows the time left until its end.
+  pub playlist_show_remaining_time: bool,
   pub progressbar_look: String,
+  /// Whether patterns used to find items are regular expressions.
+  pub regular_expressions: bool,
+  pub screen_switcher_mode: ScreenSwitcherMode,
   pub song_columns_list_format: Vec<Column>,
+  /// Format of the playing song in the title of the terminal.
+  pub song_window_title_format: SongFormat,
+  pub startup_screen: ActiveWindow,
+  /// Screen shown next to the locked startup screen, if any.
+  pub startup_slave_screen: Option<ActiveWindow>,
+  /// Whether the startup slave screen is focused.
+  pub startup_slave_screen_focus: bool,
+  pub statusbar_visibility: bool,
+  pub theme: String,
+  /// Whether the column titles of the playlist are shown.
+  pub titles_visibility: bool,
+  pub user_interface: UserInterface,
   pub volume_change_step: i8,
 }
 
 #[derive(Clone, PartialEq, Debug, Default)]
 pub struct Config {
   pub colors: ColorConfig,
+  /// Colors of the configuration file (and `:set`), without the theme.
+  pub base_colors: ColorConfig,
   pub keys: KeyConfig,
   pub params: ParamConfig,
+  pub files: ConfigFiles,
+}
+
+/// Severity of a configuration problem.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Severity {
+  /// The configuration can be used, but some of it is ignored.
+  Warning,
+  /// The configuration is invalid.
+  Error,
+}
+
+/// Problem found while loading the configuration.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Diagnostic {
+  /// File containing the problem.
+  pub file: PathBuf,
+  /// Line of the problem (starting from 1), if known.
+  pub line: Option<u32>,
+  /// Configuration key (option name, or key name for bindings), if any.
+  pub key: String,
+  /// Description of the problem.
+  pub message: String,
+  pub severity: Severity,
+}
+
+/// Files the configuration was loaded from.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ConfigFiles {
+  pub config: Option<PathBuf>,
+  pub bindings: Option<PathBuf>,
+  pub theme: Option<PathBuf>,
+}
+
+/// Watch the configuration files for modifications.
+pub struct ConfigWatcher {
+  /// Watched files, with their last modification time.
+  files: Vec<(PathBuf, Option<SystemTime>)>,
+  /// Time of the last check.
+  check_time: Timespec,
+  /// Duration between two checks.
+  dt: Duration,
 }
 
 #[derive(Default)]
 pub struct ConfigLoader {
   default_config_path: Option<PathBuf>,
   default_bindings_path: Option<PathBuf>,
+  xdg_dirs: Option<xdg::BaseDirectories>,
 }
 
 impl KeyConfig {
   pub fn new() -> KeyConfig {
     KeyConfig {
+      apply_filter: vec![ControlKey::KeyCode(KEY_CTRL_F)],
       execute_command: vec![ControlKey::Char(':')],
       clear: vec![ControlKey::Char('c')],
       delete: vec![ControlKey::KeyCode(nc::KEY_DC)],
+      find_item_backward: vec![ControlKey::Char('?')],
+      find_item_forward: vec![ControlKey::Char('/')],
+      jump_to_playing_song: vec![ControlKey::Char('o')],
+      master_screen: vec![],
       next: vec![ControlKey::Char('>')],
+      next_found_item: vec![ControlKey::Char('.')],
+      next_screen: vec![ControlKey::KeyCode(KEY_TAB)],
       play_pause: vec![ControlKey::Char('p')],
       press_enter: vec![ControlKey::Char('\n')],
       previous: vec![ControlKey::Char('<')],
+      previous_found_item: vec![ControlKey::Char(',')],
+      previous_screen: vec![ControlKey::KeyCode(nc::KEY_BTAB)],
       quit: vec![ControlKey::Char('q')],
+      reload_config: vec![],
       scroll_down: vec![ControlKey::KeyCode(nc::KEY_DOWN)],
       scroll_up: vec![ControlKey::KeyCode(nc::KEY_UP)],
       page_down: vec![ControlKey::KeyCode(nc::KEY_NPAGE)],
       page_up: vec![ControlKey::KeyCode(nc::KEY_PPAGE)],
       show_help: vec![ControlKey::KeyCode(nc::KEY_F1)],
       show_server_info: vec![ControlKey::Char('@')],
+      slave_screen: vec![],
       move_home: vec![ControlKey::KeyCode(nc::KEY_HOME)],
       move_end: vec![ControlKey::KeyCode(nc::KEY_END)],
       show_playlist: vec![ControlKey::Char('1')],
       stop: vec![ControlKey::Char('s')],
       toggle_bitrate_visibility: vec![ControlKey::Char('#')],
+      toggle_find_mode: vec![ControlKey::Char('w')],
+      toggle_interface: vec![ControlKey::Char('\\')],
+      toggle_mouse: vec![ControlKey::Char('|')],
+      toggle_playing_song_centering: vec![ControlKey::Char('U')],
       toggle_random: vec![ControlKey::Char('z')],
       toggle_repeat: vec![ControlKey::Char('r')],
+      toggle_screen_lock: vec![ControlKey::KeyCode(KEY_CTRL_L)],
       volume_down: vec![ControlKey::KeyCode(nc::KEY_LEFT)],
       volume_up: vec![ControlKey::KeyCode(nc::KEY_RIGHT)],
       custom: CustomActions::default(),
+      commands: UserCommands::default(),
     }
   }
 
@@ -135,7 +555,7 @@ impl KeyConfig {
       None => false,
       Some(keys) => {
         // TODO: use HashSet instead?
-        if !keys.contains(&key) {
+        if !keys.iter().any(|k| k.keycode() == key.keycode()) {
           keys.push(key);
           true
         } else {
@@ -145,18 +565,28 @@ impl KeyConfig {
     };
   }
 
-  /// Map from string to class members.
+  /// Map from ncmpc action names (see `NCMPCPP_ACTIONS`) to class members.
   fn action_to_keys(&mut self, action: &str) -> Option<&mut ControlKeys> {
     return match action {
       // FIXME: find a way to automate this from class members
+      "apply_filter" => Some(&mut self.apply_filter),
       "execute_command" => Some(&mut self.execute_command),
-      "clear" => Some(&mut self.clear),
-      "delete" => Some(&mut self.delete),
-      "next" => Some(&mut self.next),
-      "play_pause" => Some(&mut self.play_pause),
-      "press_enter" => Some(&mut self.press_enter),
-      "previous" => Some(&mut self.previous),
+      "playlist_clear" => Some(&mut self.clear),
+      "playlist_delete_items" => Some(&mut self.delete),
+      "find_item_backward" => Some(&mut self.find_item_backward),
+      "find_item_forward" => Some(&mut self.find_item_forward),
+      "jump_to_playing_song" => Some(&mut self.jump_to_playing_song),
+      "master_screen" => Some(&mut self.master_screen),
+      "playlist_next" => Some(&mut self.next),
+      "next_found_item" => Some(&mut self.next_found_item),
+      "next_screen" => Some(&mut self.next_screen),
+      "playlist_pause" => Some(&mut self.play_pause),
+      "play_selected" => Some(&mut self.press_enter),
+      "playlist_previous" => Some(&mut self.previous),
+      "previous_found_item" => Some(&mut self.previous_found_item),
+      "previous_screen" => Some(&mut self.previous_screen),
       "quit" => Some(&mut self.quit),
+      "reload_config" => Some(&mut self.reload_config),
       "scroll_down" => Some(&mut self.scroll_down),
       "scroll_up" => Some(&mut self.scroll_up),
       "page_down" => Some(&mut self.page_down),
@@ -166,10 +596,16 @@ impl KeyConfig {
       "show_help" => Some(&mut self.show_help),
       "show_playlist" => Some(&mut self.show_playlist),
       "show_server_info" => Some(&mut self.show_server_info),
-      "stop" => Some(&mut self.stop),
+      "slave_screen" => Some(&mut self.slave_screen),
+      "playlist_stop" => Some(&mut self.stop),
       "toggle_bitrate_visibility" => Some(&mut self.toggle_bitrate_visibility),
+      "toggle_find_mode" => Some(&mut self.toggle_find_mode),
+      "toggle_interface" => Some(&mut self.toggle_interface),
+      "toggle_mouse" => Some(&mut self.toggle_mouse),
+      "toggle_playing_song_centering" => Some(&mut self.toggle_playing_song_centering),
       "toggle_random" => Some(&mut self.toggle_random),
       "toggle_repeat" => Some(&mut self.toggle_repeat),
+      "toggle_screen_lock" => Some(&mut self.toggle_screen_lock),
       "volume_down" => Some(&mut self.volume_down),
       "volume_up" => Some(&mut self.volume_up),
       _ => None,
@@ -177,74 +613,185 @@ impl KeyConfig {
   }
 }
 
+/// Special keys: name, ncurses keycode and terminfo name of its variants with
+/// modifiers (e.g. "kUP5" for ctrl-up).
+const SPECIAL_KEYS: &[(&str, i32, &str)] = &[
+  ("up", nc::KEY_UP, "kUP"),
+  ("down", nc::KEY_DOWN, "kDN"),
+  ("left", nc::KEY_LEFT, "kLFT"),
+  ("right", nc::KEY_RIGHT, "kRIT"),
+  ("home", nc::KEY_HOME, "kHOM"),
+  ("end", nc::KEY_END, "kEND"),
+  ("page_up", nc::KEY_PPAGE, "kPRV"),
+  ("page_down", nc::KEY_NPAGE, "kNXT"),
+  ("insert", nc::KEY_IC, "kIC"),
+  ("delete", nc::KEY_DC, "kDC"),
+  ("escape", KEY_ESCAPE, ""),
+  ("space", ' ' as i32, ""),
+  ("tab", KEY_TAB, ""),
+  ("backspace", KEY_BACKSPACE, ""),
+  ("enter", '\n' as i32, ""),
+  ("mouse", nc::KEY_MOUSE, ""),
+];
+
+/// Special keys with shift that have their own ncurses keycode.
+const SHIFTED_KEYS: &[(&str, i32)] = &[
+  ("up", nc::KEY_SR),
+  ("down", nc::KEY_SF),
+  ("left", nc::KEY_SLEFT),
+  ("right", nc::KEY_SRIGHT),
+  ("home", nc::KEY_SHOME),
+  ("end", nc::KEY_SEND),
+  ("page_up", nc::KEY_SPREVIOUS),
+  ("page_down", nc::KEY_SNEXT),
+  ("insert", nc::KEY_SIC),
+  ("delete", nc::KEY_SDC),
+  ("tab", nc::KEY_BTAB),
+];
+
+// xterm modifiers
+const MOD_SHIFT: i32 = 1;
+const MOD_ALT: i32 = 2;
+const MOD_CTRL: i32 = 4;
+
+/// Split the modifiers from a key name, e.g. "ctrl-shift-up" gives the
+/// modifiers and "up".
+fn split_modifiers(key: &str) -> (i32, &str) {
+  let mut modifiers = 0;
+  let mut rest = key;
+  loop {
+    let (modifier, prefix_len) = if rest.starts_with("shift-") || rest.starts_with("shift_") {
+      (MOD_SHIFT, 6)
+    } else if rest.starts_with("ctrl-") || rest.starts_with("ctrl_") {
+      (MOD_CTRL, 5)
+    } else if rest.starts_with("alt-") || rest.starts_with("alt_") {
+      (MOD_ALT, 4)
+    } else {
+      break;
+    };
+    // The remaining part cannot be empty
+    if rest.len() == prefix_len {
+      break;
+    }
+    modifiers |= modifier;
+    rest = &rest[prefix_len..];
+  }
+  return (modifiers, rest);
+}
+
+/// Keycode of ctrl-?.
+fn ctrl_keycode(c: char) -> i32 {
+  if c >= 'a' && c <= 'z' {
+    return 1 + (c as i32 - 'a' as i32);
+  } else if c == '[' {
+    return KEY_CTRL_LEFTBRACKET;
+  } else if c == '\\' {
+    return KEY_CTRL_BACKSLASH;
+  } else if c == ']' {
+    return KEY_CTRL_RIGHTBRACKET;
+  } else if c == '^' {
+    return KEY_CTRL_CARET;
+  } else if c == '_' {
+    return KEY_CTRL_UNDERSCORE;
+  }
+  // Discard control qualifier
+  return c as i32;
+}
+
 fn to_keycode(key: &str) -> i32 {
   if key.chars().count() == 1 {
     return key.chars().next().unwrap() as i32;
-  } else {
-    // ctrl-?
-    if key.len() == 6 && (key.starts_with("ctrl_") || key.starts_with("ctrl-")) {
-      let next: char = key.chars().nth(5).unwrap();
-      if next >= 'a' && next <= 'z' {
-        return 1 + (next as i32 - 'a' as i32);
-      } else if next == '[' {
-        return KEY_CTRL_LEFTBRACKET;
-      } else if next == '\\' {
-        return KEY_CTRL_BACKSLASH;
-      } else if next == ']' {
-        return KEY_CTRL_RIGHTBRACKET;
-      } else if next == '^' {
-        return KEY_CTRL_CARET;
-      } else if next == '_' {
-        return KEY_CTRL_UNDERSCORE;
+  }
+
+  let (modifiers, name) = split_modifiers(key);
+  let alt = if modifiers & MOD_ALT != 0 { KEY_ALT } else { 0 };
+
+  // Characters: shift-?, ctrl-?, alt-?
+  if name.chars().count() == 1 {
+    let c = name.chars().next().unwrap();
+    let code = if modifiers & MOD_CTRL != 0 {
+      ctrl_keycode(c)
+    } else if modifiers & MOD_SHIFT != 0 {
+      c.to_ascii_uppercase() as i32
+    } else {
+      c as i32
+    };
+    return alt + code;
+  }
+
+  // f?
+  if name.starts_with('f') {
+    if let Ok(n) = name[1..].parse::<i32>() {
+      if n < 0 || n > 12 {
+        return -1;
+      }
+      // Same as xterm: shift-f1 = f13, ctrl-f1 = f25, alt-f1 = f49, etc.
+      let offset = match modifiers {
+        0 => 0,
+        MOD_SHIFT => 12,
+        MOD_CTRL => 24,
+        m if m == MOD_CTRL | MOD_SHIFT => 36,
+        MOD_ALT => 48,
+        _ => return -1,
+      };
+      return nc::KEY_F0 + offset + n;
+    }
+  }
+
+  if modifiers == MOD_SHIFT {
+    if let Some(&(_, code)) = SHIFTED_KEYS.iter().find(|&&(n, _)| n == name) {
+      return code;
+    }
+  }
+
+  match SPECIAL_KEYS.iter().position(|&(n, _, _)| n == name) {
+    None => -1,
+    Some(idx) => {
+      let (_, code, terminfo) = SPECIAL_KEYS[idx];
+      if modifiers == 0 {
+        code
+      } else if !terminfo.is_empty() {
+        KEY_MODIFIED + 16 * idx as i32 + 1 + modifiers
+      } else if modifiers == MOD_ALT {
+        KEY_ALT + code
+      } else {
+        -1
       }
-      // Discard control qualifier
-      return next as i32;
-    }
-    // shift-?
-    else if key.starts_with("shift_") {
-      // TODO
-      return nc::KEY_UP;
-    }
-    // f?
-    else if key.starts_with('f') {
-      let mut iter = key.chars();
-      iter.by_ref().nth(0);
-      // TODO: return error if required
-      let n: i32 = iter.as_str().parse().unwrap_or(0);
-      return nc::KEY_F0 + n;
-    }
-    // TODO: use a hashmap for the rest
-    else if key == "escape" {
-      return KEY_ESCAPE;
-    } else if key == "up" {
-      return nc::KEY_UP;
-    } else if key == "down" {
-      return nc::KEY_DOWN;
-    } else if key == "left" {
-      return nc::KEY_LEFT;
-    } else if key == "right" {
-      return nc::KEY_RIGHT;
-    } else if key == "page_up" {
-      return nc::KEY_PPAGE;
-    } else if key == "page_down" {
-      return nc::KEY_NPAGE;
-    } else if key == "home" {
-      return nc::KEY_HOME;
-    } else if key == "end" {
-      return nc::KEY_END;
-    } else if key == "space" {
-      return ' ' as i32;
-    } else if key == "insert" {
-      return nc::KEY_IC;
-    } else if key == "delete" {
-      return nc::KEY_DC;
-    } else if key == "tab" {
-      return KEY_TAB;
-    } else if key == "backspace" {
-      return KEY_BACKSPACE;
-    }
-    return -1;
+    }
+  }
+}
+
+/// Keycodes of a key sequence, e.g. "ctrl-x ctrl-s".
+fn to_key_sequence(keys: &str) -> Option<KeySequence> {
+  if keys.chars().count() == 1 {
+    return Some(vec![to_keycode(keys)]);
   }
+  let codes = keys.split_whitespace().map(to_keycode).collect::<KeySequence>();
+  if codes.is_empty() || codes.iter().any(|&c| c < 0) {
+    return None;
+  }
+  return Some(codes);
+}
+
+/// Convert a keycode returned by ncurses to the keycode used in the key
+/// bindings. Keys with modifiers defined by terminfo's extensions (e.g. "kUP5"
+/// for ctrl-up) have keycodes that depend on the terminal.
+pub fn normalize_keycode(c: i32) -> i32 {
+  if c <= nc::KEY_MAX {
+    return c;
+  }
+
+  if let Some(name) = nc::keyname(c) {
+    // Split name and xterm modifier number, e.g. "kUP" and 5
+    let split = name.len() - 1;
+    if let Ok(n) = name[split..].parse::<i32>() {
+      let base = &name[..split];
+      if let Some(idx) = SPECIAL_KEYS.iter().position(|&(_, _, t)| !t.is_empty() && t == base) {
+        return KEY_MODIFIED + 16 * idx as i32 + n;
+      }
+    }
+  }
+  return c;
 }
 
 pub trait ToKeyCode {
@@ -273,7 +820,28 @@ impl ToKeyCode for ControlKey {
 }
 
 fn from_keycode(c: i32) -> String {
-  if c > nc::KEY_F0 && c <= nc::KEY_F15 {
+  if c >= KEY_MODIFIED {
+    let idx = ((c - KEY_MODIFIED) / 16) as usize;
+    let modifiers = (c - KEY_MODIFIED) % 16 - 1;
+    let mut name = String::new();
+    if modifiers & MOD_CTRL != 0 {
+      name.push_str("Ctrl-");
+    }
+    if modifiers & MOD_ALT != 0 {
+      name.push_str("Alt-");
+    }
+    if modifiers & MOD_SHIFT != 0 {
+      name.push_str("Shift-");
+    }
+    let key = SPECIAL_KEYS.get(idx).map(|&(_, code, _)| from_keycode(code));
+    name.push_str(&key.unwrap_or_else(|| String::from("unknown")));
+    return name;
+  } else if c >= KEY_ALT {
+    return format!("Alt-{}", from_keycode(c - KEY_ALT));
+  } else if let Some(&(name, _)) = SHIFTED_KEYS.iter().find(|&&(_, code)| code == c) {
+    let key = to_keycode(name);
+    return format!("Shift-{}", from_keycode(key));
+  } else if c > nc::KEY_F0 && c <= nc::KEY_F0 + 60 {
     return format!("F{}", c - nc::KEY_F0);
   } else if c == nc::KEY_UP {
     return String::from("Up");
@@ -287,8 +855,18 @@ fn from_keycode(c: i32) -> String {
     return String::from("Home");
   } else if c == nc::KEY_END {
     return String::from("End");
+  } else if c == nc::KEY_PPAGE {
+    return String::from("PageUp");
+  } else if c == nc::KEY_NPAGE {
+    return String::from("PageDown");
+  } else if c == nc::KEY_IC {
+    return String::from("Insert");
   } else if c == nc::KEY_DC {
     return String::from("Delete");
+  } else if c == KEY_ESCAPE {
+    return String::from("Escape");
+  } else if c == nc::KEY_MOUSE {
+    return String::from("Mouse");
   } else if c == KEY_TAB {
     return String::from("Tab");
   } else if c == KEY_BACKSPACE {
@@ -297,6 +875,8 @@ fn from_keycode(c: i32) -> String {
     return String::from("Enter");
   } else if c == ' ' as i32 {
     return String::from("Space");
+  } else if c >= KEY_CTRL_A && c < KEY_CTRL_LEFTBRACKET {
+    return format!("Ctrl-{}", char::from_u32(('a' as i32 + c - KEY_CTRL_A) as u32).unwrap_or('?'));
   } else {
     return match char::from_u32(c as u32) {
       None => String::from("unknown"),
@@ -326,23 +906,32 @@ impl fmt::Display for ControlKey {
 
 impl ColorConfig {
   pub fn new() -> ColorConfig {
+    // Same defaults as ncmpcpp
     ColorConfig {
-      color1: 0,
-      color2: 0,
-      header_window: 0,
-      main_window: 0,
-      main_window_highlight: 0,
-      progressbar: 0,
-      progressbar_elapsed: 0,
-      state_flags: 0,
-      state_line: 0,
-      statusbar: 0,
-      volume: 0,
-      window_border: 0,
+      color1: nc::COLOR_WHITE,
+      color2: nc::COLOR_GREEN,
+      active_column: nc::COLOR_RED,
+      active_window_border: nc::COLOR_RED,
+      alternative_ui_separator: nc::COLOR_BLACK,
+      empty_tag: nc::COLOR_CYAN,
+      header_window: COLOR_DEFAULT,
+      main_window: nc::COLOR_YELLOW,
+      main_window_highlight: nc::COLOR_YELLOW,
+      progressbar: nc::COLOR_BLACK,
+      progressbar_elapsed: nc::COLOR_GREEN,
+      state_flags: COLOR_DEFAULT,
+      state_line: COLOR_DEFAULT,
+      statusbar: COLOR_DEFAULT,
+      volume: COLOR_DEFAULT,
+      window_border: nc::COLOR_GREEN,
     }
   }
 }
 
+const DEFAULT_ALTERNATIVE_HEADER_FIRST_LINE_FORMAT: &str = "$b$1$aqqu$/a$9 {%t}|{%f} $1$atqq$/a$9$/b";
+const DEFAULT_ALTERNATIVE_HEADER_SECOND_LINE_FORMAT: &str = "{{$4$b%a$/b$9}{ - $7%b$9}{ ($4%y$9)}}|{%D}";
+const DEFAULT_SONG_WINDOW_TITLE_FORMAT: &str = "{%a - }{%t}|{%f}";
+
 fn default_song_columns_list_format() -> Vec<Column> {
   return generate_columns(
     "(20)[]{a} (6f)[green]{NE} (50)[white]{t|f:Title} (20)[cyan]{b} \
@@ -353,15 +942,43 @@ fn default_song_columns_list_format() -> Vec<Column> {
 impl ParamConfig {
   pub fn new() -> ParamConfig {
     ParamConfig {
+      alternative_header_first_line_format: SongFormat::parse(DEFAULT_ALTERNATIVE_HEADER_FIRST_LINE_FORMAT).unwrap(),
+      alternative_header_second_line_format: SongFormat::parse(DEFAULT_ALTERNATIVE_HEADER_SECOND_LINE_FORMAT).unwrap(),
+      ask_for_locked_screen_width_part: true,
+      autocenter_mode: false,
+      centered_cursor: false,
       cyclic_scrolling: false,
+      default_find_mode: FindMode::Wrapped,
       display_bitrate: false,
       display_remaining_time: false,
       display_volume_level: true,
+      empty_tag_marker: String::from("<empty>"),
+      enable_window_title: true,
       header_text_scrolling: true,
+      header_visibility: true,
+      ignore_leading_the: false,
+      jump_to_now_playing_song_at_start: true,
+      key_sequence_timeout: 1000,
+      lines_scrolled: 2,
+      locked_screen_width_part: 50,
+      mouse_list_scroll_whole_page: true,
+      mouse_support: true,
       mpd_host: String::from("localhost"),
       mpd_port: 6600,
+      playlist_shorten_total_times: false,
+      playlist_show_remaining_time: false,
       progressbar_look: String::from("=>"),
+      regular_expressions: true,
+      screen_switcher_mode: ScreenSwitcherMode::default(),
       song_columns_list_format: default_song_columns_list_format(),
+      song_window_title_format: SongFormat::parse(DEFAULT_SONG_WINDOW_TITLE_FORMAT).unwrap(),
+      startup_screen: ActiveWindow::Playlist,
+      startup_slave_screen: None,
+      startup_slave_screen_focus: false,
+      statusbar_visibility: true,
+      theme: String::default(),
+      titles_visibility: true,
+      user_interface: UserInterface::Classic,
       volume_change_step: 2,
     }
   }
@@ -385,8 +1002,22 @@ impl Config {
 
     Config {
       colors: ColorConfig::new(),
+      base_colors: ColorConfig::new(),
       keys: keys,
       params: params,
+      files: ConfigFiles::default(),
+    }
+  }
+
+  /// Change a configuration option, e.g. from the command line.
+  pub fn set(&mut self, key: &str, val: &str) -> Result<(), String> {
+    match assign(key, val, self)? {
+      true => {
+        // Colors set while running are kept when changing the theme
+        let _ = assign_color(key, val, &mut self.base_colors);
+        Ok(())
+      }
+      false => Err(format!("unknown option \"{}\"", key)),
     }
   }
 
@@ -405,65 +1036,268 @@ impl Config {
   }
 }
 
-fn parse_color(s: &str) -> Color {
+fn parse_color(s: &str) -> Result<Color, String> {
   match s {
-    "black" => nc::COLOR_BLACK,
-    "red" => nc::COLOR_RED,
-    "green" => nc::COLOR_GREEN,
-    "yellow" => nc::COLOR_YELLOW,
-    "blue" => nc::COLOR_BLUE,
-    "magenta" => nc::COLOR_MAGENTA,
-    "cyan" => nc::COLOR_CYAN,
-    "white" => nc::COLOR_WHITE,
-    _ => -1,
+    "default" => Ok(COLOR_DEFAULT),
+    "black" => Ok(nc::COLOR_BLACK),
+    "red" => Ok(nc::COLOR_RED),
+    "green" => Ok(nc::COLOR_GREEN),
+    "yellow" => Ok(nc::COLOR_YELLOW),
+    "blue" => Ok(nc::COLOR_BLUE),
+    "magenta" => Ok(nc::COLOR_MAGENTA),
+    "cyan" => Ok(nc::COLOR_CYAN),
+    "white" => Ok(nc::COLOR_WHITE),
+    // 256 colors, in [1, 256]
+    _ => match s.parse::<Color>() {
+      Ok(c) if c >= 1 && c <= 256 => Ok(c - 1),
+      _ => Err(format!("invalid color \"{}\"", s)),
+    },
   }
 }
 
-fn parse_bool(s: &str) -> bool {
-  s == "yes"
+fn parse_bool(s: &str) -> Result<bool, String> {
+  match s {
+    "yes" => Ok(true),
+    "no" => Ok(false),
+    _ => Err(format!("invalid boolean \"{}\" (expected \"yes\" or \"no\")", s)),
+  }
 }
 
-fn parse_int<T>(s: &str) -> T
+fn parse_int<T>(s: &str) -> Result<T, String>
 where
   T: FromStr,
-  <T as FromStr>::Err: Debug,
 {
-  let res = s.parse::<T>();
-  if res.is_err() {
-    panic!(format!("Error while parsing \"{}\" as an integer", s));
+  s.parse::<T>().map_err(|_| format!("invalid number \"{}\"", s))
+}
+
+/// Parse a number greater than 0.
+fn parse_positive_int<T>(s: &str) -> Result<T, String>
+where
+  T: FromStr + PartialOrd + Default,
+{
+  match s.parse::<T>() {
+    Ok(n) if n > T::default() => Ok(n),
+    _ => Err(format!("invalid number \"{}\" (expected a positive number)", s)),
+  }
+}
+
+fn parse_song_format(s: &str) -> Result<SongFormat, String> {
+  match SongFormat::parse(s) {
+    Ok(f) => Ok(f),
+    Err(ParserError::Format(p)) => Err(format!("invalid song format \"{}\" (at character {})", s, p)),
+    Err(_) => Err(format!("invalid song format \"{}\"", s)),
+  }
+}
+
+fn parse_user_interface(s: &str) -> Result<UserInterface, String> {
+  UserInterface::from_name(s)
+    .ok_or_else(|| format!("invalid user interface \"{}\" (expected \"classic\" or \"alternative\")", s))
+}
+
+/// Parse the width of the locked screen, in [20, 80] (%).
+fn parse_locked_screen_width_part(s: &str) -> Result<i32, String> {
+  match s.parse::<i32>() {
+    Ok(part) if part >= 20 && part <= 80 => Ok(part),
+    _ => Err(format!("invalid locked screen width \"{}\" (expected 20-80)", s)),
+  }
+}
+
+fn parse_screen(s: &str) -> Result<ActiveWindow, String> {
+  ActiveWindow::from_name(s).ok_or_else(|| format!("unsupported screen \"{}\"", s))
+}
+
+fn parse_find_mode(s: &str) -> Result<FindMode, String> {
+  FindMode::from_name(s).ok_or_else(|| format!("invalid find mode \"{}\" (expected \"wrapped\" or \"normal\")", s))
+}
+
+/// Parse ncmpcpp's regular expression types. They all use the same syntax here.
+fn parse_regular_expressions(s: &str) -> Result<bool, String> {
+  match s {
+    "none" | "no" => Ok(false),
+    "basic" | "extended" | "perl" | "yes" => Ok(true),
+    _ => Err(format!("invalid regular expression type \"{}\"", s)),
+  }
+}
+
+fn parse_progressbar_look(s: &str) -> Result<String, String> {
+  let n = s.chars().count();
+  if n == 2 || n == 3 {
+    Ok(String::from(s))
+  } else {
+    Err(format!("invalid progressbar look \"{}\" (expected 2 or 3 characters)", s))
   }
-  res.unwrap()
 }
 
-fn assign(key: &str, val: &str, config: &mut Config) -> bool {
+/// Assign a color. Returns false if the key is unknown, and an error if the
+/// value is invalid.
+fn assign_color(key: &str, val: &str, colors: &mut ColorConfig) -> Result<bool, String> {
+  match key {
+    "color1" => colors.color1 = parse_color(val)?,
+    "color2" => colors.color2 = parse_color(val)?,
+    "active_column_color" => colors.active_column = parse_color(val)?,
+    "active_window_border" => colors.active_window_border = parse_color(val)?,
+    "alternative_ui_separator_color" => colors.alternative_ui_separator = parse_color(val)?,
+    "empty_tag_color" => colors.empty_tag = parse_color(val)?,
+    "header_window_color" => colors.header_window = parse_color(val)?,
+    "progressbar_color" => colors.progressbar = parse_color(val)?,
+    "progressbar_elapsed_color" => colors.progressbar_elapsed = parse_color(val)?,
+    "main_window_color" => colors.main_window = parse_color(val)?,
+    "main_window_highlight_color" => colors.main_window_highlight = parse_color(val)?,
+    "state_flags_color" => colors.state_flags = parse_color(val)?,
+    "state_line_color" => colors.state_line = parse_color(val)?,
+    "statusbar_color" => colors.statusbar = parse_color(val)?,
+    "volume_color" => colors.volume = parse_color(val)?,
+    "window_border_color" => colors.window_border = parse_color(val)?,
+    _ => return Ok(false),
+  }
+  return Ok(true);
+}
+
+/// Assign a configuration value. Returns false if the key is unknown, and an
+/// error if the value is invalid.
+fn assign(key: &str, val: &str, config: &mut Config) -> Result<bool, String> {
+  // Colors
+  if assign_color(key, val, &mut config.colors)? {
+    return Ok(true);
+  }
+
   match key {
-    // Colors
-    "color1" => config.colors.color1 = parse_color(val),
-    "color2" => config.colors.color2 = parse_color(val),
-    "header_window_color" => config.colors.header_window = parse_color(val),
-    "progressbar_color" => config.colors.progressbar = parse_color(val),
-    "progressbar_elapsed_color" => config.colors.progressbar_elapsed = parse_color(val),
-    "main_window_color" => config.colors.main_window = parse_color(val),
-    "main_window_highlight_color" => config.colors.main_window_highlight = parse_color(val),
-    "state_flags_color" => config.colors.state_flags = parse_color(val),
-    "state_line_color" => config.colors.state_line = parse_color(val),
-    "statusbar_color" => config.colors.statusbar = parse_color(val),
-    "volume_color" => config.colors.volume = parse_color(val),
-    "window_border_color" => config.colors.window_border = parse_color(val),
     // Parameters
-    "cyclic_scrolling" => config.params.cyclic_scrolling = parse_bool(val),
-    "display_bitrate" => config.params.display_bitrate = parse_bool(val),
-    "display_remaining_time" => config.params.display_remaining_time = parse_bool(val),
-    "display_volume_level" => config.params.display_volume_level = parse_bool(val),
-    "header_text_scrolling" => config.params.header_text_scrolling = parse_bool(val),
+    "alternative_header_first_line_format" => {
+      config.params.alternative_header_first_line_format = parse_song_format(val)?
+    }
+    "alternative_header_second_line_format" => {
+      config.params.alternative_header_second_line_format = parse_song_format(val)?
+    }
+    "ask_for_locked_screen_width_part" => config.params.ask_for_locked_screen_width_part = parse_bool(val)?,
+    "autocenter_mode" => config.params.autocenter_mode = parse_bool(val)?,
+    "centered_cursor" => config.params.centered_cursor = parse_bool(val)?,
+    "cyclic_scrolling" => config.params.cyclic_scrolling = parse_bool(val)?,
+    "default_find_mode" => config.params.default_find_mode = parse_find_mode(val)?,
+    "display_bitrate" => config.params.display_bitrate = parse_bool(val)?,
+    "display_remaining_time" => config.params.display_remaining_time = parse_bool(val)?,
+    "display_volume_level" => config.params.display_volume_level = parse_bool(val)?,
+    "empty_tag_marker" => config.params.empty_tag_marker = String::from(val),
+    "enable_window_title" => config.params.enable_window_title = parse_bool(val)?,
+    "header_text_scrolling" => config.params.header_text_scrolling = parse_bool(val)?,
+    "header_visibility" => config.params.header_visibility = parse_bool(val)?,
+    "ignore_leading_the" => config.params.ignore_leading_the = parse_bool(val)?,
+    "jump_to_now_playing_song_at_start" => config.params.jump_to_now_playing_song_at_start = parse_bool(val)?,
+    "key_sequence_timeout" => config.params.key_sequence_timeout = parse_positive_int(val)?,
+    "lines_scrolled" => config.params.lines_scrolled = parse_positive_int(val)?,
+    "locked_screen_width_part" => config.params.locked_screen_width_part = parse_locked_screen_width_part(val)?,
+    "mouse_list_scroll_whole_page" => config.params.mouse_list_scroll_whole_page = parse_bool(val)?,
+    "mouse_support" => config.params.mouse_support = parse_bool(val)?,
     "mpd_host" => config.params.mpd_host = String::from(val),
-    "mpd_port" => config.params.mpd_port = parse_int(val),
-    // TODO: add check (size 2 or 3)
-    "progressbar_look" => config.params.progressbar_look = String::from(val),
-    "volume_change_step" => config.params.volume_change_step = parse_int(val),
+    "mpd_port" => config.params.mpd_port = parse_int(val)?,
+    "playlist_shorten_total_times" => config.params.playlist_shorten_total_times = parse_bool(val)?,
+    "playlist_show_remaining_time" => config.params.playlist_show_remaining_time = parse_bool(val)?,
+    "progressbar_look" => config.params.progressbar_look = parse_progressbar_look(val)?,
+    "regular_expressions" => config.params.regular_expressions = parse_regular_expressions(val)?,
+    "screen_switcher_mode" => config.params.screen_switcher_mode = ScreenSwitcherMode::from_name(val),
+    "song_window_title_format" => config.params.song_window_title_format = parse_song_format(val)?,
+    "startup_screen" => config.params.startup_screen = parse_screen(val)?,
+    "startup_slave_screen" if val.is_empty() => config.params.startup_slave_screen = None,
+    "startup_slave_screen" => config.params.startup_slave_screen = Some(parse_screen(val)?),
+    "startup_slave_screen_focus" => config.params.startup_slave_screen_focus = parse_bool(val)?,
+    "statusbar_visibility" => config.params.statusbar_visibility = parse_bool(val)?,
+    "theme" => config.params.theme = String::from(val),
+    "titles_visibility" => config.params.titles_visibility = parse_bool(val)?,
+    "user_interface" => config.params.user_interface = parse_user_interface(val)?,
+    "volume_change_step" => config.params.volume_change_step = parse_positive_int(val)?,
     // Formats
-    "song_columns_list_format" => config.params.song_columns_list_format = generate_columns(val).unwrap_or_default(),
-    _ => return false,
+    "song_columns_list_format" => {
+      config.params.song_columns_list_format =
+        generate_columns(val).map_err(|_| format!("invalid columns format \"{}\"", val))?
+    }
+    _ => return Ok(false),
+  }
+  return Ok(true);
+}
+
+/// Options of the configuration file that are not colors.
+pub const PARAM_KEYS: &[&str] = &[
+  "alternative_header_first_line_format",
+  "alternative_header_second_line_format",
+  "ask_for_locked_screen_width_part",
+  "autocenter_mode",
+  "centered_cursor",
+  "cyclic_scrolling",
+  "default_find_mode",
+  "display_bitrate",
+  "display_remaining_time",
+  "display_volume_level",
+  "empty_tag_marker",
+  "enable_window_title",
+  "header_text_scrolling",
+  "header_visibility",
+  "ignore_leading_the",
+  "jump_to_now_playing_song_at_start",
+  "key_sequence_timeout",
+  "lines_scrolled",
+  "locked_screen_width_part",
+  "mouse_list_scroll_whole_page",
+  "mouse_support",
+  "mpd_host",
+  "mpd_port",
+  "playlist_shorten_total_times",
+  "playlist_show_remaining_time",
+  "progressbar_look",
+  "regular_expressions",
+  "screen_switcher_mode",
+  "song_columns_list_format",
+  "song_window_title_format",
+  "startup_screen",
+  "startup_slave_screen",
+  "startup_slave_screen_focus",
+  "statusbar_visibility",
+  "theme",
+  "titles_visibility",
+  "user_interface",
+  "volume_change_step",
+];
+
+/// Read an ncmpcpp configuration file, and call `f` for each (key, value)
+/// pair with its line number (starting from 1). `f` returns false for unknown
+/// keys, and an error for invalid values.
+fn read_config_file<F>(path: &PathBuf, diagnostics: &mut Vec<Diagnostic>, mut f: F) -> bool
+where
+  F: FnMut(&str, &str, u32) -> Result<bool, String>,
+{
+  let content = match fs::read_to_string(path) {
+    Ok(c) => c,
+    Err(e) => {
+      diagnostics.push(Diagnostic::error(path, None, "", &format!("cannot read file: {}", e)));
+      return false;
+    }
+  };
+
+  for (i, text) in content.lines().enumerate() {
+    let line = 1 + i as u32;
+    let text = text.trim();
+    if text.is_empty() || text.starts_with('#') {
+      continue;
+    }
+    let (k, v) = match text.find('=') {
+      Some(pos) if pos > 0 => (text[..pos].trim_end(), text[pos + 1..].trim_start()),
+      _ => {
+        diagnostics.push(Diagnostic::error(path, Some(line), "", "expected \"key = value\""));
+        continue;
+      }
+    };
+    // Remove quotes
+    let fixed = v.trim_matches('\"');
+    match f(k, fixed, line) {
+      Ok(true) => {}
+      Ok(false) => {
+        // The option may come from another version of ncmpcpp, so the rest
+        // of the configuration can still be used
+        if !NCMPCPP_OPTIONS.contains(&k) {
+          diagnostics.push(Diagnostic::warning(path, Some(line), k, "unknown option"));
+        }
+      }
+      Err(msg) => diagnostics.push(Diagnostic::error(path, Some(line), k, &msg)),
+    }
   }
   return true;
 }
@@ -478,10 +1312,66 @@ impl ConfigLoader {
     ConfigLoader {
       default_config_path: default_config_path,
       default_bindings_path: default_bindings_path,
+      xdg_dirs: Some(xdg_dirs),
+    }
+  }
+
+  /// Find a theme file, given either its name (looked up in the "themes"
+  /// configuration directory) or its path.
+  fn find_theme(&self, name: &str) -> Option<PathBuf> {
+    let path = PathBuf::from(name);
+    if path.is_file() {
+      return Some(path);
+    }
+    return match self.xdg_dirs {
+      Some(ref dirs) => dirs.find_config_file(PathBuf::from("themes").join(name)),
+      None => None,
+    };
+  }
+
+  /// Names of the themes found in the "themes" configuration directory.
+  pub fn list_themes(&self) -> Vec<String> {
+    let mut themes = match self.xdg_dirs {
+      Some(ref dirs) => dirs
+        .list_config_files("themes")
+        .iter()
+        .filter_map(|p| p.file_name().and_then(|n| n.to_str()).map(String::from))
+        .collect::<Vec<String>>(),
+      None => vec![],
+    };
+    themes.sort();
+    themes.dedup();
+    return themes;
+  }
+
+  fn read_theme(&self, name: &str, base: &ColorConfig, diagnostics: &mut Vec<Diagnostic>) -> Option<ColorConfig> {
+    let path = self.find_theme(name)?;
+
+    let mut colors = *base;
+    if read_config_file(&path, diagnostics, |k, v, _| assign_color(k, v, &mut colors)) {
+      Some(colors)
+    } else {
+      None
     }
   }
 
+  /// Load a color theme on top of the given colors, which are kept when the
+  /// theme file does not set them.
+  pub fn load_theme(&self, name: &str, base: &ColorConfig) -> Option<ColorConfig> {
+    self.read_theme(name, base, &mut vec![])
+  }
+
+  /// Load the configuration, and print the diagnostics to stderr.
   pub fn load(&self, user_config: &Option<PathBuf>, bindings: &Option<PathBuf>) -> Config {
+    let (config, diagnostics) = self.load_with_diagnostics(user_config, bindings);
+    for d in &diagnostics {
+      stderr!("{}", d);
+    }
+    return config;
+  }
+
+  /// Load the configuration, and return the problems found in the process.
+  pub fn load_with_diagnostics(&self, user_config: &Option<PathBuf>, bindings: &Option<PathBuf>) -> (Config, Vec<Diagnostic>) {
     let opt_config = if user_config.is_some() {
       user_config.clone()
     } else {
@@ -494,46 +1384,207 @@ impl ConfigLoader {
     };
 
     let mut config = Config::new();
+    let mut diagnostics = Vec::<Diagnostic>::new();
+    config.files.config = opt_config.clone();
+    config.files.bindings = opt_bindings.clone();
 
     // Read ncmpcpp configuration (.ini file)
-    if opt_config.is_some() {
-      let path = opt_config.unwrap();
-      let file = path.to_str().unwrap();
-      let ini = Ini::load_from_file(file).unwrap();
-      for (_, prop) in ini.iter() {
-        for (k, v) in prop.iter() {
-          // Remove quotes
-          let fixed = v.trim_matches('\"');
-          assign(&k, fixed, &mut config);
+    let mut theme_line = None;
+    if let Some(path) = opt_config {
+      read_config_file(&path, &mut diagnostics, |k, v, line| {
+        if k == "theme" {
+          theme_line = Some(line);
+        }
+        assign(k, v, &mut config)
+      });
+    }
+
+    // Colors from the theme file take precedence over the main configuration
+    config.base_colors = config.colors;
+    if !config.params.theme.is_empty() {
+      match self.read_theme(&config.params.theme, &config.base_colors, &mut diagnostics) {
+        Some(colors) => {
+          config.colors = colors;
+          config.files.theme = self.find_theme(&config.params.theme);
+        }
+        None => {
+          let path = config.files.config.clone().unwrap_or_default();
+          let msg = format!("cannot load theme \"{}\"", config.params.theme);
+          diagnostics.push(Diagnostic::error(&path, theme_line, "theme", &msg));
         }
       }
     }
 
     // Read ncmpcpp bindings
-    if opt_bindings.is_some() {
-      let path = opt_bindings.unwrap();
-      let res = parse_bindings_configuration(&PathBuf::from(path.to_str().unwrap()));
-      if res.is_ok() {
-        for val in res.unwrap() {
-          let key = to_keycode(val.0.as_str());
-          // If key is associated with a single action
-          if val.1.len() == 1 {
-            // Store it directly with the action
-            config.keys.assign(
-              val.1[0].as_str(),
-              ControlKey::KeyCode(key),
-            );
-          } else {
-            // Else store it in custom actions
-            config.keys.custom.insert(key, val.1);
+    if let Some(path) = opt_bindings {
+      match parse_bindings_file(&path) {
+        Ok(bindings) => {
+          let action_map = get_action_map();
+          for mut b in bindings {
+            let line = Some(b.line);
+            let name = match b.target {
+              BindingTarget::Key(ref k) | BindingTarget::Command(ref k, _) => k.clone(),
+            };
+
+            // Use the names of ncmpc's actions
+            for action in b.actions.iter_mut() {
+              if action.name == "require_runnable" {
+                action.args = action.args.iter().map(|a| String::from(translate_action(a))).collect();
+              }
+              action.name = String::from(translate_action(&action.name));
+            }
+
+            // Single actions may use the names of the key configuration
+            let mut is_supported = |a: &ActionDef| {
+              action_map.contains_key(a.name.as_str())
+                || (b.actions.len() == 1 && config.keys.action_to_keys(a.name.as_str()).is_some())
+            };
+            // Bindings are rejected as a whole, so that a chain never runs
+            // without one of its actions (e.g. a guard)
+            if let Some(action) = b.actions.iter().find(|a| !is_supported(a)) {
+              if NCMPCPP_ACTION_NAMES.contains(&action.name.as_str()) {
+                let msg = format!("unsupported action \"{}\"", action.name);
+                diagnostics.push(Diagnostic::warning(&path, line, &name, &msg));
+              } else {
+                let msg = format!("unknown action \"{}\"", action.name);
+                diagnostics.push(Diagnostic::error(&path, line, &name, &msg));
+              }
+              continue;
+            }
+
+            match b.target {
+              BindingTarget::Command(_, ref mode) => {
+                if mode != "deferred" && mode != "immediate" {
+                  let msg = format!("invalid command mode \"{}\"", mode);
+                  diagnostics.push(Diagnostic::error(&path, line, &name, &msg));
+                  continue;
+                }
+                let command = UserCommand {
+                  immediate: mode == "immediate",
+                  actions: b.actions,
+                };
+                config.keys.commands.insert(name, command);
+              }
+              BindingTarget::Key(_) => {
+                let keys = match to_key_sequence(name.as_str()) {
+                  Some(keys) => keys,
+                  None => {
+                    diagnostics.push(Diagnostic::error(&path, line, &name, "unknown key"));
+                    continue;
+                  }
+                };
+                if let Some(ref screen) = b.screen {
+                  if ActiveWindow::from_name(screen).is_none() {
+                    let msg = format!("unknown screen \"{}\"", screen);
+                    diagnostics.push(Diagnostic::error(&path, line, &name, &msg));
+                    continue;
+                  }
+                }
+
+                // If a single key is associated with a single action without arguments
+                let single = keys.len() == 1 && b.screen.is_none() && b.actions.len() == 1 && b.actions[0].args.is_empty();
+                if single && config.keys.action_to_keys(b.actions[0].name.as_str()).is_some() {
+                  // Store it directly with the action
+                  config.keys.assign(b.actions[0].name.as_str(), ControlKey::KeyCode(keys[0]));
+                } else {
+                  // Else store it in custom actions
+                  config.keys.custom.entry((b.screen, keys)).or_insert_with(Vec::new).push(b.actions);
+                }
+              }
+            }
           }
         }
-      } else {
-        stderr!("[Error] failed to parse {}", path.to_str().unwrap());
+        Err(ParserError::Io(e)) => {
+          diagnostics.push(Diagnostic::error(&path, None, "", &format!("cannot read file: {}", e)));
+        }
+        Err(ParserError::Parse(line)) if line > 0 => {
+          diagnostics.push(Diagnostic::error(&path, Some(line), "", "syntax error"));
+        }
+        Err(_) => diagnostics.push(Diagnostic::error(&path, None, "", "syntax error")),
       }
     }
 
-    return config;
+    return (config, diagnostics);
+  }
+}
+
+impl Diagnostic {
+  pub fn new(severity: Severity, file: &PathBuf, line: Option<u32>, key: &str, message: &str) -> Diagnostic {
+    Diagnostic {
+      file: file.clone(),
+      line: line,
+      key: String::from(key),
+      message: String::from(message),
+      severity: severity,
+    }
+  }
+
+  pub fn error(file: &PathBuf, line: Option<u32>, key: &str, message: &str) -> Diagnostic {
+    Diagnostic::new(Severity::Error, file, line, key, message)
+  }
+
+  pub fn warning(file: &PathBuf, line: Option<u32>, key: &str, message: &str) -> Diagnostic {
+    Diagnostic::new(Severity::Warning, file, line, key, message)
+  }
+
+  pub fn is_error(&self) -> bool {
+    self.severity == Severity::Error
+  }
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.file.display())?;
+    if let Some(line) = self.line {
+      write!(f, ":{}", line)?;
+    }
+    let severity = match self.severity {
+      Severity::Warning => "warning",
+      Severity::Error => "error",
+    };
+    write!(f, ": {}: ", severity)?;
+    if !self.key.is_empty() {
+      write!(f, "{}: ", self.key)?;
+    }
+    write!(f, "{}", self.message)
+  }
+}
+
+fn modification_time(path: &PathBuf) -> Option<SystemTime> {
+  fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl ConfigWatcher {
+  pub fn new(files: &ConfigFiles, dt: Duration) -> ConfigWatcher {
+    let paths = [&files.config, &files.bindings, &files.theme];
+    ConfigWatcher {
+      files: paths
+        .iter()
+        .filter_map(|p| p.as_ref())
+        .map(|p| (p.clone(), modification_time(p)))
+        .collect(),
+      check_time: get_time(),
+      dt: dt,
+    }
+  }
+
+  /// Whether one of the watched files changed since the last call.
+  pub fn has_changed(&mut self) -> bool {
+    let ts = get_time();
+    if ts < self.check_time + self.dt {
+      return false;
+    }
+    self.check_time = ts;
+
+    let mut changed = false;
+    for &mut (ref path, ref mut mtime) in &mut self.files {
+      let new_mtime = modification_time(path);
+      if new_mtime != *mtime {
+        *mtime = new_mtime;
+        changed = true;
+      }
+    }
+    return changed;
   }
 }
 
@@ -568,5 +1619,184 @@ fn test_keycode() {
   assert_eq!(to_keycode("f5"), nc::KEY_F5);
   assert_eq!(to_keycode("f10"), nc::KEY_F10);
 
+  assert_eq!(to_keycode("enter"), '\n' as i32);
+  assert_eq!(to_keycode("mouse"), nc::KEY_MOUSE);
+  assert_eq!(to_keycode("shift-a"), 'A' as i32);
+  assert_eq!(to_keycode("shift-up"), nc::KEY_SR);
+  assert_eq!(to_keycode("shift-down"), nc::KEY_SF);
+  assert_eq!(to_keycode("shift-tab"), nc::KEY_BTAB);
+  assert_eq!(to_keycode("shift-f1"), nc::KEY_F0 + 13);
+  assert_eq!(to_keycode("ctrl-f2"), nc::KEY_F0 + 26);
+  assert_eq!(to_keycode("alt-x"), KEY_ALT + 'x' as i32);
+  assert_eq!(to_keycode("alt-ctrl-x"), KEY_ALT + 24);
+  assert_eq!(to_keycode("ctrl-up"), KEY_MODIFIED + 5);
+  assert_eq!(to_keycode("ctrl-shift-left"), KEY_MODIFIED + 16 * 2 + 6);
+  assert_eq!(to_keycode("ctrl-enter"), -1);
+  assert_eq!(to_keycode("unknown"), -1);
+
+  assert_eq!(to_key_sequence(" "), Some(vec![' ' as i32]));
+  assert_eq!(to_key_sequence("g g"), Some(vec!['g' as i32, 'g' as i32]));
+  assert_eq!(to_key_sequence("ctrl-x ctrl-s"), Some(vec![24, 19]));
+  assert_eq!(to_key_sequence("g unknown"), None);
+
   assert_eq!(from_keycode(nc::KEY_F9), String::from("F9"));
+  assert_eq!(from_keycode(nc::KEY_BTAB), String::from("Shift-Tab"));
+  assert_eq!(from_keycode(to_keycode("alt-x")), String::from("Alt-x"));
+  assert_eq!(from_keycode(to_keycode("ctrl-up")), String::from("Ctrl-Up"));
+  assert_eq!(from_keycode(to_keycode("ctrl-s")), String::from("Ctrl-s"));
+}
+
+#[test]
+fn test_config_watcher() {
+  use std::fs::File;
+  use std::io::Write;
+
+  let path = env::temp_dir().join(format!("ncmpc_watcher_{}", std::process::id()));
+  let mut file = File::create(&path).unwrap();
+  file.set_modified(SystemTime::UNIX_EPOCH).unwrap();
+
+  let files = ConfigFiles {
+    config: Some(path.clone()),
+    bindings: None,
+    theme: None,
+  };
+  let mut watcher = ConfigWatcher::new(&files, Duration::zero());
+  assert!(!watcher.has_changed());
+
+  writeln!(file, "cyclic_scrolling = yes").unwrap();
+  file.set_modified(SystemTime::now()).unwrap();
+  assert!(watcher.has_changed());
+  assert!(!watcher.has_changed());
+
+  fs::remove_file(&path).unwrap();
+  assert!(watcher.has_changed());
+}
+
+#[test]
+fn test_find_options() {
+  let mut config = Config::new();
+  assert_eq!(config.set("default_find_mode", "normal"), Ok(()));
+  assert_eq!(config.params.default_find_mode, FindMode::Normal);
+  assert_eq!(config.set("regular_expressions", "none"), Ok(()));
+  assert!(!config.params.regular_expressions);
+  assert_eq!(config.set("regular_expressions", "extended"), Ok(()));
+  assert!(config.params.regular_expressions);
+  assert_eq!(config.set("ignore_leading_the", "yes"), Ok(()));
+  assert!(config.params.ignore_leading_the);
+}
+
+#[test]
+fn test_autocenter_options() {
+  let mut config = Config::new();
+  assert_eq!(config.set("autocenter_mode", "yes"), Ok(()));
+  assert!(config.params.autocenter_mode);
+  assert_eq!(config.set("centered_cursor", "yes"), Ok(()));
+  assert!(config.params.centered_cursor);
+}
+
+#[test]
+fn test_mouse_options() {
+  let mut config = Config::new();
+  assert_eq!(config.set("lines_scrolled", "5"), Ok(()));
+  assert_eq!(config.params.lines_scrolled, 5);
+  assert_eq!(config.set("mouse_support", "no"), Ok(()));
+  assert!(!config.params.mouse_support);
+  assert_eq!(config.set("mouse_list_scroll_whole_page", "no"), Ok(()));
+  assert!(!config.params.mouse_list_scroll_whole_page);
+}
+
+#[test]
+fn test_visibility_options() {
+  let mut config = Config::new();
+  assert_eq!(config.set("header_visibility", "no"), Ok(()));
+  assert!(!config.params.header_visibility);
+  assert_eq!(config.set("statusbar_visibility", "no"), Ok(()));
+  assert!(!config.params.statusbar_visibility);
+  assert_eq!(config.set("titles_visibility", "no"), Ok(()));
+  assert!(!config.params.titles_visibility);
+}
+
+#[test]
+fn test_user_interface_option() {
+  let mut config = Config::new();
+  assert_eq!(config.set("user_interface", "alternative"), Ok(()));
+  assert_eq!(config.params.user_interface, UserInterface::Alternative);
+  assert!(config.set("user_interface", "modern").is_err());
+}
+
+#[test]
+fn test_locked_screen_options() {
+  let mut config = Config::new();
+  assert_eq!(config.set("startup_slave_screen", "help"), Ok(()));
+  assert_eq!(config.params.startup_slave_screen, Some(ActiveWindow::Help));
+  assert_eq!(config.set("startup_slave_screen_focus", "yes"), Ok(()));
+  assert!(config.params.startup_slave_screen_focus);
+  assert_eq!(config.set("locked_screen_width_part", "30"), Ok(()));
+  assert_eq!(config.params.locked_screen_width_part, 30);
+  assert_eq!(config.set("ask_for_locked_screen_width_part", "no"), Ok(()));
+  assert!(!config.params.ask_for_locked_screen_width_part);
+}
+
+#[test]
+fn test_screen_options() {
+  let mut config = Config::new();
+  assert_eq!(config.set("screen_switcher_mode", "previous"), Ok(()));
+  assert_eq!(config.params.screen_switcher_mode, ScreenSwitcherMode::Previous);
+  assert_eq!(config.set("startup_screen", "server_info"), Ok(()));
+  assert_eq!(config.params.startup_screen, ActiveWindow::ServerInfo);
+}
+
+#[test]
+fn test_playlist_time_options() {
+  let mut config = Config::new();
+  assert_eq!(config.set("playlist_show_remaining_time", "yes"), Ok(()));
+  assert!(config.params.playlist_show_remaining_time);
+  assert_eq!(config.set("playlist_shorten_total_times", "yes"), Ok(()));
+  assert!(config.params.playlist_shorten_total_times);
+}
+
+#[test]
+fn test_window_title_options() {
+  let mut config = Config::new();
+  assert_eq!(config.set("enable_window_title", "no"), Ok(()));
+  assert!(!config.params.enable_window_title);
+  assert_eq!(config.set("song_window_title_format", "{%t}|{%f}"), Ok(()));
+  assert_eq!(config.params.song_window_title_format, SongFormat::parse("{%t}|{%f}").unwrap());
+}
+
+#[test]
+fn test_param_keys() {
+  // Every parameter can be set
+  let mut config = Config::new();
+  for key in PARAM_KEYS {
+    assert!(assign(key, "?", &mut config) != Ok(false), "{}", key);
+  }
+  // And every option of ncmpcpp that is supported is a parameter or a color
+  for key in NCMPCPP_OPTIONS {
+    let is_color = assign_color(key, "?", &mut config.colors) != Ok(false);
+    let is_param = assign(key, "?", &mut config) != Ok(false);
+    assert_eq!(is_param && !is_color, PARAM_KEYS.contains(key), "{}", key);
+  }
+}
+
+#[test]
+fn test_translate_action() {
+  use crate::action::build_chain;
+
+  // Every translated action is an action of ncmpc
+  let action_map = get_action_map();
+  let mut keys = KeyConfig::new();
+  for &(ncmpcpp, ncmpc) in NCMPCPP_ACTIONS {
+    assert!(NCMPCPP_ACTION_NAMES.contains(&ncmpcpp), "{}", ncmpcpp);
+    assert!(action_map.contains_key(ncmpc) || keys.action_to_keys(ncmpc).is_some(), "{}", ncmpc);
+  }
+
+  // Single actions and chains use the same names
+  let names = ["press_enter", "pause", "toggle_random", "clear_playlist"];
+  for name in names.iter() {
+    assert!(keys.action_to_keys(translate_action(name)).is_some(), "{}", name);
+  }
+  let defs: Vec<ActionDef> = names.iter().map(|n| ActionDef::new(translate_action(n))).collect();
+  assert!(build_chain(&action_map, &defs).is_some());
+  assert_eq!(translate_action("reload_config"), "reload_config");
 }
@@ -33,11 +33,26 @@ pub const COLOR_PAIR_STATE_LINE: Color = 216;
 pub const COLOR_PAIR_STATE_FLAGS: Color = 217;
 pub const COLOR_PAIR_TRACK: Color = 218;
 pub const COLOR_PAIR_WINDOW_BORDER: Color = 219;
+pub const COLOR_PAIR_MAIN_WINDOW: Color = 220;
+pub const COLOR_PAIR_MAIN_WINDOW_HIGHLIGHT: Color = 221;
+pub const COLOR_PAIR_EMPTY_TAG: Color = 222;
+pub const COLOR_PAIR_ACTIVE_COLUMN: Color = 223;
+pub const COLOR_PAIR_ALTERNATIVE_UI_SEPARATOR: Color = 224;
+pub const COLOR_PAIR_COLOR1: Color = 225;
+pub const COLOR_PAIR_COLOR2: Color = 226;
+pub const COLOR_PAIR_ACTIVE_WINDOW_BORDER: Color = 227;
 
 pub const COLOR_PAIR_COLUMNS: [Color; MAX_NUM_COLUMNS] = [30, 31, 32, 33, 34, 35, 36, 37, 38, 39];
 
 // ctrl-?
 pub const KEY_CTRL_A: i32 = 1;
+pub const KEY_CTRL_E: i32 = 5;
+pub const KEY_CTRL_F: i32 = 6;
+pub const KEY_CTRL_H: i32 = 8;
+pub const KEY_CTRL_K: i32 = 11;
+pub const KEY_CTRL_L: i32 = 12;
+pub const KEY_CTRL_U: i32 = 21;
+pub const KEY_CTRL_W: i32 = 23;
 pub const KEY_CTRL_LEFTBRACKET: i32 = 27;
 pub const KEY_CTRL_BACKSLASH: i32 = 28;
 pub const KEY_CTRL_RIGHTBRACKET: i32 = 29;
@@ -46,3 +61,10 @@ pub const KEY_CTRL_UNDERSCORE: i32 = 31;
 pub const KEY_ESCAPE: i32 = 27;
 pub const KEY_TAB: i32 = 9;
 pub const KEY_BACKSPACE: i32 = 127;
+
+// Keys with modifiers, which do not have a single ncurses keycode
+/// Alt + keycode.
+pub const KEY_ALT: i32 = 0x10000;
+/// Special key with modifiers (e.g. ctrl-up), encoded as
+/// `KEY_MODIFIED + 16 * index of the key + xterm modifier number`.
+pub const KEY_MODIFIED: i32 = 0x20000;
@@ -1,11 +1,19 @@
 extern crate ncurses;
+extern crate signal_hook;
 
-use crate::action::Action;
+use crate::action::{build_chain, execute_chain_with_count, Action, ActionChain};
 use crate::config::*;
+use crate::constants::{KEY_ALT, KEY_ESCAPE};
+use crate::keymap::{KeyLookup, Keymap};
 use crate::model::*;
 use ncurses as nc;
 
-use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use time::{get_time, Duration, Timespec};
+
+/// Maximum count that can be typed before an action.
+const MAX_COUNT: u32 = 9999;
 
 pub enum ControlQuery {
   /// Some query was made.
@@ -16,12 +24,26 @@ pub enum ControlQuery {
   Exit,
 }
 
-pub type ControllerCallbacks<'m> = HashMap<i32, Vec<Action<'m>>>;
+/// Chains of actions bound to each key sequence, tried in order until one of
+/// them can be run.
+pub type ControllerCallbacks<'m> = Keymap<Vec<ActionChain<'m>>>;
 
 pub struct Controller<'c, 'm: 'c> {
   model: &'c mut SharedModel<'m>,
   callbacks: ControllerCallbacks<'m>,
   quit_keycodes: Vec<i32>,
+  /// Keys typed so far, when they are the start of a key sequence.
+  pending_keys: Vec<i32>,
+  /// Count typed before an action (e.g. 5 in "5j"), 0 if none.
+  count: u32,
+  /// Time of the last pending key.
+  pending_time: Timespec,
+  /// Files the configuration was loaded from.
+  config_files: ConfigFiles,
+  /// Watcher used to reload the configuration when its files change.
+  config_watcher: ConfigWatcher,
+  /// Set when SIGHUP is received.
+  sighup: Arc<AtomicBool>,
 }
 
 macro_rules! register_callback {
@@ -30,126 +52,306 @@ macro_rules! register_callback {
     let name: &str = stringify!($action);
     let desc: &str = ACTION_DESCRIPTION.get(&name).unwrap_or(&"Missing description");
     for key in &$config.keys.$action {
-      $callbacks.insert(key.keycode(), vec![Action::new(name, desc, $callback)]);
+      $callbacks.insert(None, &[key.keycode()], vec![vec![Action::new(name, desc, $callback)]]);
     }
   }};
   // For special keycodes
   ($callbacks: ident, $key:expr, $callback: ident) => {{
     let name: &str = stringify!($action);
     let desc: &str = ACTION_DESCRIPTION.get(&name).unwrap_or(&"Missing description");
-    $callbacks.insert($key, vec![Action::new(name, desc, $callback)]);
+    $callbacks.insert(None, &[$key], vec![vec![Action::new(name, desc, $callback)]]);
   }};
   // For custom actions
-  ($callbacks: ident, $map: ident, $key:expr, actions => $actions: ident) => {{
+  ($callbacks: ident, $map: ident, $screen:expr, $keys:expr, actions => $actions: ident) => {{
     let user_actions = $actions
       .iter()
-      .filter_map(|ref name| $map.get(name.as_str()))
-      .cloned()
-      .collect::<Vec<Action<'m>>>();
-    $callbacks.insert($key.clone(), user_actions);
+      .filter_map(|defs| build_chain(&$map, defs))
+      .filter(|chain| !chain.is_empty())
+      .collect::<Vec<ActionChain<'m>>>();
+    if !user_actions.is_empty() {
+      $callbacks.insert($screen, $keys, user_actions);
+    }
   }};
 }
 
+fn create_callbacks<'m>(config: &Config) -> ControllerCallbacks<'m> {
+  // Set callbacks
+  let mut callbacks = ControllerCallbacks::new();
+
+  // Execute a command
+  register_callback!(callbacks, config, execute_command, execute_command);
+  // Clear the playlist
+  register_callback!(callbacks, config, clear, playlist_clear);
+  // Delete selected items
+  register_callback!(callbacks, config, delete, playlist_delete_items);
+  // Pause
+  register_callback!(callbacks, config, play_pause, playlist_pause);
+  // Stop
+  register_callback!(callbacks, config, stop, playlist_stop);
+  // Previous song
+  register_callback!(callbacks, config, previous, playlist_previous);
+  // Next song
+  register_callback!(callbacks, config, next, playlist_next);
+  // Locked screen
+  register_callback!(callbacks, config, toggle_screen_lock, toggle_screen_lock);
+  register_callback!(callbacks, config, master_screen, master_screen);
+  register_callback!(callbacks, config, slave_screen, slave_screen);
+  // Increase volume
+  register_callback!(callbacks, config, volume_up, volume_up);
+  // Decrease volume
+  register_callback!(callbacks, config, volume_down, volume_down);
+  // Press enter
+  register_callback!(callbacks, config, press_enter, play_selected);
+  // Scroll down
+  register_callback!(callbacks, config, scroll_down, scroll_down);
+  // Scroll up
+  register_callback!(callbacks, config, scroll_up, scroll_up);
+  // Page down
+  register_callback!(callbacks, config, page_down, page_down);
+  // Page up
+  register_callback!(callbacks, config, page_up, page_up);
+  // Move home
+  register_callback!(callbacks, config, move_home, move_home);
+  // Move end
+  register_callback!(callbacks, config, move_end, move_end);
+  // Show help
+  register_callback!(callbacks, config, show_help, show_help);
+  // Show playlist
+  register_callback!(callbacks, config, show_playlist, show_playlist);
+  // Show server info
+  register_callback!(callbacks, config, show_server_info, show_server_info);
+  // Switch screens
+  register_callback!(callbacks, config, next_screen, next_screen);
+  register_callback!(callbacks, config, previous_screen, previous_screen);
+  // Playing song
+  register_callback!(callbacks, config, jump_to_playing_song, jump_to_playing_song);
+  register_callback!(callbacks, config, toggle_playing_song_centering, toggle_playing_song_centering);
+  // Filter the playlist
+  register_callback!(callbacks, config, apply_filter, apply_filter);
+  // Find items
+  register_callback!(callbacks, config, find_item_forward, find_item_forward);
+  register_callback!(callbacks, config, find_item_backward, find_item_backward);
+  register_callback!(callbacks, config, next_found_item, next_found_item);
+  register_callback!(callbacks, config, previous_found_item, previous_found_item);
+  register_callback!(callbacks, config, toggle_find_mode, toggle_find_mode);
+  // Toggle bitrate visibility
+  register_callback!(callbacks, config, toggle_bitrate_visibility, toggle_bitrate_visibility);
+  // Toggle the user interface
+  register_callback!(callbacks, config, toggle_interface, toggle_interface);
+  // Toggle mouse support
+  register_callback!(callbacks, config, toggle_mouse, toggle_mouse);
+  // Toggle random
+  register_callback!(callbacks, config, toggle_random, toggle_random);
+  // Toggle repeat
+  register_callback!(callbacks, config, toggle_repeat, toggle_repeat);
+  // Reload configuration
+  register_callback!(callbacks, config, reload_config, reload_config);
+  // Mouse support
+  register_callback!(callbacks, nc::KEY_MOUSE, process_mouse);
+  // Resize windows
+  register_callback!(callbacks, nc::KEY_RESIZE, resize_windows);
+
+  // Register custom user actions (possibly overriding defaults).
+  let action_map = get_action_map();
+  for ((screen, keys), actions) in &config.keys.custom {
+    register_callback!(callbacks, action_map, screen.as_ref().map(|s| s.as_str()), keys, actions => actions);
+  }
+
+  return callbacks;
+}
+
+/// Read a key, with its modifiers.
+fn read_key() -> i32 {
+  let ch = normalize_keycode(nc::getch());
+
+  // Alt-? is received as escape followed by the key
+  if ch == KEY_ESCAPE {
+    let next = nc::getch();
+    if next != -1 {
+      return KEY_ALT + normalize_keycode(next);
+    }
+  }
+  return ch;
+}
+
+fn get_quit_keycodes(config: &Config) -> Vec<i32> {
+  config.keys.quit.iter().map(|&key| key.keycode()).collect::<Vec<i32>>()
+}
+
+fn create_config_watcher(config: &Config) -> ConfigWatcher {
+  ConfigWatcher::new(&config.files, Duration::seconds(1))
+}
+
 impl<'c, 'm> Controller<'c, 'm> {
   pub fn new(model: &'c mut SharedModel<'m>, config: &'c Config) -> Controller<'c, 'm> {
-    // Set callbacks
-    let mut callbacks = ControllerCallbacks::new();
-
-    // Execute a command
-    register_callback!(callbacks, config, execute_command, execute_command);
-    // Clear the playlist
-    register_callback!(callbacks, config, clear, playlist_clear);
-    // Delete selected items
-    register_callback!(callbacks, config, delete, playlist_delete_items);
-    // Pause
-    register_callback!(callbacks, config, play_pause, playlist_pause);
-    // Stop
-    register_callback!(callbacks, config, stop, playlist_stop);
-    // Previous song
-    register_callback!(callbacks, config, previous, playlist_previous);
-    // Next song
-    register_callback!(callbacks, config, next, playlist_next);
-    // Increase volume
-    register_callback!(callbacks, config, volume_up, volume_up);
-    // Decrease volume
-    register_callback!(callbacks, config, volume_down, volume_down);
-    // Press enter
-    register_callback!(callbacks, config, press_enter, play_selected);
-    // Scroll down
-    register_callback!(callbacks, config, scroll_down, scroll_down);
-    // Scroll up
-    register_callback!(callbacks, config, scroll_up, scroll_up);
-    // Page down
-    register_callback!(callbacks, config, page_down, page_down);
-    // Page up
-    register_callback!(callbacks, config, page_up, page_up);
-    // Move home
-    register_callback!(callbacks, config, move_home, move_home);
-    // Move end
-    register_callback!(callbacks, config, move_end, move_end);
-    // Show help
-    register_callback!(callbacks, config, show_help, show_help);
-    // Show playlist
-    register_callback!(callbacks, config, show_playlist, show_playlist);
-    // Show server info
-    register_callback!(callbacks, config, show_server_info, show_server_info);
-    // Toggle bitrate visibility
-    register_callback!(callbacks, config, toggle_bitrate_visibility, toggle_bitrate_visibility);
-    // Toggle random
-    register_callback!(callbacks, config, toggle_random, toggle_random);
-    // Toggle repeat
-    register_callback!(callbacks, config, toggle_repeat, toggle_repeat);
-    // Mouse support
-    register_callback!(callbacks, nc::KEY_MOUSE, process_mouse);
-    // Resize windows
-    register_callback!(callbacks, nc::KEY_RESIZE, resize_windows);
-
-    // Register custom user actions (possibly overriding defaults).
-    let action_map = get_action_map();
-    for (keycode, actions) in &config.keys.custom {
-      register_callback!(callbacks, action_map, keycode, actions => actions);
+    // Reload the configuration on SIGHUP
+    let sighup = Arc::new(AtomicBool::new(false));
+    if signal_hook::flag::register(signal_hook::SIGHUP, sighup.clone()).is_err() {
+      stderr!("[Warning] failed to register SIGHUP handler");
     }
 
-    let quit_keycodes = config.keys.quit.iter().map(|&key| key.keycode()).collect::<Vec<i32>>();
-
     Controller {
       model: model,
-      callbacks: callbacks,
-      quit_keycodes: quit_keycodes,
+      callbacks: create_callbacks(config),
+      quit_keycodes: get_quit_keycodes(config),
+      pending_keys: Vec::new(),
+      count: 0,
+      pending_time: get_time(),
+      config_files: config.files.clone(),
+      config_watcher: create_config_watcher(config),
+      sighup: sighup,
+    }
+  }
+
+  /// Reload the configuration files, and apply the new configuration.
+  pub fn reload_config(&mut self) {
+    let loader = ConfigLoader::new();
+    let (config, diagnostics) = loader.load_with_diagnostics(&self.config_files.config, &self.config_files.bindings);
+    self.callbacks = create_callbacks(&config);
+    self.quit_keycodes = get_quit_keycodes(&config);
+    self.count = 0;
+    self.set_pending_keys(Vec::new());
+    self.config_watcher = create_config_watcher(&config);
+
+    let mut model = self.model.lock().unwrap();
+    model.set_config(config);
+
+    // Report the most important problem, if any
+    let problem = diagnostics.iter().find(|d| d.is_error()).or_else(|| diagnostics.first());
+    if let Some(d) = problem {
+      model.update_message(&format!("{} ({} problem(s) in total)", d, diagnostics.len()));
     }
   }
 
+  /// Whether the configuration should be reloaded.
+  fn should_reload_config(&mut self) -> bool {
+    let requested = self.model.lock().unwrap().take_reload_request();
+    let sighup = self.sighup.swap(false, Ordering::Relaxed);
+    let changed = self.config_watcher.has_changed();
+    return requested || sighup || changed;
+  }
+
   pub fn process_input(&mut self) -> ControlQuery {
+    if self.should_reload_config() {
+      self.reload_config();
+    }
+
     // Get user input
-    let ch = nc::getch();
+    let ch = read_key();
 
-    // Quit check
-    if !self.quit_keycodes.contains(&ch) {
-      // No key pressed
-      if ch == -1 {
-        // Do nothing
-        return ControlQuery::Nothing;
+    // Keys typed while a command is being typed go to the command line
+    if ch != -1 {
+      let mut model = self.model.lock().unwrap();
+      if model.is_command_line_active() {
+        model.process_command_line_key(ch);
+        return ControlQuery::Command;
       }
-      // Registered callbacks
-      else if let Some(actions) = self.callbacks.get_mut(&ch) {
-        for action in actions {
-          let mut model = self.model.lock().unwrap();
-          action.execute(&mut model);
+    }
+
+    // No key pressed
+    if ch == -1 {
+      // Run the binding of a pending key sequence after the timeout
+      // The timeout is read from the model, as it can be changed with ":set"
+      let waiting = !self.pending_keys.is_empty() || self.count > 0;
+      let timeout = Duration::milliseconds(i64::from(self.model.lock().unwrap().key_sequence_timeout()));
+      if waiting && get_time() > self.pending_time + timeout {
+        let mut keys = self.pending_keys.clone();
+        // A digit that was not followed by an action is a key on its own
+        if keys.is_empty() {
+          if self.count < 10 {
+            keys.push('0' as i32 + self.count as i32);
+          }
+          self.count = 0;
         }
-      }
-      // TODO: debug only
-      else {
-        let mut model = self.model.lock().unwrap();
-        let name = nc::keyname(ch);
-        if name.is_some() {
-          model.update_message(&format!("Pressed unmapped '{}' (keycode = {})", name.unwrap(), ch));
-        } else {
-          model.update_message(&format!("Pressed unmapped key (keycode = {})", ch));
+        self.set_pending_keys(Vec::new());
+        if !keys.is_empty() {
+          self.run_bindings(&keys, false);
         }
+        return ControlQuery::Command;
       }
-      return ControlQuery::Command;
-    } else {
+      // Do nothing
+      return ControlQuery::Nothing;
+    }
+
+    // Count prefix, e.g. "10" in "10j". A digit bound to an action (e.g. "1"
+    // to show_playlist) runs it when no other key follows before the timeout.
+    if self.pending_keys.is_empty() && ch >= '0' as i32 && ch <= '9' as i32 {
+      let digit = (ch - '0' as i32) as u32;
+      if self.count > 0 || digit > 0 {
+        self.count = (10 * self.count + digit).min(MAX_COUNT);
+        self.pending_time = get_time();
+        self.set_pending_keys(Vec::new());
+        return ControlQuery::Command;
+      }
+    }
+
+    // Quit check
+    if self.pending_keys.is_empty() && self.quit_keycodes.contains(&ch) {
       return ControlQuery::Exit;
     }
+
+    let mut keys = self.pending_keys.clone();
+    keys.push(ch);
+    self.set_pending_keys(Vec::new());
+    let pending = self.run_bindings(&keys, true);
+    if !pending.is_empty() {
+      // Wait for the next key of the sequence
+      self.pending_time = get_time();
+      self.set_pending_keys(pending);
+    }
+    return ControlQuery::Command;
+  }
+
+  /// Run the actions bound to a key sequence. A sequence that is not bound
+  /// runs its longest bound start, then the rest of its keys. Returns the keys
+  /// of a sequence still being typed (only if `wait` is true).
+  fn run_bindings(&mut self, keys: &[i32], wait: bool) -> Vec<i32> {
+    let mut model = self.model.lock().unwrap();
+    let mut keys = keys.to_vec();
+
+    while !keys.is_empty() {
+      let screen = model.active_screen();
+      let (n, chains) = match self.callbacks.lookup(screen, &keys) {
+        KeyLookup::Prefix | KeyLookup::Ambiguous(_) if wait => return keys,
+        KeyLookup::Match(chains) | KeyLookup::Ambiguous(chains) => (keys.len(), chains),
+        KeyLookup::None | KeyLookup::Prefix => match self.callbacks.longest_match(screen, &keys) {
+          Some(m) => m,
+          None => {
+            // Show the keycode of the unmapped key, which is needed to bind
+            // keys without a name
+            let message = match keys.len() {
+              1 => format!("Unmapped key '{}' (keycode = {})", ControlKey::KeyCode(keys[0]), keys[0]),
+              _ => format!("Unmapped key '{}'", ControlKey::KeyCode(keys[0])),
+            };
+            model.update_message(&message);
+            keys.remove(0);
+            continue;
+          }
+        },
+      };
+
+      let count = self.count.max(1);
+      for chain in chains {
+        // Stop at the first chain that could be run entirely
+        if execute_chain_with_count(chain, &mut model, count) {
+          break;
+        }
+      }
+      self.count = 0;
+      keys.drain(..n);
+    }
+    self.count = 0;
+    return keys;
+  }
+
+  /// Set the keys of the key sequence being typed, and show them with the
+  /// count.
+  fn set_pending_keys(&mut self, keys: Vec<i32>) {
+    let mut names = keys.iter().map(|&k| ControlKey::KeyCode(k).to_string()).collect::<Vec<String>>();
+    if self.count > 0 {
+      names.insert(0, self.count.to_string());
+    }
+    self.model.lock().unwrap().set_pending_keys(&names.join(" "));
+    self.pending_keys = keys;
   }
 }
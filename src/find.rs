@@ -0,0 +1,166 @@
+extern crate regex;
+
+use regex::{Regex, RegexBuilder};
+
+/// Behavior of a search reaching the end of a list (`default_find_mode`).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FindMode {
+  /// Stop at the end of the list.
+  Normal,
+  /// Continue from the other end of the list.
+  Wrapped,
+}
+
+impl FindMode {
+  pub fn from_name(name: &str) -> Option<FindMode> {
+    match name {
+      "normal" => Some(FindMode::Normal),
+      "wrapped" => Some(FindMode::Wrapped),
+      _ => None,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      FindMode::Normal => "normal",
+      FindMode::Wrapped => "wrapped",
+    }
+  }
+
+  pub fn toggle(self) -> FindMode {
+    match self {
+      FindMode::Normal => FindMode::Wrapped,
+      FindMode::Wrapped => FindMode::Normal,
+    }
+  }
+}
+
+impl Default for FindMode {
+  fn default() -> FindMode {
+    FindMode::Wrapped
+  }
+}
+
+/// Case-insensitive pattern used to find items.
+#[derive(Clone, Debug)]
+pub struct Matcher {
+  pattern: String,
+  regex: Regex,
+  ignore_leading_the: bool,
+}
+
+impl Matcher {
+  /// Create a matcher. Unless `regular_expressions` is set, the pattern is
+  /// matched literally.
+  pub fn new(pattern: &str, regular_expressions: bool, ignore_leading_the: bool) -> Result<Matcher, String> {
+    let re = if regular_expressions {
+      String::from(pattern)
+    } else {
+      regex::escape(pattern)
+    };
+    let regex = RegexBuilder::new(&re)
+      .case_insensitive(true)
+      .build()
+      .map_err(|_| format!("invalid regular expression \"{}\"", pattern))?;
+    return Ok(Matcher {
+      pattern: String::from(pattern),
+      regex: regex,
+      ignore_leading_the: ignore_leading_the,
+    });
+  }
+
+  pub fn pattern(&self) -> &str {
+    &self.pattern
+  }
+
+  /// Whether a text matches the pattern.
+  pub fn matches(&self, text: &str) -> bool {
+    let text = if self.ignore_leading_the { strip_leading_the(text) } else { text };
+    return self.regex.is_match(text);
+  }
+
+  /// Whether any of the texts (e.g. the columns of a row) matches the
+  /// pattern.
+  pub fn matches_any<S: AsRef<str>>(&self, texts: &[S]) -> bool {
+    texts.iter().any(|t| self.matches(t.as_ref()))
+  }
+}
+
+/// Remove a leading "The " from a text (e.g. "The Beatles").
+fn strip_leading_the(text: &str) -> &str {
+  if text.len() > 4 && text.is_char_boundary(4) && text[..4].eq_ignore_ascii_case("the ") {
+    &text[4..]
+  } else {
+    text
+  }
+}
+
+/// Index of the next matching item among `n` items, starting after `current`
+/// (or at the first item if there is no current item).
+pub fn find_next<F>(n: usize, current: Option<usize>, forward: bool, mode: FindMode, is_match: F) -> Option<usize>
+where
+  F: Fn(usize) -> bool,
+{
+  if n == 0 {
+    return None;
+  }
+
+  let candidates: Box<dyn Iterator<Item = usize>> = match (current, forward) {
+    (None, true) => Box::new(0..n),
+    (None, false) => Box::new((0..n).rev()),
+    (Some(c), true) => {
+      let after = (c + 1).min(n)..n;
+      match mode {
+        FindMode::Normal => Box::new(after),
+        FindMode::Wrapped => Box::new(after.chain(0..c.min(n))),
+      }
+    }
+    (Some(c), false) => {
+      let before = (0..c.min(n)).rev();
+      match mode {
+        FindMode::Normal => Box::new(before),
+        FindMode::Wrapped => Box::new(before.chain(((c + 1).min(n)..n).rev())),
+      }
+    }
+  };
+  return candidates.into_iter().find(|&i| is_match(i));
+}
+
+#[test]
+fn test_matcher() {
+  let m = Matcher::new("beatles", false, true).unwrap();
+  assert!(m.matches("The Beatles"));
+  assert!(m.matches_any(&["Abbey Road", "The BEATLES"]));
+  assert!(!m.matches("Abbey Road"));
+
+  let m = Matcher::new("^beat", true, true).unwrap();
+  assert!(m.matches("The Beatles"));
+  let m = Matcher::new("^beat", true, false).unwrap();
+  assert!(!m.matches("The Beatles"));
+
+  // Literal patterns are not regular expressions
+  let m = Matcher::new("a.c", false, false).unwrap();
+  assert!(m.matches("a.c"));
+  assert!(!m.matches("abc"));
+
+  assert!(Matcher::new("(", true, false).is_err());
+  assert!(Matcher::new("(", false, false).is_ok());
+}
+
+#[test]
+fn test_find_next() {
+  let items = ["a", "b", "a", "c", "a"];
+  let is_a = |i: usize| items[i] == "a";
+  let n = items.len();
+
+  assert_eq!(find_next(n, None, true, FindMode::Normal, is_a), Some(0));
+  assert_eq!(find_next(n, None, false, FindMode::Normal, is_a), Some(4));
+  assert_eq!(find_next(n, Some(0), true, FindMode::Normal, is_a), Some(2));
+  assert_eq!(find_next(n, Some(2), false, FindMode::Normal, is_a), Some(0));
+  assert_eq!(find_next(n, Some(4), true, FindMode::Normal, is_a), None);
+  assert_eq!(find_next(n, Some(4), true, FindMode::Wrapped, is_a), Some(0));
+  assert_eq!(find_next(n, Some(0), false, FindMode::Normal, is_a), None);
+  assert_eq!(find_next(n, Some(0), false, FindMode::Wrapped, is_a), Some(4));
+  assert_eq!(find_next(n, Some(1), true, FindMode::Wrapped, |i| items[i] == "b"), None);
+  assert_eq!(find_next(0, None, true, FindMode::Wrapped, is_a), None);
+}
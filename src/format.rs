@@ -1,10 +1,12 @@
 extern crate ncurses;
 extern crate nom;
 
-use crate::constants::Color;
+use crate::constants::{Color, COLOR_DEFAULT};
 use crate::parser::{cstr, get_columns_format, ParserError};
 use ncurses as nc;
 use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
 
 /// Column type for playlist display.
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
@@ -203,6 +205,181 @@ pub enum Expression {
   SongProperty(SongProperty),
 }
 
+/// Element of a song format.
+#[derive(Clone, PartialEq, Debug)]
+pub enum FormatItem {
+  Expression(Expression),
+  /// Optional part (`{...}`), only shown if all its song properties are set.
+  /// Otherwise, the next alternative (`|{...}`) is tried.
+  Group(Vec<Vec<FormatItem>>),
+}
+
+/// Song format used by ncmpcpp, e.g. `{%a - }{%t}|{%f}`, with colors (`$1`)
+/// and attributes (`$b`, `$/b`).
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct SongFormat {
+  items: Vec<FormatItem>,
+}
+
+/// Color of a `$0`-`$9` format code (0 and 9 restore the default color).
+fn get_format_color(c: char) -> Color {
+  match c {
+    '1'..='8' => c as Color - '1' as Color,
+    _ => COLOR_DEFAULT,
+  }
+}
+
+fn parse_format_items(chars: &mut Peekable<Chars>, in_group: bool) -> Result<Vec<FormatItem>, ParserError> {
+  let mut items = Vec::new();
+  let mut text = String::new();
+  let flush = |text: &mut String, items: &mut Vec<FormatItem>| {
+    if !text.is_empty() {
+      items.push(FormatItem::Expression(Expression::String(text.clone())));
+      text.clear();
+    }
+  };
+
+  while let Some(c) = chars.next() {
+    match c {
+      '{' => {
+        flush(&mut text, &mut items);
+        let mut alternatives = vec![parse_format_items(chars, true)?];
+        while chars.peek() == Some(&'|') {
+          chars.next();
+          if chars.next() != Some('{') {
+            return Err(ParserError::Parse(0));
+          }
+          alternatives.push(parse_format_items(chars, true)?);
+        }
+        items.push(FormatItem::Group(alternatives));
+      }
+      '}' if in_group => {
+        flush(&mut text, &mut items);
+        return Ok(items);
+      }
+      '%' => match chars.next() {
+        Some('%') => text.push('%'),
+        Some(p) => {
+          flush(&mut text, &mut items);
+          let property = get_column_type(&p.to_string())?;
+          items.push(FormatItem::Expression(Expression::SongProperty(property)));
+        }
+        None => return Err(ParserError::Parse(0)),
+      },
+      '$' => match chars.next() {
+        Some('$') => text.push('$'),
+        // Right alignment is not supported
+        Some('R') => flush(&mut text, &mut items),
+        Some(d) if d.is_ascii_digit() => {
+          flush(&mut text, &mut items);
+          items.push(FormatItem::Expression(Expression::Color(get_format_color(d))));
+        }
+        Some('/') => {
+          flush(&mut text, &mut items);
+          let code = format!("/{}", chars.next().unwrap_or(' '));
+          items.push(FormatItem::Expression(Expression::Format(get_format(&code)?)));
+        }
+        Some(f) => {
+          flush(&mut text, &mut items);
+          items.push(FormatItem::Expression(Expression::Format(get_format(&f.to_string())?)));
+        }
+        None => return Err(ParserError::Parse(0)),
+      },
+      _ => text.push(c),
+    }
+  }
+
+  if in_group {
+    return Err(ParserError::Parse(0));
+  }
+  flush(&mut text, &mut items);
+  return Ok(items);
+}
+
+/// Append text to rendered expressions, merging it with the previous text.
+fn push_text(out: &mut Vec<Expression>, text: &str) {
+  if let Some(Expression::String(ref mut s)) = out.last_mut() {
+    s.push_str(text);
+    return;
+  }
+  out.push(Expression::String(String::from(text)));
+}
+
+/// Render format items, and return whether all their song properties are set.
+fn render_format_items<F>(items: &[FormatItem], value: &F, out: &mut Vec<Expression>) -> bool
+where
+  F: Fn(&SongProperty) -> String,
+{
+  let mut complete = true;
+  for item in items {
+    match item {
+      FormatItem::Expression(Expression::SongProperty(p)) => {
+        let v = value(p);
+        if v.is_empty() {
+          complete = false;
+        } else {
+          push_text(out, &v);
+        }
+      }
+      FormatItem::Expression(Expression::String(s)) => push_text(out, s),
+      FormatItem::Expression(e) => out.push(e.clone()),
+      FormatItem::Group(alternatives) => {
+        for alternative in alternatives {
+          let mut rendered = Vec::new();
+          if render_format_items(alternative, value, &mut rendered) {
+            for e in rendered {
+              match e {
+                Expression::String(s) => push_text(out, &s),
+                e => out.push(e),
+              }
+            }
+            break;
+          }
+        }
+      }
+    }
+  }
+  return complete;
+}
+
+impl SongFormat {
+  pub fn parse(format: &str) -> Result<SongFormat, ParserError> {
+    let mut chars = format.chars().peekable();
+    return match parse_format_items(&mut chars, false) {
+      Ok(items) => Ok(SongFormat { items: items }),
+      // The error is at the last character read
+      Err(ParserError::Parse(_)) => Err(ParserError::Format((format.chars().count() - chars.count()) as u32)),
+      Err(e) => Err(e),
+    };
+  }
+
+  /// Render the format, given the values of the song properties (empty if
+  /// unset), as text, colors and attributes.
+  pub fn render<F>(&self, value: F) -> Vec<Expression>
+  where
+    F: Fn(&SongProperty) -> String,
+  {
+    let mut out = Vec::new();
+    render_format_items(&self.items, &value, &mut out);
+    return out;
+  }
+
+  /// Render the format as plain text, ignoring colors and attributes.
+  pub fn render_text<F>(&self, value: F) -> String
+  where
+    F: Fn(&SongProperty) -> String,
+  {
+    self
+      .render(value)
+      .iter()
+      .filter_map(|e| match e {
+        Expression::String(s) => Some(s.as_str()),
+        _ => None,
+      })
+      .collect()
+  }
+}
+
 #[test]
 fn check_get_format() {
   assert_eq!(get_format("").unwrap(), Format::None);
@@ -227,3 +404,40 @@ fn check_expression() {
   v.push(Expression::Format(Format::NoBold));
   v.push(Expression::SongProperty(SongProperty::Album));
 }
+
+#[test]
+fn check_song_format() {
+  let value = |p: &SongProperty| match *p {
+    SongProperty::Artist => String::from("Air"),
+    SongProperty::Title => String::from("La femme d'argent"),
+    SongProperty::Filename => String::from("air.flac"),
+    _ => String::new(),
+  };
+  let text = |f: &str| SongFormat::parse(f).unwrap().render_text(value);
+
+  assert_eq!(text("{%a - }{%t}|{%f}"), "Air - La femme d'argent");
+  assert_eq!(text("{%b - }{%t}"), "La femme d'argent");
+  assert_eq!(text("{%y}|{%f}"), "air.flac");
+  assert_eq!(text("{{%a}{ - %b}}|{%D}"), "Air");
+  assert_eq!(text("100%% %a $$"), "100% Air $");
+
+  let f = SongFormat::parse("$b$4%a$9$/b").unwrap();
+  assert_eq!(
+    f.render(value),
+    vec![
+      Expression::Format(Format::Bold),
+      Expression::Color(nc::COLOR_YELLOW),
+      Expression::String(String::from("Air")),
+      Expression::Color(COLOR_DEFAULT),
+      Expression::Format(Format::NoBold),
+    ]
+  );
+
+  let position = |f: &str| match SongFormat::parse(f) {
+    Err(ParserError::Format(p)) => p,
+    _ => 0,
+  };
+  assert_eq!(position("{%a"), 3);
+  assert_eq!(position("{%a}|%t"), 6);
+  assert_eq!(position("$b%z"), 4);
+}
@@ -1,63 +1,87 @@
 use std::cmp;
 
+use crate::command::COMMANDS;
 use crate::config::{Config, ControlKey};
-use ncurses as nc;
+use crate::constants::{COLOR_PAIR_COLOR1, COLOR_PAIR_COLOR2, COLOR_PAIR_MAIN_WINDOW_HIGHLIGHT};
+use crate::find::{find_next, FindMode, Matcher};
+use crate::render::{Style, Surface};
+use crate::text;
 
 use crate::model::ACTION_DESCRIPTION;
-use crate::view::{bold, get_color};
 
 pub struct Help {
-  pad: nc::WINDOW,
-  max_x: i32,
-  max_y: i32,
-  start_y: i32,
+  /// Number of rows of the pane showing the help.
+  height: i32,
   cur_y: i32,
   config: Config,
   current_row: i32,
   total_rows: i32,
   tab_size: i32,
   key_col_size: i32,
+  /// Text of each row, used to find items.
+  lines: Vec<String>,
+  /// Pattern of the items to highlight.
+  matcher: Option<Matcher>,
+  /// Row of the last found item.
+  found_row: Option<usize>,
 }
 
 impl Help {
-  pub fn new(win: nc::WINDOW, config: &Config) -> Help {
-    let mut max_x = 0;
-    let mut max_y = 0;
-    nc::getmaxyx(win, &mut max_y, &mut max_x);
-    max_y -= 1;
+  pub fn new(config: &Config) -> Help {
     Help {
-      pad: nc::newpad(500, max_x),
-      max_x: max_x,
-      max_y: max_y,
-      start_y: 2,
+      height: 0,
       cur_y: 0,
       config: config.clone(),
       current_row: 0,
       total_rows: 0,
       tab_size: 2,
       key_col_size: 20,
+      lines: Vec::new(),
+      matcher: None,
+      found_row: None,
     }
   }
 
+  /// Update the configuration (e.g. after a reload).
+  pub fn set_config(&mut self, config: &Config) {
+    self.config = config.clone();
+    self.total_rows = 0;
+  }
+
+  /// First row of the help shown in its pane.
+  pub fn first_row(&self) -> i32 {
+    return self.cur_y;
+  }
+
   fn newline(&mut self) {
     self.current_row += 1;
   }
 
-  fn section(&mut self, name: &str) {
-    nc::wattron(self.pad, bold());
-    nc::mvwprintw(self.pad, self.current_row, self.tab_size, &name);
-    nc::wattroff(self.pad, bold());
+  /// Record the text of the current row.
+  fn set_line(&mut self, text: String) {
+    let row = self.current_row as usize;
+    if self.lines.len() <= row {
+      self.lines.resize(row + 1, String::new());
+    }
+    self.lines[row] = text;
+  }
+
+  fn section(&mut self, s: &mut dyn Surface, name: &str) {
+    self.set_line(String::from(name));
+    s.print(self.current_row, self.tab_size, name, Style::color(COLOR_PAIR_COLOR2).bold());
     self.current_row += 1;
   }
 
-  fn print_entry(&self, name: &str, desc: &str) {
-    let col_size = cmp::max(self.key_col_size, name.len() as i32);
-    nc::mvwprintw(self.pad, self.current_row, 2 * self.tab_size, &name);
-    nc::mvwprintw(self.pad, self.current_row, 2 * self.tab_size + col_size, ": ");
-    nc::mvwprintw(self.pad, self.current_row, 2 * self.tab_size + col_size + 2, &desc);
+  fn print_entry(&mut self, s: &mut dyn Surface, name: &str, desc: &str) {
+    self.set_line(format!("{} : {}", name, desc));
+    let col_size = cmp::max(self.key_col_size, text::width(name) as i32);
+    let x = 2 * self.tab_size;
+    s.print(self.current_row, x, name, Style::color(COLOR_PAIR_COLOR1));
+    s.print(self.current_row, x + col_size, ": ", Style::default());
+    s.print(self.current_row, x + col_size + 2, desc, Style::default());
   }
 
-  fn keys(&self, keys: &[ControlKey], desc: &str) {
+  fn keys(&mut self, s: &mut dyn Surface, keys: &[ControlKey], desc: &str) {
     let keys_s: String = keys.iter().fold(String::default(), |acc, &x| {
       if acc.is_empty() {
         format!("{}", x)
@@ -65,11 +89,11 @@ impl Help {
         acc + format!(" {}", x).as_str()
       }
     });
-    self.print_entry(keys_s.as_str(), desc);
+    self.print_entry(s, keys_s.as_str(), desc);
   }
 
   pub fn scroll(&mut self, offset: i32) {
-    let range = self.total_rows - self.max_y;
+    let range = self.total_rows - (self.height - 1);
     let min_y = 0;
     let max_y = range;
 
@@ -81,26 +105,60 @@ impl Help {
     }
   }
 
-  pub fn print(&mut self) {
+  /// Set the pattern of the items to find and highlight.
+  pub fn set_matcher(&mut self, matcher: Option<Matcher>) {
+    self.matcher = matcher;
+    self.found_row = None;
+  }
+
+  /// Scroll to the next item matching the pattern. Returns false if there is
+  /// none.
+  pub fn find(&mut self, forward: bool, mode: FindMode) -> bool {
+    let found = match self.matcher {
+      Some(ref m) => {
+        // Start from the last found item, or from the first displayed row
+        let current = self
+          .found_row
+          .or_else(|| if self.cur_y > 0 { Some(self.cur_y as usize - 1) } else { None });
+        let lines = &self.lines;
+        find_next(lines.len(), current, forward, mode, |i| m.matches(&lines[i]))
+      }
+      None => None,
+    };
+
+    if let Some(row) = found {
+      self.found_row = found;
+      self.cur_y = 0;
+      self.scroll(-(row as i32));
+    }
+    return found.is_some();
+  }
+
+  /// Print the whole help on a surface, shown in a pane of `height` rows
+  /// from `first_row`.
+  pub fn print(&mut self, s: &mut dyn Surface, height: i32) {
+    self.height = height;
+
     macro_rules! print_key(
       ($k:ident, $desc:expr) => (
-        self.keys(&self.config.keys.$k, $desc);
+        let keys = self.config.keys.$k.clone();
+        self.keys(s, &keys, $desc);
         self.newline();
         )
       );
     macro_rules! print_text(
       ($k:expr, $desc:expr) => (
-        self.print_entry($k, $desc);
+        self.print_entry(s, $k, $desc);
         self.newline();
         )
       );
 
-    nc::scrollok(self.pad, true);
+    s.erase();
 
     self.current_row = 0;
 
     self.newline();
-    self.section("Keys - Movement");
+    self.section(s, "Keys - Movement");
     self.newline();
     print_key!(scroll_up, "Move cursor up");
     print_key!(scroll_down, "Move cursor down");
@@ -109,13 +167,26 @@ impl Help {
     print_key!(move_home, "Home");
     print_key!(move_end, "End");
     self.newline();
+    print_key!(find_item_forward, "Find item forward");
+    print_key!(find_item_backward, "Find item backward");
+    print_key!(next_found_item, "Jump to next found item");
+    print_key!(previous_found_item, "Jump to previous found item");
+    print_key!(toggle_find_mode, "Toggle find mode (normal/wrapped)");
+    self.newline();
     print_key!(show_help, "Show help");
     print_key!(show_playlist, "Show playlist");
     self.newline();
     print_key!(show_server_info, "Show server info");
+    self.newline();
+    print_key!(next_screen, "Switch to the next screen");
+    print_key!(previous_screen, "Switch to the previous screen");
+    self.newline();
+    print_key!(toggle_screen_lock, "Lock/unlock current screen");
+    print_key!(master_screen, "Switch to master screen (left one)");
+    print_key!(slave_screen, "Switch to slave screen (right one)");
 
     self.newline();
-    self.section("Keys - Global");
+    self.section(s, "Keys - Global");
     self.newline();
     print_key!(stop, "Stop");
     print_key!(play_pause, "Pause");
@@ -123,34 +194,52 @@ impl Help {
     print_key!(previous, "Previous track");
     print_key!(
       volume_down,
-      format!("Decrease volume by {}%%", self.config.params.volume_change_step).as_str()
+      format!("Decrease volume by {}%", self.config.params.volume_change_step).as_str()
     );
     print_key!(
       volume_up,
-      format!("Increase volume by {}%%", self.config.params.volume_change_step).as_str()
+      format!("Increase volume by {}%", self.config.params.volume_change_step).as_str()
     );
     self.newline();
     print_key!(toggle_repeat, "Toggle repeat mode");
     print_key!(toggle_random, "Toggle random mode");
     print_key!(toggle_bitrate_visibility, "Toggle bitrate visibility");
+    print_key!(toggle_mouse, "Toggle mouse support");
+    print_key!(toggle_interface, "Toggle user interface");
     self.newline();
+    print_key!(reload_config, "Reload configuration");
     print_key!(quit, "Quit");
 
     self.newline();
-    self.section("Keys - Playlist");
+    self.section(s, "Keys - Playlist");
     self.newline();
     print_key!(press_enter, "Play selected item");
     print_key!(delete, "Delete selected item(s) from playlist");
     print_key!(clear, "Clear playlist");
+    print_key!(apply_filter, "Filter playlist");
+    print_key!(jump_to_playing_song, "Jump to current song");
+    print_key!(toggle_playing_song_centering, "Toggle playing song centering");
 
     self.newline();
-    self.section("Mouse - Playlist");
+    self.section(s, "Mouse - Playlist");
     self.newline();
     print_text!("Left click", "Select pointed item");
-    print_text!("Right click", "Play");
+    print_text!("Double click", "Play pointed item");
+    print_text!("Right click", "Play pointed item");
+    print_text!("Mouse wheel", "Scroll");
+    print_text!("Column header", "Sort displayed playlist by column");
+    print_text!("Volume", "Set volume (wheel to change it)");
+
+    self.newline();
+    self.section(s, "Commands");
+    self.newline();
+
+    for command in COMMANDS {
+      print_text!(&command.usage(), command.help);
+    }
 
     self.newline();
-    self.section("List of available commands");
+    self.section(s, "List of available commands");
     self.newline();
 
     for (name, desc) in ACTION_DESCRIPTION.iter() {
@@ -158,15 +247,12 @@ impl Help {
     }
 
     self.newline();
-    self.section("List of available colors");
+    self.section(s, "List of available colors");
     self.newline();
 
     let mut pos = 0;
     for i in 1..232 {
-      let color = get_color(i);
-      nc::wattron(self.pad, color);
-      nc::mvwprintw(self.pad, self.current_row, pos, format!("{} ", i).as_str());
-      nc::wattroff(self.pad, color);
+      s.print(self.current_row, pos, &format!("{} ", i), Style::color(i));
       pos += 3;
     }
 
@@ -174,6 +260,66 @@ impl Help {
       self.total_rows = self.current_row;
     }
 
-    nc::prefresh(self.pad, self.cur_y, 0, 2, 0, self.start_y + self.max_y, self.max_x);
+    // Highlight found items
+    if let Some(ref m) = self.matcher {
+      for (row, line) in self.lines.iter().enumerate() {
+        if Some(row) == self.found_row {
+          s.set_row_style(row as i32, Style::color(COLOR_PAIR_MAIN_WINDOW_HIGHLIGHT).reverse());
+        } else if m.matches(line) {
+          s.set_row_style(row as i32, Style::default().underline());
+        }
+      }
+    }
   }
 }
+
+#[test]
+fn test_print_help() {
+  use crate::render::CellBuffer;
+
+  let mut help = Help::new(&Config::new());
+  let mut buf = CellBuffer::new(12, 60);
+  help.print(&mut buf, 12);
+  let expected = [
+    "",
+    "  Keys - Movement",
+    "",
+    "    Up                  : Move cursor up",
+    "    Down                : Move cursor down",
+    "    PageUp              : Move page up",
+    "    PageDown            : Move page down",
+    "    Home                : Home",
+    "    End                 : End",
+    "",
+    "    /                   : Find item forward",
+    "    ?                   : Find item backward",
+  ];
+  assert_eq!(buf.text(), expected.join("\n"));
+  assert_eq!(buf.cell(1, 2).unwrap().style, Style::color(COLOR_PAIR_COLOR2).bold());
+  assert_eq!(buf.cell(3, 4).unwrap().style, Style::color(COLOR_PAIR_COLOR1));
+
+  // Narrow terminal
+  let mut buf = CellBuffer::new(4, 24);
+  help.print(&mut buf, 4);
+  assert_eq!(buf.text(), "\n  Keys - Movement\n\n    Up");
+
+  // Scrolling stops at the last rows
+  help.scroll(-5);
+  assert_eq!(help.first_row(), 5);
+  help.scroll(-1000);
+  assert_eq!(help.first_row(), help.total_rows - 3);
+
+  // Found items are highlighted
+  help.scroll(1000);
+  help.set_matcher(Some(Matcher::new("page", false, false).unwrap()));
+  assert!(help.find(true, FindMode::Normal));
+  assert_eq!(help.first_row(), 5);
+  let mut buf = CellBuffer::new(8, 60);
+  help.print(&mut buf, 8);
+  assert_eq!(
+    buf.cell(5, 0).unwrap().style,
+    Style::color(COLOR_PAIR_MAIN_WINDOW_HIGHLIGHT).reverse()
+  );
+  assert!(buf.cell(6, 0).unwrap().style.underline);
+  assert!(!buf.cell(7, 0).unwrap().style.underline);
+}
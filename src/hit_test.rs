@@ -0,0 +1,119 @@
+use crate::format::SongProperty;
+use crate::model::SongId;
+use crate::view::ActiveWindow;
+
+/// What a clickable region of the screen stands for.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Target {
+  /// Row of the playlist, with the song it shows.
+  Song { row: u32, id: Option<SongId> },
+  /// Column header of the playlist, sorting the displayed rows by its property.
+  ColumnHeader(SongProperty),
+  /// Tab of the header, showing a screen.
+  Screen(ActiveWindow),
+  /// Volume level.
+  Volume,
+  /// Progressbar of the current song.
+  Progress,
+  /// Scrollable content of the main window (e.g. the help text).
+  Content,
+}
+
+/// Part of the screen drawn by a single widget, whose regions are replaced
+/// when it is redrawn.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Area {
+  Header,
+  /// Pane of the main window, given its index.
+  Pane(usize),
+  Progressbar,
+}
+
+/// Rectangle of the screen (in screen coordinates) and its target.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Region {
+  pub area: Area,
+  pub y: i32,
+  pub x: i32,
+  pub height: i32,
+  pub width: i32,
+  pub target: Target,
+}
+
+impl Region {
+  fn contains(&self, y: i32, x: i32) -> bool {
+    y >= self.y && y < self.y + self.height && x >= self.x && x < self.x + self.width
+  }
+}
+
+/// Result of a hit test.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Hit {
+  pub area: Area,
+  pub target: Target,
+  /// Horizontal position of the hit in its region, from 0 to 1.
+  pub fraction: f32,
+}
+
+/// Clickable regions of the screen, registered by the widgets as they are
+/// drawn.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct HitMap {
+  regions: Vec<Region>,
+}
+
+impl HitMap {
+  pub fn new() -> HitMap {
+    HitMap { regions: Vec::new() }
+  }
+
+  /// Forget the regions of an area, before redrawing it.
+  pub fn clear(&mut self, area: Area) {
+    self.regions.retain(|r| r.area != area);
+  }
+
+  /// Register a region of an area.
+  pub fn add(&mut self, area: Area, y: i32, x: i32, height: i32, width: i32, target: Target) {
+    if height > 0 && width > 0 {
+      self.regions.push(Region {
+        area: area,
+        y: y,
+        x: x,
+        height: height,
+        width: width,
+        target: target,
+      });
+    }
+  }
+
+  /// Target at a given position of the screen. Regions registered last (e.g.
+  /// rows above the content of a window) take precedence.
+  pub fn hit(&self, y: i32, x: i32) -> Option<Hit> {
+    self.regions.iter().rev().find(|r| r.contains(y, x)).map(|r| Hit {
+      area: r.area,
+      target: r.target.clone(),
+      fraction: (x - r.x) as f32 / r.width as f32,
+    })
+  }
+}
+
+#[test]
+fn test_hit_map() {
+  let mut map = HitMap::new();
+  map.add(Area::Pane(0), 2, 0, 10, 80, Target::Content);
+  map.add(Area::Pane(0), 4, 0, 1, 80, Target::Song { row: 7, id: None });
+  map.add(Area::Progressbar, 12, 0, 1, 80, Target::Progress);
+  map.add(Area::Header, 0, 70, 1, 10, Target::Volume);
+
+  assert_eq!(map.hit(4, 10).map(|h| h.target), Some(Target::Song { row: 7, id: None }));
+  assert_eq!(map.hit(5, 10).map(|h| (h.area, h.target)), Some((Area::Pane(0), Target::Content)));
+  assert_eq!(map.hit(12, 20).map(|h| h.fraction), Some(0.25));
+  assert_eq!(map.hit(0, 75).map(|h| (h.target, h.fraction)), Some((Target::Volume, 0.5)));
+  assert_eq!(map.hit(0, 10), None);
+  assert_eq!(map.hit(13, 0), None);
+
+  // Redrawn areas forget their previous regions
+  map.clear(Area::Pane(0));
+  assert_eq!(map.hit(4, 10), None);
+  assert_eq!(map.hit(12, 20).map(|h| h.target), Some(Target::Progress));
+}
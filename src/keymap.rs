@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+/// Trie of key sequences.
+pub struct KeyTrie<T> {
+  value: Option<T>,
+  children: HashMap<i32, KeyTrie<T>>,
+}
+
+/// Result of the lookup of a key sequence.
+#[derive(Debug, PartialEq)]
+pub enum KeyLookup<'a, T: 'a> {
+  /// No binding starts with these keys.
+  None,
+  /// The keys are the start of longer sequences only.
+  Prefix,
+  /// The keys are bound, and are not the start of a longer sequence.
+  Match(&'a T),
+  /// The keys are bound, but are also the start of longer sequences.
+  Ambiguous(&'a T),
+}
+
+impl<T> KeyTrie<T> {
+  pub fn new() -> KeyTrie<T> {
+    KeyTrie {
+      value: None,
+      children: HashMap::new(),
+    }
+  }
+
+  /// Bind a value to a key sequence, replacing the previous one.
+  pub fn insert(&mut self, keys: &[i32], value: T) {
+    let mut node = self;
+    for key in keys {
+      node = node.children.entry(*key).or_insert_with(KeyTrie::new);
+    }
+    node.value = Some(value);
+  }
+
+  fn find(&self, keys: &[i32]) -> Option<&KeyTrie<T>> {
+    let mut node = self;
+    for key in keys {
+      node = node.children.get(key)?;
+    }
+    return Some(node);
+  }
+
+  /// Value bound to a key sequence, if any.
+  pub fn get(&self, keys: &[i32]) -> Option<&T> {
+    self.find(keys).and_then(|node| node.value.as_ref())
+  }
+
+  /// Whether some longer key sequences start with the given keys.
+  pub fn is_prefix(&self, keys: &[i32]) -> bool {
+    self.find(keys).map_or(false, |node| !node.children.is_empty())
+  }
+}
+
+impl<T> Default for KeyTrie<T> {
+  fn default() -> KeyTrie<T> {
+    KeyTrie::new()
+  }
+}
+
+/// Key bindings: bindings of a given screen take precedence over the global
+/// ones.
+pub struct Keymap<T> {
+  global: KeyTrie<T>,
+  screens: HashMap<String, KeyTrie<T>>,
+}
+
+impl<T> Keymap<T> {
+  pub fn new() -> Keymap<T> {
+    Keymap {
+      global: KeyTrie::new(),
+      screens: HashMap::new(),
+    }
+  }
+
+  /// Bind a value to a key sequence, for a given screen or for all screens.
+  pub fn insert(&mut self, screen: Option<&str>, keys: &[i32], value: T) {
+    match screen {
+      None => self.global.insert(keys, value),
+      Some(s) => self.screens.entry(String::from(s)).or_insert_with(KeyTrie::new).insert(keys, value),
+    }
+  }
+
+  /// Value bound to a key sequence, typed while the given screen is active.
+  fn get(&self, screen: &str, keys: &[i32]) -> Option<&T> {
+    self.screens.get(screen).and_then(|t| t.get(keys)).or_else(|| self.global.get(keys))
+  }
+
+  /// Look up a key sequence, typed while the given screen is active.
+  pub fn lookup(&self, screen: &str, keys: &[i32]) -> KeyLookup<'_, T> {
+    let local = self.screens.get(screen);
+    let value = self.get(screen, keys);
+    let is_prefix = local.map_or(false, |t| t.is_prefix(keys)) || self.global.is_prefix(keys);
+
+    return match (value, is_prefix) {
+      (None, false) => KeyLookup::None,
+      (None, true) => KeyLookup::Prefix,
+      (Some(v), false) => KeyLookup::Match(v),
+      (Some(v), true) => KeyLookup::Ambiguous(v),
+    };
+  }
+
+  /// Longest start of a key sequence that is bound, with its number of keys.
+  pub fn longest_match(&self, screen: &str, keys: &[i32]) -> Option<(usize, &T)> {
+    return (1..=keys.len()).rev().find_map(|n| self.get(screen, &keys[..n]).map(|v| (n, v)));
+  }
+}
+
+impl<T> Default for Keymap<T> {
+  fn default() -> Keymap<T> {
+    Keymap::new()
+  }
+}
+
+#[test]
+fn test_key_trie() {
+  let mut trie = KeyTrie::new();
+  trie.insert(&['g' as i32, 'g' as i32], 1);
+  trie.insert(&['g' as i32], 2);
+  trie.insert(&['x' as i32], 3);
+
+  assert_eq!(trie.get(&['g' as i32, 'g' as i32]), Some(&1));
+  assert_eq!(trie.get(&['g' as i32]), Some(&2));
+  assert_eq!(trie.get(&['g' as i32, 'x' as i32]), None);
+  assert!(trie.is_prefix(&['g' as i32]));
+  assert!(!trie.is_prefix(&['x' as i32]));
+  assert!(!trie.is_prefix(&['y' as i32]));
+}
+
+#[test]
+fn test_keymap_screens() {
+  let mut keymap = Keymap::new();
+  keymap.insert(None, &['d' as i32], "global");
+  keymap.insert(None, &['c' as i32, 'c' as i32], "global sequence");
+  keymap.insert(Some("playlist"), &['d' as i32, 'd' as i32], "playlist sequence");
+  keymap.insert(Some("playlist"), &['x' as i32], "playlist");
+
+  assert_eq!(keymap.lookup("help", &['d' as i32]), KeyLookup::Match(&"global"));
+  assert_eq!(keymap.lookup("playlist", &['d' as i32]), KeyLookup::Ambiguous(&"global"));
+  assert_eq!(
+    keymap.lookup("playlist", &['d' as i32, 'd' as i32]),
+    KeyLookup::Match(&"playlist sequence")
+  );
+  assert_eq!(keymap.lookup("playlist", &['c' as i32]), KeyLookup::Prefix);
+  assert_eq!(keymap.lookup("playlist", &['x' as i32]), KeyLookup::Match(&"playlist"));
+  assert_eq!(keymap.lookup("help", &['x' as i32]), KeyLookup::None);
+
+  // Sequences that were not completed
+  let keys = ['d' as i32, 'x' as i32];
+  assert_eq!(keymap.lookup("playlist", &keys), KeyLookup::None);
+  assert_eq!(keymap.longest_match("playlist", &keys), Some((1, &"global")));
+  assert_eq!(
+    keymap.longest_match("playlist", &['d' as i32, 'd' as i32, 'x' as i32]),
+    Some((2, &"playlist sequence"))
+  );
+  assert_eq!(keymap.longest_match("playlist", &['c' as i32, 'x' as i32]), None);
+}
@@ -0,0 +1,71 @@
+/// Rectangle of the screen.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Rect {
+  pub y: i32,
+  pub x: i32,
+  pub height: i32,
+  pub width: i32,
+}
+
+/// Arrangement of the panes sharing the main window, each pane showing a
+/// screen.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Layout {
+  /// Single pane, given its index.
+  Pane(usize),
+  /// Two layouts side by side, the left one taking a percentage of the width.
+  Columns(Box<Layout>, Box<Layout>, i32),
+}
+
+impl Layout {
+  /// Locked screen (pane 0) on the left of the slave screen (pane 1).
+  pub fn locked(width_part: i32) -> Layout {
+    Layout::Columns(Box::new(Layout::Pane(0)), Box::new(Layout::Pane(1)), width_part)
+  }
+
+  /// Rectangles of the panes, given the one of the whole layout.
+  pub fn rects(&self, rect: Rect) -> Vec<(usize, Rect)> {
+    match self {
+      Layout::Pane(idx) => vec![(*idx, rect)],
+      Layout::Columns(left, right, part) => {
+        // Both sides keep at least one column
+        let left_width = (rect.width * part / 100).min(rect.width - 1).max(1);
+        let left_rect = Rect { width: left_width, ..rect };
+        let right_rect = Rect {
+          x: rect.x + left_width,
+          width: (rect.width - left_width).max(1),
+          ..rect
+        };
+        let mut rects = left.rects(left_rect);
+        rects.extend(right.rects(right_rect));
+        rects
+      }
+    }
+  }
+}
+
+#[test]
+fn test_layout_rects() {
+  let rect = Rect {
+    y: 2,
+    x: 0,
+    height: 20,
+    width: 81,
+  };
+  assert_eq!(Layout::Pane(0).rects(rect), vec![(0, rect)]);
+
+  let layout = Layout::locked(30);
+  assert_eq!(
+    layout.rects(rect),
+    vec![(0, Rect { width: 24, ..rect }), (1, Rect { x: 24, width: 57, ..rect })]
+  );
+
+  // Nested layouts split the remaining width
+  let layout = Layout::Columns(Box::new(Layout::Pane(2)), Box::new(Layout::locked(50)), 20);
+  let widths = layout.rects(rect).iter().map(|&(i, r)| (i, r.x, r.width)).collect::<Vec<_>>();
+  assert_eq!(widths, vec![(2, 0, 16), (0, 16, 32), (1, 48, 33)]);
+
+  // Tiny terminals
+  let narrow = Rect { width: 1, ..rect };
+  assert!(layout.rects(narrow).iter().all(|&(_, r)| r.width == 1));
+}
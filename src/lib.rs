@@ -13,28 +13,41 @@ extern crate lazy_static;
 
 extern crate chrono;
 extern crate getopts;
-extern crate ini;
 extern crate mpd;
 extern crate ncurses;
+extern crate signal_hook;
 extern crate time;
+extern crate unicode_segmentation;
+extern crate unicode_width;
 
 pub mod action;
 pub mod cli;
+pub mod command;
+pub mod command_line;
 pub mod config;
 pub mod constants;
 pub mod controller;
+pub mod find;
 pub mod format;
 pub mod help;
+pub mod hit_test;
+pub mod keymap;
+pub mod layout;
 pub mod model;
 pub mod parser;
+pub mod queue_rows;
+pub mod random_items;
+pub mod render;
+pub mod screen;
 pub mod server_info;
+pub mod text;
 pub mod view;
 
 pub use crate::cli::process_cli;
-pub use crate::config::{ConfigLoader, ParamConfig};
+pub use crate::config::{ColorConfig, ConfigLoader, Diagnostic, ParamConfig, Severity};
 pub use crate::constants::Color;
 pub use crate::controller::{ControlQuery, Controller};
 pub use crate::format::{Column, SongProperty};
 pub use crate::model::Model;
-pub use crate::parser::parse_bindings_configuration;
+pub use crate::parser::{parse_bindings_configuration, ActionDef, Binding, BindingTarget};
 pub use crate::view::View;
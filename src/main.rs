@@ -40,6 +40,7 @@ fn main() {
       let mut m = shared_model.lock().unwrap();
       m.take_snapshot();
       m.update_header();
+      m.update_window_title();
       m.update_stateline();
       m.update_main_window();
       m.update_progressbar();
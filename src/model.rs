@@ -1,19 +1,31 @@
 extern crate lazy_static;
 
 extern crate mpd;
+extern crate ncurses;
 extern crate time;
 
-use crate::action::Action;
+use crate::action::{build_chain, execute_chain, Action};
+use crate::command::*;
+use crate::command_line::*;
 use crate::config::*;
+use crate::find::*;
 use crate::format::*;
-use crate::util::{CachedValue, TimedValue};
+use crate::queue_rows::{QueueRow, QueueRows};
+use crate::random_items::RandomItems;
+use crate::screen::{get_screen, SCREEN_ACTIONS};
+use crate::util::{CachedValue, Rng, TimedValue};
+use mpd::search::{Query, Term};
 use mpd::song::Song;
 use mpd::status::{State, Status};
+use ncurses as nc;
+use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap};
 use std::net::TcpStream;
 use std::process;
+use std::process::Stdio;
 use std::sync::{Arc, Mutex};
-use time::{get_time, Duration};
+use std::thread;
+use time::{get_time, Duration, Timespec};
 
 use crate::view::*;
 
@@ -23,7 +35,17 @@ pub type SharedModel<'m> = Arc<Mutex<Model<'m>>>;
 lazy_static! {
   pub static ref ACTION_DESCRIPTION: BTreeMap<&'static str, &'static str> = {
     let mut m = BTreeMap::new();
+    m.insert("add_random_items", "Add random songs, artists or albums to the playlist");
+    m.insert("apply_filter", "Show the songs matching a pattern");
+    m.insert("dummy", "Do nothing");
     m.insert("execute_command", "Execute a command");
+    m.insert("find", "Find text");
+    m.insert("find_item_backward", "Find item backward");
+    m.insert("find_item_forward", "Find item forward");
+    m.insert("jump_to_playing_song", "Select the playing song");
+    m.insert("master_screen", "Focus the locked screen");
+    m.insert("next_found_item", "Jump to the next found item");
+    m.insert("next_screen", "Show the next screen (see screen_switcher_mode)");
     m.insert("playlist_play", "Play the playlist");
     m.insert("playlist_pause", "Pause the playlist");
     m.insert("playlist_stop", "Stop the playlist");
@@ -32,9 +54,18 @@ lazy_static! {
     m.insert("playlist_previous", "Play the playlist's previous song");
     m.insert("playlist_next", "Play the playlist's next song");
     m.insert("play_selected", "Play the selected song");
+    m.insert("previous_found_item", "Jump to the previous found item");
+    m.insert("previous_screen", "Show the previous screen (see screen_switcher_mode)");
     m.insert("process_mouse", "Process mouse events");
+    m.insert("push_character", "Push a character to the input queue");
+    m.insert("push_characters", "Push characters to the input queue");
+    m.insert("reload_config", "Reload the configuration files");
+    m.insert("require_runnable", "Stop unless the given actions can be run");
+    m.insert("require_screen", "Stop unless the given screen is active");
     m.insert("resize_windows", "Resize the windows");
+    m.insert("run_external_command", "Run an external command");
     m.insert("scroll_down", "Scroll down in a list");
+    m.insert("set_selected_items_priority", "Set the priority of the selected songs");
     m.insert("scroll_up", "Scroll up in a list");
     m.insert("page_down", "Page down in a list");
     m.insert("page_up", "Page up in a list");
@@ -43,15 +74,32 @@ lazy_static! {
     m.insert("show_help", "Show the help view");
     m.insert("show_playlist", "Show the playlist view");
     m.insert("show_server_info", "Show the MPD server information");
+    m.insert("slave_screen", "Focus the screen shown next to the locked one");
     m.insert("toggle_bitrate_visibility", "Toggle the bitrate visibility");
+    m.insert("toggle_find_mode", "Toggle the find mode (normal/wrapped)");
+    m.insert("toggle_interface", "Switch between the classic and alternative interfaces");
+    m.insert("toggle_mouse", "Toggle the mouse support");
+    m.insert("toggle_playing_song_centering", "Toggle following the playing song");
     m.insert("toggle_random", "Toggle the \"random\" mode");
     m.insert("toggle_repeat", "Toggle the \"repeat\" mode");
+    m.insert("toggle_screen_lock", "Lock the current screen, showing other screens next to it");
     m.insert("volume_down", "Lower the volume");
     m.insert("volume_up", "Raise the volume");
     m
   };
 }
 
+/// Maximum time (in milliseconds) between the two clicks of a double click.
+const DOUBLE_CLICK_INTERVAL: i64 = 300;
+
+/// Delays (in milliseconds) between two attempts to connect again to the
+/// server, doubled after each failure.
+const RECONNECT_DELAY_MIN: i64 = 250;
+const RECONNECT_DELAY_MAX: i64 = 8000;
+
+/// Tags that can be given to the "add" command.
+const SEARCH_TAGS: &[&str] = &["album", "albumartist", "any", "artist", "composer", "date", "genre", "title"];
+
 fn start_client(config: &Config) -> Result<mpd::Client, mpd::error::Error> {
   mpd::Client::connect(config.socket_addr())
 }
@@ -61,9 +109,13 @@ fn get_song_info(song: &Song, tag: &SongProperty) -> String {
     SongProperty::Title => {
       return match song.title.as_ref() {
         Some(t) => t.clone(),
-        None => String::from("unknown"),
+        None => String::default(),
       }
     }
+    SongProperty::Filename => {
+      // Name of the file, without its directory
+      return String::from(song.file.rsplit('/').next().unwrap_or_default());
+    }
     SongProperty::Length => {
       let (min, sec) = match song.duration {
         Some(d) => (d.num_minutes(), d.num_seconds() % 60),
@@ -78,6 +130,12 @@ fn get_song_info(song: &Song, tag: &SongProperty) -> String {
       };
       return format!("{:>02}", track);
     }
+    SongProperty::Priority => {
+      return match song.place {
+        Some(ref p) => format!("{}", p.prio),
+        None => String::default(),
+      }
+    }
     SongProperty::TrackFull => {
       let track = get_song_info(song, &SongProperty::Track);
       let total = "12";
@@ -88,21 +146,52 @@ fn get_song_info(song: &Song, tag: &SongProperty) -> String {
       let tag_s = format!("{}", tag);
       return match song.tags.get(tag_s.as_str()) {
         Some(t) => t.clone(),
-        None => String::from("unknown"),
+        None => String::default(),
       };
     }
   }
 }
 
+fn non_empty_or(s: String, default: &str) -> String {
+  if s.is_empty() {
+    String::from(default)
+  } else {
+    s
+  }
+}
+
 fn get_song_time(status: &Status) -> (Duration, Duration) {
   status.time.unwrap_or((Duration::seconds(0), Duration::seconds(0)))
 }
 
+/// Format the time of the playing song for the statusbar, e.g. "[1:02/9:22]",
+/// or "[-8:20/9:22]" with the remaining time. Only the elapsed time is shown
+/// for songs of unknown duration (e.g. streams).
+fn format_song_time(elapsed: Duration, total: Duration, remaining: bool) -> String {
+  let min_sec = |d: Duration| format!("{}:{:02}", d.num_minutes(), d.num_seconds() % 60);
+  if total <= Duration::zero() {
+    return format!("[{}]", min_sec(elapsed));
+  }
+  if remaining {
+    let left = (total - elapsed).max(Duration::zero());
+    return format!("[-{}/{}]", min_sec(left), min_sec(total));
+  }
+  return format!("[{}/{}]", min_sec(elapsed), min_sec(total));
+}
+
 /// Get a song's (hopefully unique) id.
 fn get_song_id(song: &mpd::Song) -> Option<SongId> {
   return song.place.as_ref().map(|p| SongId::from(p.id));
 }
 
+/// Key used to sort songs by a column: lengths are compared as durations.
+fn sort_key(song: &Song, property: &SongProperty) -> (i64, String) {
+  match *property {
+    SongProperty::Length => (song.duration.map_or(0, |d| d.num_seconds()), String::new()),
+    _ => (0, get_song_info(song, property)),
+  }
+}
+
 fn get_song_bitrate(status: &Status) -> u32 {
   status.bitrate.unwrap_or(0u32)
 }
@@ -111,9 +200,13 @@ fn get_song_bitrate(status: &Status) -> u32 {
 macro_rules! register_actions(
   ($($fun:ident), *) => (
     $(
-      pub fn $fun(model: &mut Model)
+      pub fn $fun(model: &mut Model, _args: &[String]) -> bool
       {
+        if !model.can_run(stringify!($fun)) {
+          return false;
+        }
         model.$fun();
+        return true;
       }
     )*
   )
@@ -121,7 +214,15 @@ macro_rules! register_actions(
 
 // Register actions for closures
 register_actions!(
+  apply_filter,
   execute_command,
+  find,
+  find_item_backward,
+  find_item_forward,
+  jump_to_playing_song,
+  master_screen,
+  next_found_item,
+  next_screen,
   playlist_play,
   playlist_pause,
   playlist_stop,
@@ -130,7 +231,10 @@ register_actions!(
   playlist_previous,
   playlist_next,
   play_selected,
+  previous_found_item,
+  previous_screen,
   process_mouse,
+  reload_config,
   resize_windows,
   scroll_down,
   scroll_up,
@@ -138,16 +242,125 @@ register_actions!(
   page_up,
   move_home,
   move_end,
-  show_help,
-  show_playlist,
-  show_server_info,
+  slave_screen,
   toggle_bitrate_visibility,
+  toggle_find_mode,
+  toggle_interface,
+  toggle_mouse,
+  toggle_playing_song_centering,
   toggle_random,
   toggle_repeat,
+  toggle_screen_lock,
   volume_down,
   volume_up
 );
 
+// Actions showing a screen
+macro_rules! register_screen_actions(
+  ($($fun:ident => $window:expr), *) => (
+    $(
+      pub fn $fun(model: &mut Model, _args: &[String]) -> bool
+      {
+        model.show_screen($window);
+        return true;
+      }
+    )*
+  )
+);
+
+register_screen_actions!(
+  show_help => ActiveWindow::Help,
+  show_playlist => ActiveWindow::Playlist,
+  show_server_info => ActiveWindow::ServerInfo
+);
+
+/// Do nothing (e.g. to disable a default binding).
+pub fn dummy(_model: &mut Model, _args: &[String]) -> bool {
+  return true;
+}
+
+/// Guard: stop the chain unless one of the given screens is active.
+pub fn require_screen(model: &mut Model, args: &[String]) -> bool {
+  return args.iter().any(|screen| model.is_screen_active(screen));
+}
+
+/// Guard: stop the chain unless all the given actions can be run.
+pub fn require_runnable(model: &mut Model, args: &[String]) -> bool {
+  return args.iter().all(|action| model.can_run(action));
+}
+
+pub fn run_external_command(model: &mut Model, args: &[String]) -> bool {
+  for command in args {
+    model.run_external_command(command);
+  }
+  return true;
+}
+
+/// Add random items to the playlist: `add_random_items [count] [mode] [seed]`.
+/// Missing arguments are asked for.
+pub fn add_random_items(model: &mut Model, args: &[String]) -> bool {
+  if args.is_empty() {
+    model.ask_random_items_count();
+    return true;
+  }
+
+  let count = match args[0].parse::<usize>() {
+    Ok(c) => c,
+    Err(_) => {
+      model.update_message(format!("Error: invalid number of items \"{}\"", args[0]).as_str());
+      return false;
+    }
+  };
+  let seed = match args.get(2).map(|s| s.parse::<u64>()) {
+    Some(Ok(s)) => Some(s),
+    Some(Err(_)) => {
+      model.update_message(format!("Error: invalid seed \"{}\"", args[2]).as_str());
+      return false;
+    }
+    None => None,
+  };
+  match args.get(1) {
+    Some(mode) => model.add_random_items(count, mode, seed),
+    None => model.ask_random_items_mode(count),
+  }
+  return true;
+}
+
+/// Set the priority of the selected songs to the given value, or ask for it.
+pub fn set_selected_items_priority(model: &mut Model, args: &[String]) -> bool {
+  if !model.can_run("set_selected_items_priority") {
+    return false;
+  }
+  match args.first() {
+    Some(prio) => model.set_priority(prio),
+    None => model.ask_priority(),
+  }
+  return true;
+}
+
+pub fn push_character(model: &mut Model, args: &[String]) -> bool {
+  push_characters(model, args)
+}
+
+pub fn push_characters(_model: &mut Model, args: &[String]) -> bool {
+  // Characters are read back in the reverse order
+  for c in args.concat().chars().rev() {
+    nc::ungetch(c as i32);
+  }
+  return true;
+}
+
+/// Whether an action uses the count typed before it (e.g. "5j"), rather than
+/// being repeated.
+pub fn accepts_count(action: &str) -> bool {
+  match action {
+    "scroll_down" | "scroll_up" | "page_down" | "page_up" => true,
+    "volume_down" | "volume_up" => true,
+    "playlist_next" | "playlist_previous" => true,
+    _ => false,
+  }
+}
+
 macro_rules! actions_to_map(
     ($($fun:ident), *) => (
       {
@@ -164,7 +377,17 @@ macro_rules! actions_to_map(
 
 pub fn get_action_map<'m>() -> BTreeMap<String, Action<'m>> {
   let action_map = actions_to_map!(
+    add_random_items,
+    apply_filter,
+    dummy,
     execute_command,
+    find,
+    find_item_backward,
+    find_item_forward,
+    jump_to_playing_song,
+    master_screen,
+    next_found_item,
+    next_screen,
     playlist_play,
     playlist_pause,
     playlist_stop,
@@ -173,20 +396,35 @@ pub fn get_action_map<'m>() -> BTreeMap<String, Action<'m>> {
     playlist_previous,
     playlist_next,
     play_selected,
+    previous_found_item,
+    previous_screen,
     process_mouse,
+    push_character,
+    push_characters,
+    reload_config,
+    require_runnable,
+    require_screen,
     resize_windows,
+    run_external_command,
     scroll_down,
     scroll_up,
     page_down,
     page_up,
+    set_selected_items_priority,
     move_home,
     move_end,
     show_help,
     show_playlist,
     show_server_info,
+    slave_screen,
     toggle_bitrate_visibility,
+    toggle_find_mode,
+    toggle_interface,
+    toggle_mouse,
+    toggle_playing_song_centering,
     toggle_random,
     toggle_repeat,
+    toggle_screen_lock,
     volume_down,
     volume_up
   );
@@ -194,6 +432,40 @@ pub fn get_action_map<'m>() -> BTreeMap<String, Action<'m>> {
   return action_map;
 }
 
+/// Purpose of the line typed in the command line.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Prompt {
+  /// Command to run.
+  Command,
+  /// Pattern to find, forward or backward.
+  Find(bool),
+  /// Pattern of the songs to show.
+  Filter,
+  /// Priority of the selected songs.
+  Priority,
+  /// Number of random items to add.
+  RandomCount,
+  /// Kind of random items to add, given their number.
+  RandomMode(usize),
+  /// Width of the screen to lock (in %).
+  LockedScreenWidth,
+}
+
+impl Prompt {
+  /// Name of the history of lines typed for this prompt.
+  fn history_name(self) -> &'static str {
+    match self {
+      Prompt::Command => "command",
+      Prompt::Find(_) => "find",
+      Prompt::Filter => "filter",
+      Prompt::Priority => "priority",
+      Prompt::RandomCount => "random_count",
+      Prompt::RandomMode(_) => "random_mode",
+      Prompt::LockedScreenWidth => "locked_screen_width",
+    }
+  }
+}
+
 /// Structure containing the status of changes to the model's data.
 #[derive(Default)]
 pub struct DataChangeStatus {
@@ -226,6 +498,27 @@ impl From<mpd::Id> for SongId {
   }
 }
 
+impl From<SongId> for mpd::Id {
+  fn from(id: SongId) -> Self {
+    mpd::Id(id.0)
+  }
+}
+
+/// Time left until the end of the queue: the rest of the playing song and the
+/// songs after it.
+fn remaining_time(queue: &[Song], status: &Status) -> Duration {
+  let pos = match status.song {
+    Some(place) => place.pos as usize,
+    None => return Duration::zero(),
+  };
+  let (elapsed, _) = get_song_time(status);
+  let total = queue
+    .iter()
+    .skip(pos)
+    .fold(Duration::zero(), |sum, song| sum + song.duration.unwrap_or_else(Duration::zero));
+  return (total - elapsed).max(Duration::zero());
+}
+
 /// Structure containing the current MPD data.
 struct Snapshot {
   /// Current MPD status.
@@ -245,9 +538,9 @@ impl Snapshot {
     }
   }
 
-  pub fn update(&mut self, client: &mut mpd::Client, change: &DataChangeStatus) {
+  pub fn update(&mut self, client: &mut mpd::Client, change: &DataChangeStatus) -> Result<(), mpd::error::Error> {
     if change.playlist {
-      self.queue.get_or(|| client.queue().unwrap());
+      self.queue.try_get_or(|| client.queue())?;
       self.pl_info.size = (*self.queue).len() as u32;
       let sum = (*self.queue).iter().fold(0i64, |sum, val| {
         sum + val.duration.unwrap_or_else(|| Duration::seconds(0)).num_seconds()
@@ -256,22 +549,31 @@ impl Snapshot {
     }
 
     if change.status {
-      self.status = client.status().unwrap();
+      self.status = client.status()?;
+    }
+
+    if change.playlist || change.status {
+      self.pl_info.remaining = remaining_time(&self.queue, &self.status);
     }
+    return Ok(());
   }
 }
 
 pub struct Model<'m> {
   /// MPD client.
   client: mpd::Client<TcpStream>,
+  /// Time of the next attempt to connect again, while the connection is lost.
+  reconnect_time: Option<Timespec>,
+  /// Delay before the attempt following the next one.
+  reconnect_delay: Duration,
   /// TUI view.
   view: &'m mut View,
-  /// Initial configuration.
-  config: &'m Config,
+  /// Loaded configuration.
+  config: Config,
   /// Current state configuration.
   params: ParamConfig,
-  /// Current active window.
-  active_window: ActiveWindow,
+  /// Current colors (may be changed by loading a theme).
+  colors: ColorConfig,
   /// Index of the currently selected song (if any).
   selected_song: Option<TimedValue<u32>>,
   /// Snapshot of MPD data.
@@ -284,10 +586,51 @@ pub struct Model<'m> {
   change_status: DataChangeStatus,
   /// Cache of song infos, given their ids.
   song_info_map: HashMap<(SongId, SongProperty), String>,
+  /// Whether a configuration reload was requested.
+  reload_requested: bool,
+  /// Options changed while running (e.g. with `:set` or `:theme`), in the
+  /// order they were changed.
+  overrides: Vec<(String, String)>,
+  /// Keys of the key sequence being typed.
+  pending_keys: String,
+  /// Title last set on the terminal window.
+  window_title: Option<String>,
+  /// Line editor used to type commands.
+  command_line: CommandLine,
+  /// What the line being typed is for.
+  prompt: Prompt,
+  /// Histories of the prompts other than the current one, by name.
+  prompt_histories: HashMap<&'static str, Vec<String>>,
+  /// Pattern of the items to find.
+  matcher: Option<Matcher>,
+  /// Current find mode (wrapped or normal).
+  find_mode: FindMode,
+  /// Pattern of the songs shown in the playlist.
+  filter: Option<Matcher>,
+  /// Filter applied before typing a new one.
+  previous_filter: Option<Matcher>,
+  /// Column the displayed playlist is sorted by, if not in queue order.
+  sort: Option<SongProperty>,
+  /// Rows of the displayed playlist.
+  rows: QueueRows,
+  /// First displayed row of the playlist.
+  playlist_start: usize,
+  /// Whether the playlist is centered on the selected row at the next update.
+  center_selection: bool,
+  /// Playing song at the last playlist update.
+  playing_id: Option<SongId>,
+  /// Whether the playlist was already displayed.
+  playlist_shown: bool,
+  /// Screen focused before the current one.
+  previous_window: Option<ActiveWindow>,
+  /// Row of the last left click, to detect double clicks.
+  last_click: Option<TimedValue<u32>>,
+  /// Count given to the current action (e.g. 5 in "5j").
+  count: u32,
 }
 
 impl<'m> Model<'m> {
-  pub fn new(view: &'m mut View, config: &'m Config) -> Model<'m> {
+  pub fn new(view: &'m mut View, config: &Config) -> Model<'m> {
     // Instantiate client.
     let res = start_client(config);
     if res.is_err() {
@@ -298,19 +641,95 @@ impl<'m> Model<'m> {
     let mut snapshot = Snapshot::new();
     snapshot.queue.set(client.queue().unwrap());
 
-    Model {
+    let mut command_line = CommandLine::new();
+    if let Some(path) = history_path() {
+      command_line.set_history(load_history(&path));
+    }
+
+    let model = Model {
       client: client,
+      reconnect_time: None,
+      reconnect_delay: Duration::milliseconds(RECONNECT_DELAY_MIN),
       view: view,
-      config: config,
+      config: config.clone(),
       params: config.params.clone(),
-      active_window: ActiveWindow::Playlist,
+      colors: config.colors,
       selected_song: None,
       snapshot: snapshot,
       info_msg: None,
       action_map: get_action_map(),
       change_status: DataChangeStatus::new(),
       song_info_map: HashMap::default(),
+      reload_requested: false,
+      overrides: Vec::new(),
+      pending_keys: String::new(),
+      window_title: None,
+      command_line: command_line,
+      prompt: Prompt::Command,
+      prompt_histories: HashMap::default(),
+      matcher: None,
+      find_mode: config.params.default_find_mode,
+      filter: None,
+      previous_filter: None,
+      sort: None,
+      rows: QueueRows::new(),
+      playlist_start: 0,
+      center_selection: false,
+      playing_id: None,
+      playlist_shown: false,
+      previous_window: None,
+      last_click: None,
+      count: 1,
+    };
+    model.view.show_screen(config.params.startup_screen);
+
+    // Show the startup slave screen next to the locked startup screen
+    let params = &config.params;
+    if let Some(screen) = params.startup_slave_screen.filter(|s| *s != model.active_window()) {
+      model.view.lock_screen(params.locked_screen_width_part);
+      model.view.show_screen(screen);
+      if !params.startup_slave_screen_focus {
+        model.view.focus_pane(0);
+      }
     }
+    return model;
+  }
+
+  /// Request a reload of the configuration files.
+  pub fn reload_config(&mut self) {
+    self.reload_requested = true;
+  }
+
+  /// Whether a configuration reload was requested (resets the request).
+  pub fn take_reload_request(&mut self) -> bool {
+    let requested = self.reload_requested;
+    self.reload_requested = false;
+    return requested;
+  }
+
+  /// Apply a new configuration, while keeping the connection to the server and
+  /// the state of the UI. Options changed while running take precedence over
+  /// the ones of the configuration files.
+  pub fn set_config(&mut self, mut config: Config) {
+    // Keep the current server
+    config.params.mpd_host = self.config.params.mpd_host.clone();
+    config.params.mpd_port = self.config.params.mpd_port;
+
+    for (key, val) in &self.overrides {
+      if key == "theme" {
+        if let Some(colors) = ConfigLoader::new().load_theme(val, &config.base_colors) {
+          config.colors = colors;
+        }
+      }
+      let _ = config.set(key, val);
+    }
+
+    self.params = config.params.clone();
+    self.colors = config.colors;
+    self.view.set_config(&config);
+    self.config = config;
+
+    self.update_message("Configuration reloaded");
   }
 
   pub fn playlist_play(&mut self) {
@@ -343,23 +762,169 @@ impl<'m> Model<'m> {
     self.change_status.status = true;
   }
 
-  pub fn read_input_command(&mut self) -> String {
-    return self.view.read_input_command();
+  /// Start typing a line for the given prompt, with its own history.
+  fn start_prompt(&mut self, prompt: Prompt, text: &str) {
+    let current = self.prompt.history_name();
+    let next = prompt.history_name();
+    if current != next {
+      let history = self.prompt_histories.remove(next).unwrap_or_default();
+      self.prompt_histories.insert(current, self.command_line.history().to_vec());
+      self.command_line.set_history(history);
+    }
+    self.prompt = prompt;
+    self.command_line.start(text);
   }
 
+  /// Commands typed in the command line, the most recent last.
+  pub fn command_history(&self) -> &[String] {
+    if self.prompt == Prompt::Command {
+      return self.command_line.history();
+    }
+    return self.prompt_histories.get("command").map_or(&[], |h| h.as_slice());
+  }
+
+  /// Start typing a command.
   pub fn execute_command(&mut self) {
-    let cmd = self.read_input_command();
+    self.start_prompt(Prompt::Command, ":");
+  }
+
+  pub fn is_command_line_active(&self) -> bool {
+    return self.command_line.is_active();
+  }
+
+  /// Process a key typed in the command line.
+  pub fn process_command_line_key(&mut self, ch: i32) {
+    if ch == nc::KEY_RESIZE {
+      self.resize_windows();
+      return;
+    }
+
+    match (self.command_line.process_key(ch), self.prompt) {
+      (CommandLineEvent::Submit(input), Prompt::Find(forward)) => self.find_pattern(&input, forward),
+      (CommandLineEvent::Submit(input), Prompt::Filter) => {
+        if let Err(e) = self.set_filter(&input) {
+          self.update_message(format!("Error: {}", e).as_str());
+        }
+      }
+      // The filter is updated while typing, ignoring incomplete expressions
+      (CommandLineEvent::Edited, Prompt::Filter) => {
+        let input = self.command_line.text();
+        let _ = self.set_filter(&input);
+      }
+      (CommandLineEvent::Cancel, Prompt::Filter) => self.filter = self.previous_filter.take(),
+      (CommandLineEvent::Submit(input), Prompt::Priority) => self.set_priority(&input),
+      (CommandLineEvent::Submit(input), Prompt::RandomCount) => match input.trim().parse::<usize>() {
+        Ok(count) => self.ask_random_items_mode(count),
+        Err(_) => self.update_message(format!("Error: invalid number of items \"{}\"", input).as_str()),
+      },
+      (CommandLineEvent::Submit(input), Prompt::RandomMode(count)) => self.add_random_items(count, &input, None),
+      (CommandLineEvent::Submit(input), Prompt::LockedScreenWidth) => match input.trim().parse::<i32>() {
+        Ok(part) if part >= 20 && part <= 80 => self.lock_screen(part),
+        _ => self.update_message(format!("Error: invalid width \"{}\" (expected 20-80)", input).as_str()),
+      },
+      (CommandLineEvent::Submit(input), Prompt::Command) => {
+        self.save_command_history();
+        self.run_command(&input);
+      }
+      (CommandLineEvent::Complete, Prompt::Command) => {
+        let (words, prefix) = self.command_line.completion_context();
+        let candidates = self.completion_candidates(&words, &prefix);
+        self.command_line.complete(candidates);
+      }
+      (CommandLineEvent::Edited, Prompt::Command) => {
+        // Commands in "immediate" mode are run as soon as they are typed
+        let input = self.command_line.text();
+        if self.config.keys.commands.get(&input).map_or(false, |c| c.immediate) {
+          self.command_line.process_key('\n' as i32);
+          self.save_command_history();
+          self.run_command(&input);
+        }
+      }
+      _ => {}
+    }
+    self.change_status.status = true;
+  }
+
+  /// Possible completions of a word of the command line, given the previous
+  /// words.
+  fn completion_candidates(&mut self, words: &[String], prefix: &str) -> Vec<String> {
+    let mut candidates = match (words.first().map(|w| w.as_str()), words.len()) {
+      // Command names
+      (None, _) => {
+        let mut names = self.action_map.keys().cloned().collect::<Vec<String>>();
+        names.extend(self.config.keys.commands.keys().cloned());
+        names.extend(COMMANDS.iter().map(|c| String::from(c.name)));
+        names
+      }
+      // Arguments
+      (Some("add"), 1) => SEARCH_TAGS.iter().map(|&t| String::from(t)).collect(),
+      (Some("save"), 1) => self
+        .client
+        .playlists()
+        .map(|l| l.into_iter().map(|p| p.name).collect())
+        .unwrap_or_default(),
+      (Some("set"), 1) => PARAM_KEYS.iter().map(|&k| String::from(k)).collect(),
+      (Some("set"), 2) => vec![String::from("no"), String::from("yes")],
+      (Some("require_screen"), _) => ActiveWindow::all().iter().map(|w| String::from(w.name())).collect(),
+      (Some("require_runnable"), _) => self.action_map.keys().cloned().collect(),
+      (Some("theme"), 1) => ConfigLoader::new().list_themes(),
+      (Some(_), _) => vec![],
+    };
+    candidates.retain(|c| c.starts_with(prefix));
+    candidates.sort();
+    candidates.dedup();
+    return candidates;
+  }
+
+  fn save_command_history(&mut self) {
+    if let Some(path) = history_path() {
+      save_history(&path, self.command_history());
+    }
+  }
+
+  /// Run a command typed in the command line.
+  pub fn run_command(&mut self, input: &str) {
+    // Commands defined in the bindings file
+    if let Some(actions) = self.config.keys.commands.get(input.trim()).map(|c| c.actions.clone()) {
+      match build_chain(&self.action_map, &actions) {
+        Some(chain) => {
+          execute_chain(&chain, self);
+        }
+        None => self.update_message(format!("Error: unsupported action in \"{}\"", input.trim()).as_str()),
+      }
+      return;
+    }
+
+    let words = match split_command(input) {
+      Ok(words) => words,
+      Err(e) => return self.update_message(format!("Error: {}", e).as_str()),
+    };
+
+    // Nothing typed
+    let cmd = match words.first() {
+      Some(cmd) => cmd.clone(),
+      None => return,
+    };
+
+    // Built-in commands
+    if let Some(spec) = find_command(&cmd) {
+      match spec.parse(&words[1..]) {
+        Ok(command) => self.run_builtin_command(&command),
+        Err(e) => self.update_message(format!("Error: {}: {}", cmd, e).as_str()),
+      }
+      return;
+    }
 
     // Copy action to satisfy borrow checker
     let opt_action: Option<Action<'m>> = match self.action_map.get(cmd.as_str()) {
-      Some(action) => Some(action.clone()),
+      Some(action) => Some(action.with_args(&words[1..])),
       None => None,
     };
 
     match opt_action {
       Some(ref action) => {
+        self.update_message(format!("Executing command \"{}\"", cmd).as_str());
         action.execute(self);
-        self.update_message(format!("Executing command \"{}\"", cmd).as_str())
       }
       None => self.update_message(format!("No command named \"{}\"", cmd).as_str()),
     }
@@ -369,6 +934,386 @@ impl<'m> Model<'m> {
     self.change_status.current_song = true;
   }
 
+  /// Run a built-in command, with arguments of the expected types.
+  fn run_builtin_command(&mut self, command: &Command) {
+    match command.name.as_str() {
+      "add" => self.add_matching_songs(command.word(0), command.word(1)),
+      "crossfade" => {
+        if self.client.crossfade(command.int(0)).is_err() {
+          self.update_message("Error: crossfade failed");
+        }
+      }
+      "save" => {
+        let name = command.word(0);
+        match self.client.save(name) {
+          Ok(_) => self.update_message(format!("Playlist saved as \"{}\"", name).as_str()),
+          Err(_) => self.update_message(format!("Error: cannot save playlist \"{}\"", name).as_str()),
+        }
+      }
+      "seek" => self.seek(command.time(0)),
+      "set" => self.set_option(command.word(0), command.word(1)),
+      "theme" => self.load_theme(command.word(0)),
+      "volume" => self.set_volume(command.int(0) as i8),
+      _ => {}
+    }
+
+    self.change_status.playlist = true;
+    self.change_status.status = true;
+  }
+
+  /// Add the songs of the database whose tag matches a value.
+  pub fn add_matching_songs(&mut self, tag: &str, value: &str) {
+    let term = match tag {
+      "any" => Term::Any,
+      "file" => Term::File,
+      _ => Term::Tag(Cow::Borrowed(tag)),
+    };
+    let mut query = Query::new();
+    let query = query.and(term, value);
+    if self.client.findadd(query).is_err() {
+      self.update_message(format!("Error: cannot add songs with {} \"{}\"", tag, value).as_str());
+    }
+  }
+
+  /// Seek in the current song.
+  pub fn seek(&mut self, pos: Position) {
+    let (elapsed, total) = get_song_time(&self.snapshot.status);
+    let secs = match pos {
+      Position::Absolute(s) => s,
+      Position::Relative(s) => elapsed.num_seconds() + s,
+    };
+    let secs = secs.max(0).min(total.num_seconds());
+    if self.client.rewind(Duration::seconds(secs)).is_err() {
+      self.update_message("Error: seek failed");
+    }
+  }
+
+  /// Change a configuration option while running.
+  pub fn set_option(&mut self, key: &str, val: &str) {
+    if key == "mpd_host" || key == "mpd_port" {
+      self.update_message(format!("Error: \"{}\" cannot be changed while running", key).as_str());
+      return;
+    }
+    if key == "theme" {
+      self.load_theme(val);
+      return;
+    }
+
+    // Keep the changes made while running (e.g. the theme)
+    let mut config = self.config.clone();
+    config.params = self.params.clone();
+    config.colors = self.colors;
+    match config.set(key, val) {
+      Ok(_) => {
+        let _ = self.config.set(key, val);
+        self.params = config.params.clone();
+        self.colors = config.colors;
+        self.view.set_config(&config);
+        self.add_override(key, val);
+        self.update_message(format!("{} = {}", key, val).as_str());
+      }
+      Err(e) => self.update_message(format!("Error: {}", e).as_str()),
+    }
+  }
+
+  /// Remember an option changed while running, to keep it after a reload.
+  fn add_override(&mut self, key: &str, val: &str) {
+    self.overrides.retain(|o| o.0 != key);
+    self.overrides.push((String::from(key), String::from(val)));
+  }
+
+  /// Load a color theme and repaint the screen.
+  pub fn load_theme(&mut self, name: &str) {
+    if name.is_empty() {
+      self.update_message("Error: missing theme name");
+      return;
+    }
+
+    // Themes are applied on top of the colors of the configuration file
+    match ConfigLoader::new().load_theme(name, &self.config.base_colors) {
+      Some(colors) => {
+        self.colors = colors;
+        self.params.theme = String::from(name);
+        self.view.set_colors(&self.colors, &self.params);
+        self.add_override("theme", name);
+        self.update_message(format!("Loaded theme \"{}\"", name).as_str());
+      }
+      None => self.update_message(format!("No theme named \"{}\"", name).as_str()),
+    }
+  }
+
+  /// Start typing the pattern of the items to find.
+  fn start_find(&mut self, forward: bool) {
+    self.start_prompt(Prompt::Find(forward), if forward { "Find forward: " } else { "Find backward: " });
+  }
+
+  /// Find text (in the help screen).
+  pub fn find(&mut self) {
+    self.start_find(true);
+  }
+
+  pub fn find_item_forward(&mut self) {
+    self.start_find(true);
+  }
+
+  pub fn find_item_backward(&mut self) {
+    self.start_find(false);
+  }
+
+  pub fn next_found_item(&mut self) {
+    self.find_item(true);
+  }
+
+  pub fn previous_found_item(&mut self) {
+    self.find_item(false);
+  }
+
+  pub fn toggle_find_mode(&mut self) {
+    self.find_mode = self.find_mode.toggle();
+    self.update_message(format!("Find mode: {}", self.find_mode.name()).as_str());
+  }
+
+  /// Find the items matching a pattern. An empty pattern stops highlighting
+  /// the found items.
+  pub fn find_pattern(&mut self, pattern: &str, forward: bool) {
+    if pattern.is_empty() {
+      self.matcher = None;
+      self.view.help.set_matcher(None);
+      return;
+    }
+
+    match Matcher::new(pattern, self.params.regular_expressions, self.params.ignore_leading_the) {
+      Ok(m) => {
+        self.view.help.set_matcher(Some(m.clone()));
+        self.matcher = Some(m);
+        self.find_item(forward);
+      }
+      Err(e) => self.update_message(format!("Error: {}", e).as_str()),
+    }
+  }
+
+  /// Select the next item matching the current pattern.
+  fn find_item(&mut self, forward: bool) {
+    let pattern = match self.matcher {
+      Some(ref m) => String::from(m.pattern()),
+      None => return self.update_message("No pattern to find"),
+    };
+
+    let found = match self.active_window() {
+      ActiveWindow::Playlist => {
+        let found = self.found_rows();
+        let current = self.selected_song.as_ref().map(|s| s.value as usize);
+        match find_next(found.len(), current, forward, self.find_mode, |i| found[i]) {
+          Some(idx) => {
+            self.selected_song = Some(TimedValue::<u32>::new(idx as u32));
+            true
+          }
+          None => false,
+        }
+      }
+      ActiveWindow::Help => self.view.help.find(forward, self.find_mode),
+      _ => false,
+    };
+
+    if !found {
+      self.update_message(format!("Unable to find \"{}\"", pattern).as_str());
+    }
+  }
+
+  /// Whether each displayed song of the queue matches the current pattern,
+  /// given the displayed columns.
+  fn found_rows(&mut self) -> Vec<bool> {
+    let columns = self.config.params.song_columns_list_format.clone();
+    let data = self.playlist_rows_data(&columns);
+    return match self.matcher {
+      Some(ref m) if !columns.is_empty() => data.chunks(columns.len()).map(|row| m.matches_any(row)).collect(),
+      _ => vec![false; self.rows.len()],
+    };
+  }
+
+  /// Start typing the pattern of the songs to show in the playlist.
+  pub fn apply_filter(&mut self) {
+    self.previous_filter = self.filter.clone();
+    self.start_prompt(Prompt::Filter, "Apply filter: ");
+    if let Some(ref f) = self.filter {
+      self.command_line.set_text(f.pattern());
+    }
+  }
+
+  /// Only show the songs matching a pattern in the playlist. An empty pattern
+  /// shows every song.
+  pub fn set_filter(&mut self, pattern: &str) -> Result<(), String> {
+    self.filter = if pattern.is_empty() {
+      None
+    } else {
+      Some(Matcher::new(
+        pattern,
+        self.params.regular_expressions,
+        self.params.ignore_leading_the,
+      )?)
+    };
+    return Ok(());
+  }
+
+  /// Ask for the priority of the selected songs.
+  pub fn ask_priority(&mut self) {
+    self.start_prompt(Prompt::Priority, "Set priority [0-255]: ");
+  }
+
+  /// Set the priority of the selected songs (used by random playback).
+  pub fn set_priority(&mut self, prio: &str) {
+    let prio = match prio.trim().parse::<u8>() {
+      Ok(p) => p,
+      Err(_) => return self.update_message(format!("Error: invalid priority \"{}\" (expected 0-255)", prio).as_str()),
+    };
+
+    if let Some(id) = self.selected_row().and_then(|r| r.id) {
+      match self.client.priority(mpd::Id::from(id), prio) {
+        Ok(_) => self.update_message(format!("Priority set to {}", prio).as_str()),
+        Err(_) => self.update_message("Error: priority change failed"),
+      }
+    }
+
+    self.change_status.playlist = true;
+  }
+
+  /// Ask for the number of random items to add.
+  pub fn ask_random_items_count(&mut self) {
+    self.start_prompt(Prompt::RandomCount, "Number of random items: ");
+  }
+
+  /// Ask for the kind of random items to add.
+  pub fn ask_random_items_mode(&mut self, count: usize) {
+    self.start_prompt(Prompt::RandomMode(count), "Add random (songs/artists/albums/<tag>): ");
+    self.command_line.set_text("songs");
+  }
+
+  /// Add random songs, or all the songs of random values of a tag (e.g.
+  /// artists), to the playlist. The same seed picks the same items from the
+  /// same database.
+  pub fn add_random_items(&mut self, count: usize, mode: &str, seed: Option<u64>) {
+    let items = RandomItems::from_name(mode.trim());
+    let mut candidates = match items.candidates(&mut self.client) {
+      Ok(c) => c,
+      Err(_) => return self.update_message(format!("Error: cannot list {}", items.name()).as_str()),
+    };
+    candidates.retain(|c| !c.is_empty());
+    candidates.sort();
+    candidates.dedup();
+
+    let mut rng = seed.map_or_else(Rng::from_time, Rng::new);
+    let picked = rng.sample(&candidates, count);
+    if picked.is_empty() {
+      return self.update_message(format!("No {} to add", items.name()).as_str());
+    }
+
+    self.change_status.playlist = true;
+    for value in &picked {
+      if let Err(e) = items.add(&mut self.client, value) {
+        return self.update_message(format!("Error: cannot add random {}: {}", items.name(), e).as_str());
+      }
+    }
+    self.update_message(format!("Added {} random {}", picked.len(), items.name()).as_str());
+  }
+
+  /// Song of the selected row, if any.
+  fn selected_row(&self) -> Option<QueueRow> {
+    self.selected_song.as_ref().and_then(|s| self.rows.get(s.value as usize)).cloned()
+  }
+
+  /// Update the displayed rows of the queue, given the cells of each song, and
+  /// keep the same song selected.
+  fn update_rows(&mut self, grid: &[&[String]]) {
+    let selected_id = self.selected_row().and_then(|r| r.id);
+    self.rows = match self.filter {
+      Some(ref f) => QueueRows::build(&*self.snapshot.queue, |i| grid.get(i).map_or(false, |row| f.matches_any(row))),
+      None => QueueRows::build(&*self.snapshot.queue, |_| true),
+    };
+    if let Some(ref property) = self.sort {
+      let queue: &[Song] = &*self.snapshot.queue;
+      self.rows.sort_by_key(|pos| queue.get(pos).map(|s| sort_key(s, property)));
+    }
+
+    if self.rows.is_empty() {
+      self.selected_song = None;
+    } else if let Some(ref mut s) = self.selected_song {
+      let rows = &self.rows;
+      let row = selected_id.and_then(|id| rows.row_of_id(id)).unwrap_or(s.value as usize);
+      s.value = row.min(rows.len() - 1) as u32;
+    }
+  }
+
+  /// Cells of the displayed rows of the queue, row by row.
+  fn playlist_rows_data(&mut self, columns: &[Column]) -> Vec<String> {
+    let n_cols = columns.len();
+    let grid_data = self.fill_grid_data(columns);
+    if n_cols == 0 {
+      return grid_data;
+    }
+
+    let grid: Vec<&[String]> = grid_data.chunks(n_cols).collect();
+    self.update_rows(&grid);
+    let mut data = Vec::with_capacity(self.rows.len() * n_cols);
+    for pos in self.rows.positions() {
+      data.extend_from_slice(grid[pos]);
+    }
+    return data;
+  }
+
+  /// Set the count given to the next action.
+  pub fn set_count(&mut self, count: u32) {
+    self.count = count.max(1);
+  }
+
+  /// Screen of the focused pane.
+  fn active_window(&self) -> ActiveWindow {
+    return self.view.active_window();
+  }
+
+  /// Time to wait for the next key of a key sequence, in milliseconds.
+  pub fn key_sequence_timeout(&self) -> i32 {
+    return self.params.key_sequence_timeout;
+  }
+
+  /// Name of the active screen.
+  pub fn active_screen(&self) -> &'static str {
+    return self.active_window().name();
+  }
+
+  /// Whether the given screen (ncmpcpp name, e.g. "playlist") is active.
+  pub fn is_screen_active(&self, screen: &str) -> bool {
+    return self.active_window().name() == screen;
+  }
+
+  /// Whether an action can be run in the current state.
+  pub fn can_run(&self, action: &str) -> bool {
+    match action {
+      _ if SCREEN_ACTIONS.contains(&action) => get_screen(self.active_window()).can_run(self, action),
+      "move_end" => !self.rows.is_empty(),
+      "master_screen" | "slave_screen" => self.view.visible_screens().len() > 1,
+      _ => self.action_map.contains_key(action),
+    }
+  }
+
+  /// Run an external command in the background.
+  pub fn run_external_command(&mut self, command: &str) {
+    let child = process::Command::new("sh")
+      .arg("-c")
+      .arg(command)
+      .stdin(Stdio::null())
+      .stdout(Stdio::null())
+      .stderr(Stdio::null())
+      .spawn();
+
+    match child {
+      Ok(mut child) => {
+        // Reap the process once it terminates
+        thread::spawn(move || child.wait());
+      }
+      Err(e) => self.update_message(format!("Error: cannot run \"{}\": {}", command, e).as_str()),
+    }
+  }
+
   pub fn playlist_stop(&mut self) {
     if self.client.stop().is_err() {
       self.update_message("Error: stop failed");
@@ -379,16 +1324,22 @@ impl<'m> Model<'m> {
   }
 
   pub fn playlist_previous(&mut self) {
-    if self.client.prev().is_err() {
-      self.update_message("Error: previous song failed");
+    for _ in 0..self.count {
+      if self.client.prev().is_err() {
+        self.update_message("Error: previous song failed");
+        break;
+      }
     }
 
     self.change_status.current_song = true;
   }
 
   pub fn playlist_next(&mut self) {
-    if self.client.next().is_err() {
-      self.update_message("Error: next song failed");
+    for _ in 0..self.count {
+      if self.client.next().is_err() {
+        self.update_message("Error: next song failed");
+        break;
+      }
     }
 
     self.change_status.current_song = true;
@@ -404,16 +1355,16 @@ impl<'m> Model<'m> {
   }
 
   pub fn playlist_delete_items(&mut self) {
-    if let Some(ref s) = self.selected_song {
-      self.client.delete(s.value).unwrap_or(())
+    if let Some(row) = self.selected_row() {
+      self.client.delete(row.pos).unwrap_or(())
     };
 
     self.change_status.playlist = true;
   }
 
   pub fn play_selected(&mut self) {
-    if let Some(ref s) = self.selected_song {
-      self.client.switch(s.value).unwrap_or(())
+    if let Some(row) = self.selected_row() {
+      self.client.switch(row.pos).unwrap_or(())
     };
 
     self.change_status.status = true;
@@ -444,6 +1395,16 @@ impl<'m> Model<'m> {
     self.change_status.status = true;
   }
 
+  /// Follow (or stop following) the playing song in the playlist.
+  pub fn toggle_playing_song_centering(&mut self) {
+    self.params.autocenter_mode = !self.params.autocenter_mode;
+    let state = if self.params.autocenter_mode { "enabled" } else { "disabled" };
+    self.update_message(format!("Centering playing song: {}", state).as_str());
+    if self.params.autocenter_mode {
+      self.jump_to_playing_song();
+    }
+  }
+
   pub fn toggle_random(&mut self) {
     let random = self.snapshot.status.random;
     if self.client.random(!random).is_err() {
@@ -478,25 +1439,102 @@ impl<'m> Model<'m> {
           self.selected_song.as_mut().unwrap().bump();
         }
       }
-      MouseEvent::ScrollDown => self.scroll_down(),
-      MouseEvent::ScrollUp => self.scroll_up(),
+      MouseEvent::ScrollDown => {
+        let offset = self.wheel_step();
+        self.scroll(offset);
+      }
+      MouseEvent::ScrollUp => {
+        let offset = -self.wheel_step();
+        self.scroll(offset);
+      }
       MouseEvent::SetProgress(pct) => self.set_song_progress(pct),
-      MouseEvent::SetSelectedSong(idx) => self.selected_song = Some(TimedValue::<u32>::new(idx)),
+      MouseEvent::SetVolume(vol) => self.set_volume(vol),
+      MouseEvent::VolumeUp => self.volume_up(),
+      MouseEvent::VolumeDown => self.volume_down(),
+      MouseEvent::ShowScreen(window) => self.show_screen(window),
+      event => get_screen(self.active_window()).handle_mouse(self, event),
     };
   }
 
+  /// Select a clicked song, and play it on double click.
+  pub fn click_song(&mut self, row: u32, id: Option<SongId>) {
+    let idx = self.clicked_row(row, id);
+    let double_click = self.last_click.map_or(false, |c| {
+      c.value == idx && get_time() < c.timestamp + Duration::milliseconds(DOUBLE_CLICK_INTERVAL)
+    });
+    self.selected_song = Some(TimedValue::<u32>::new(idx));
+    if double_click {
+      self.last_click = None;
+      self.play_selected();
+    } else {
+      self.last_click = Some(TimedValue::<u32>::new(idx));
+    }
+  }
+
+  /// Select and play a clicked song.
+  pub fn play_clicked_song(&mut self, row: u32, id: Option<SongId>) {
+    let idx = self.clicked_row(row, id);
+    self.selected_song = Some(TimedValue::<u32>::new(idx));
+    self.play_selected();
+  }
+
+  /// Row of a clicked song, which may have moved since it was displayed.
+  fn clicked_row(&self, row: u32, id: Option<SongId>) -> u32 {
+    id.and_then(|id| self.rows.row_of_id(id)).map_or(row, |r| r as u32)
+  }
+
+  /// Sort the displayed playlist by a column, or show it in the order of the
+  /// queue again if it is already sorted by this column. The queue of the
+  /// server is not changed.
+  pub fn sort_playlist_by(&mut self, property: &SongProperty) {
+    if self.sort.as_ref() == Some(property) {
+      self.sort = None;
+      self.update_message("Playlist shown in queue order");
+    } else {
+      self.sort = Some(property.clone());
+      self.update_message(format!("Playlist sorted by {}", property).as_str());
+    }
+    self.change_status.playlist = true;
+  }
+
+  /// Number of lines scrolled by the mouse wheel.
+  fn wheel_step(&self) -> i32 {
+    if self.params.mouse_list_scroll_whole_page {
+      self.page_height()
+    } else {
+      self.params.lines_scrolled.max(1)
+    }
+  }
+
+  /// Enable or disable the mouse.
+  pub fn toggle_mouse(&mut self) {
+    self.params.mouse_support = !self.params.mouse_support;
+    set_mouse_support(self.params.mouse_support);
+    let state = if self.params.mouse_support { "enabled" } else { "disabled" };
+    self.update_message(format!("Mouse support {}", state).as_str());
+  }
+
+  /// Volume change, multiplied by the count.
+  fn volume_step(&self) -> i32 {
+    return i32::from(self.config.params.volume_change_step) * self.count.min(100) as i32;
+  }
+
+  /// Change the volume by a (possibly large) amount, kept in [0,100].
+  fn change_volume(&mut self, delta: i32) {
+    let vol = i32::from(self.get_volume()) + delta;
+    self.set_volume(vol.max(0).min(100) as i8);
+  }
+
   pub fn volume_up(&mut self) {
-    let vol = self.get_volume();
-    let step = self.config.params.volume_change_step;
-    self.set_volume(vol + step);
+    let step = self.volume_step();
+    self.change_volume(step);
 
     self.change_status.status = true;
   }
 
   pub fn volume_down(&mut self) {
-    let vol = self.get_volume();
-    let step = self.config.params.volume_change_step;
-    self.set_volume(vol - step);
+    let step = self.volume_step();
+    self.change_volume(-step);
 
     self.change_status.status = true;
   }
@@ -508,7 +1546,57 @@ impl<'m> Model<'m> {
       None
     };
 
-    self.view.display_header(&self.active_window, &self.snapshot.pl_info, vol);
+    match self.params.user_interface {
+      UserInterface::Classic => self.view.display_header(&self.active_window(), &self.snapshot.pl_info, vol),
+      UserInterface::Alternative => {
+        let song = self.snapshot.status.song.and_then(|s| (*self.snapshot.queue).get(s.pos as usize));
+        let lines = match song {
+          Some(song) => vec![
+            self.params.alternative_header_first_line_format.render(|p| get_song_info(song, p)),
+            self.params.alternative_header_second_line_format.render(|p| get_song_info(song, p)),
+          ],
+          None => vec![],
+        };
+        self.view.display_alternative_header(&lines, vol);
+      }
+    }
+  }
+
+  /// Show the playing song in the title of the terminal window, when it
+  /// changes.
+  pub fn update_window_title(&mut self) {
+    // Give the terminal its title back when the option is disabled
+    if !self.params.enable_window_title {
+      if self.window_title.take().is_some() {
+        self.view.restore_window_title();
+      }
+      return;
+    }
+
+    let title = self.window_title_text();
+    if self.window_title.as_ref() != Some(&title) {
+      self.view.set_window_title(&title);
+      self.window_title = Some(title);
+    }
+  }
+
+  /// Title of the terminal window: the playing song, or the name of the
+  /// program.
+  pub fn window_title_text(&self) -> String {
+    let song = self.snapshot.status.song.and_then(|s| (*self.snapshot.queue).get(s.pos as usize));
+    return match song {
+      Some(song) if self.snapshot.status.state != State::Stop => {
+        self.params.song_window_title_format.render_text(|p| get_song_info(song, p))
+      }
+      _ => String::from("ncmpc"),
+    };
+  }
+
+  /// Switch between the classic and alternative interfaces.
+  pub fn toggle_interface(&mut self) {
+    self.params.user_interface = self.params.user_interface.toggle();
+    self.view.set_layout(&self.params);
+    self.update_message(format!("User interface: {}", self.params.user_interface.name()).as_str());
   }
 
   pub fn update_stateline(&mut self) {
@@ -534,10 +1622,8 @@ impl<'m> Model<'m> {
   }
 
   pub fn update_main_window(&mut self) {
-    match self.active_window {
-      ActiveWindow::Help => self.update_help(),
-      ActiveWindow::Playlist => self.update_playlist(),
-      ActiveWindow::ServerInfo => self.update_server_info(),
+    for window in self.view.visible_screens() {
+      get_screen(window).draw(self);
     }
   }
 
@@ -567,12 +1653,13 @@ impl<'m> Model<'m> {
       for (j, cell) in row.iter_mut().enumerate() {
         let col_type = &columns[j].column_type;
 
-        // Search for info in the map, or initialize it
-        if let Some(id_value) = id {
-          let info = song_info_map.entry((id_value, col_type.clone())).or_insert_with(String::new);
-          if info.is_empty() {
-            *info = get_song_info(&(*self.snapshot.queue)[i], &col_type);
-          }
+        // Search for info in the map, or initialize it. The priority can
+        // change while the song is queued, so it is not cached.
+        if let Some(id_value) = id.filter(|_| *col_type != SongProperty::Priority) {
+          let song = &(*self.snapshot.queue)[i];
+          let info = song_info_map
+            .entry((id_value, col_type.clone()))
+            .or_insert_with(|| get_song_info(song, &col_type));
           *cell = info.clone();
         } else {
           // TODO: avoid code duplication
@@ -586,18 +1673,49 @@ impl<'m> Model<'m> {
 
   pub fn update_playlist(&mut self) {
     // Get grid data and convert to 2D slices
-    let columns = &self.config.params.song_columns_list_format;
+    let columns = self.config.params.song_columns_list_format.clone();
     let n_cols = columns.len();
     // TODO: reuse data rather than reallocating for every call
-    let grid_data = &mut self.fill_grid_data(&columns);
+    let grid_data = &mut self.playlist_rows_data(&columns);
     let grid_base: Vec<_> = grid_data.as_slice().chunks(n_cols).collect();
     let grid: &[&[String]] = grid_base.as_slice();
 
-    // Get index of current song
+    // Songs matching the pattern of the items to find
+    let found = match self.matcher {
+      Some(ref m) => grid.iter().map(|row| m.matches_any(row)).collect(),
+      None => vec![],
+    };
+
+    // Get the row of the current song
     let song = self.snapshot.status.song;
-    let cur_song = if song.is_some() { Some(song.unwrap().pos) } else { None };
+    let cur_song = song.and_then(|s| self.rows.row_of_pos(s.pos)).map(|r| r as u32);
+
+    // Select the playing song at start, or when it changes in autocenter mode
+    let playing_id = song.map(|s| SongId::from(s.id));
+    let jump = if self.playlist_shown {
+      self.params.autocenter_mode && playing_id != self.playing_id
+    } else {
+      self.params.jump_to_now_playing_song_at_start
+    };
+    self.playing_id = playing_id;
+    self.playlist_shown = true;
+    if jump {
+      self.jump_to_playing_song();
+    }
 
-    self.view.display_main_playlist(&columns, &grid, cur_song, &self.selected_song);
+    let centered = self.params.centered_cursor || self.center_selection;
+    self.center_selection = false;
+    let ids = self.rows.ids();
+    self.view.display_main_playlist(
+      &columns,
+      &grid,
+      &ids,
+      cur_song,
+      &self.selected_song,
+      &found,
+      &mut self.playlist_start,
+      centered,
+    );
   }
 
   pub fn update_progressbar(&mut self) {
@@ -612,10 +1730,20 @@ impl<'m> Model<'m> {
   pub fn update_statusbar(&mut self) {
     use mpd::status::State;
 
+    // If a command is being typed
+    if self.command_line.is_active() {
+      let text = self.command_line.text();
+      self
+        .view
+        .display_command_line(self.command_line.prompt(), &text, self.command_line.cursor());
+      return;
+    }
+
     // If an info message has to be displayed
     if self.info_msg.is_some() {
       if get_time() < self.info_msg.as_ref().unwrap().timestamp + Duration::seconds(5) {
         self.view.display_statusbar_msg(&self.info_msg.as_ref().unwrap().value);
+        self.view.display_pending_keys(&self.pending_keys);
         return;
       } else {
         self.info_msg = None;
@@ -645,29 +1773,31 @@ impl<'m> Model<'m> {
         }
 
         let song = data.unwrap();
-        let artist = get_song_info(&song, &SongProperty::Artist);
-        let album = get_song_info(&song, &SongProperty::Album);
+        let artist = non_empty_or(get_song_info(&song, &SongProperty::Artist), "Unknown artist");
+        let album = non_empty_or(get_song_info(&song, &SongProperty::Album), "Unknown album");
         let title = song.title.unwrap_or_else(|| "Unknown title".to_string());
         msg = format!("{} - {} - {}", artist, title, album);
 
         let mut bitrate = String::default();
         let (cur, total) = get_song_time(&status);
-        let cur_min = cur.num_minutes();
-        let cur_sec = cur.num_seconds() % 60;
-        let total_min = total.num_minutes();
-        let total_sec = total.num_seconds() % 60;
         if self.params.display_bitrate {
           let val = get_song_bitrate(&status);
           if val > 0 {
             bitrate = format!("({} kbps) ", val);
           }
         }
-        track = format!("{}[{}:{:02}/{}:{:02}]", bitrate, cur_min, cur_sec, total_min, total_sec);
+        track = format!("{}{}", bitrate, format_song_time(cur, total, self.params.display_remaining_time));
       }
     } else {
       mode = "No MPD status available".to_string();
     }
     self.view.display_statusbar(&mode, &msg, &track);
+    self.view.display_pending_keys(&self.pending_keys);
+  }
+
+  /// Set the keys of the key sequence being typed.
+  pub fn set_pending_keys(&mut self, keys: &str) {
+    self.pending_keys = String::from(keys);
   }
 
   pub fn update_message(&mut self, msg: &str) {
@@ -680,30 +1810,23 @@ impl<'m> Model<'m> {
   }
 
   pub fn scroll_playlist(&mut self, offset: i32) {
-    let end = self.snapshot.pl_info.size as i32;
+    let end = self.rows.len() as i32;
     self.selected_song = Some(TimedValue::<u32>::new(match self.selected_song {
-      Some(ref s) => {
-        let cur_pos = s.value as i32;
-        // If we're going past the end of the playlist
-        if cur_pos >= end - offset {
-          if self.params.cyclic_scrolling {
-            (cur_pos + offset - end) as u32
-          } else {
-            end as u32
-          }
-        // If we're going past the start of the playlist
-        } else if cur_pos < -offset {
+      Some(ref s) if end > 0 => {
+        let new_pos = s.value as i32 + offset;
+        // If we're going past the start or the end of the playlist
+        if new_pos < 0 || new_pos >= end {
           if self.params.cyclic_scrolling {
-            (end + cur_pos + offset) as u32
+            new_pos.rem_euclid(end) as u32
           } else {
-            0
+            new_pos.max(0).min(end - 1) as u32
           }
         // We're staying within the playlist
         } else {
-          (cur_pos + offset) as u32
+          new_pos as u32
         }
       }
-      None => 0,
+      _ => 0,
     }))
   }
 
@@ -711,39 +1834,62 @@ impl<'m> Model<'m> {
     self.view.help.scroll(offset);
   }
 
+  /// Scroll the list of the active window.
+  fn scroll(&mut self, offset: i32) {
+    get_screen(self.active_window()).scroll(self, offset);
+  }
+
+  /// Number of lines of the list of the active window.
+  fn page_height(&self) -> i32 {
+    return get_screen(self.active_window()).page_height(self);
+  }
+
+  /// Number of lines of the focused pane.
+  pub fn pane_height(&self) -> i32 {
+    return self.view.get_main_window_height();
+  }
+
+  /// Number of songs shown in the playlist.
+  pub fn playlist_height(&self) -> i32 {
+    return self.view.get_main_playlist_height();
+  }
+
+  /// Whether a song of the playlist is selected.
+  pub fn has_selected_song(&self) -> bool {
+    return self.selected_song.is_some();
+  }
+
+  /// Whether a song is playing (or paused).
+  pub fn has_playing_song(&self) -> bool {
+    return self.snapshot.status.song.is_some();
+  }
+
   pub fn scroll_down(&mut self) {
-    match self.active_window {
-      ActiveWindow::Help => self.scroll_help(1),
-      ActiveWindow::Playlist => self.scroll_playlist(1),
-      _ => {}
-    }
+    let offset = self.count as i32;
+    self.scroll(offset);
   }
 
   pub fn page_down(&mut self) {
-    let offset = self.view.get_main_playlist_height();
-    match self.active_window {
-      // TODO: use adaptative height
-      ActiveWindow::Help => self.scroll_help(offset),
-      ActiveWindow::Playlist => self.scroll_playlist(offset),
-      _ => {}
-    }
+    let offset = self.page_height() * self.count as i32;
+    self.scroll(offset);
   }
 
   pub fn scroll_up(&mut self) {
-    match self.active_window {
-      ActiveWindow::Help => self.scroll_help(-1),
-      ActiveWindow::Playlist => self.scroll_playlist(-1),
-      _ => {}
-    }
+    let offset = -(self.count as i32);
+    self.scroll(offset);
   }
 
   pub fn page_up(&mut self) {
-    let offset = -self.view.get_main_playlist_height();
-    match self.active_window {
-      // TODO: use adaptative height
-      ActiveWindow::Help => self.scroll_help(offset),
-      ActiveWindow::Playlist => self.scroll_playlist(offset),
-      _ => {}
+    let offset = -self.page_height() * self.count as i32;
+    self.scroll(offset);
+  }
+
+  /// Select the playing song, in the middle of the screen.
+  pub fn jump_to_playing_song(&mut self) {
+    let song = self.snapshot.status.song;
+    if let Some(row) = song.and_then(|s| self.rows.row_of_pos(s.pos)) {
+      self.selected_song = Some(TimedValue::<u32>::new(row as u32));
+      self.center_selection = true;
     }
   }
 
@@ -752,25 +1898,167 @@ impl<'m> Model<'m> {
   }
 
   pub fn move_end(&mut self) {
-    let end = self.snapshot.pl_info.size;
+    let end = self.rows.len() as u32;
     self.selected_song = Some(TimedValue::<u32>::new(end - 1));
   }
 
-  pub fn show_help(&mut self) {
-    self.active_window = ActiveWindow::Help;
+  /// Show a screen, remembering the current one.
+  pub fn show_screen(&mut self, window: ActiveWindow) {
+    let current = self.active_window();
+    self.view.show_screen(window);
+    if self.active_window() != current {
+      self.previous_window = Some(current);
+    }
+  }
+
+  pub fn next_screen(&mut self) {
+    self.switch_screen(true);
+  }
+
+  pub fn previous_screen(&mut self) {
+    self.switch_screen(false);
+  }
+
+  /// Show the next or previous screen, given the screen switcher mode.
+  fn switch_screen(&mut self, forward: bool) {
+    let current = self.active_window();
+    let mode = &self.params.screen_switcher_mode;
+    if let Some(window) = mode.switch(current, self.previous_window, forward) {
+      self.show_screen(window);
+    }
+  }
+
+  /// Lock the active screen, other screens being then shown next to it, or
+  /// unlock it.
+  pub fn toggle_screen_lock(&mut self) {
+    if self.view.is_screen_locked() {
+      self.view.unlock_screen();
+      self.update_message("Screen unlocked");
+    } else if self.params.ask_for_locked_screen_width_part {
+      self.start_prompt(Prompt::LockedScreenWidth, "% of the locked screen's width to be reserved (20-80): ");
+      self.command_line.set_text(&self.params.locked_screen_width_part.to_string());
+    } else {
+      let part = self.params.locked_screen_width_part;
+      self.lock_screen(part);
+    }
+  }
+
+  /// Lock the active screen, on a given part (in %) of the width.
+  pub fn lock_screen(&mut self, width_part: i32) {
+    self.view.lock_screen(width_part);
+    self.update_message(format!("Screen locked (with {}% width)", width_part).as_str());
+  }
+
+  /// Focus the locked screen.
+  pub fn master_screen(&mut self) {
+    self.focus_pane(0);
   }
 
-  pub fn show_server_info(&mut self) {
-    self.active_window = ActiveWindow::ServerInfo;
+  /// Focus the screen shown next to the locked one.
+  pub fn slave_screen(&mut self) {
+    self.focus_pane(1);
   }
 
-  pub fn show_playlist(&mut self) {
-    self.active_window = ActiveWindow::Playlist;
+  /// Focus a pane, remembering the current screen.
+  fn focus_pane(&mut self, idx: usize) {
+    let current = self.active_window();
+    if self.view.focus_pane(idx) && self.active_window() != current {
+      self.previous_window = Some(current);
+    }
   }
 
   pub fn take_snapshot(&mut self) {
-    self.snapshot.update(&mut self.client, &self.change_status);
+    match self.reconnect_time {
+      // While the connection is lost, connect again from time to time
+      Some(t) if get_time() >= t => self.reconnect(),
+      Some(_) => {}
+      // A closed connection is noticed by the commands expecting a reply
+      None => {
+        if self.snapshot.update(&mut self.client, &self.change_status).is_err() {
+          self.reconnect();
+        }
+      }
+    }
     // TODO: reset
     self.change_status = DataChangeStatus::new();
   }
+
+  /// Fetch the queue at the next snapshot, even if it was fetched recently.
+  pub fn refresh_queue(&mut self) {
+    self.snapshot.queue.invalidate();
+    self.change_status.playlist = true;
+  }
+
+  /// Connect to the server again, after the connection was lost.
+  fn reconnect(&mut self) {
+    match start_client(&self.config).and_then(|mut c| c.queue().map(|q| (c, q))) {
+      Ok((client, queue)) => {
+        self.client = client;
+        self.snapshot.queue.set(queue);
+        // Song ids may have changed if the server was restarted
+        self.song_info_map.clear();
+        self.reconnect_time = None;
+        self.reconnect_delay = Duration::milliseconds(RECONNECT_DELAY_MIN);
+        self.update_message("Reconnected to MPD");
+      }
+      Err(_) => {
+        self.snapshot.status = Status::default();
+        self.reconnect_time = Some(get_time() + self.reconnect_delay);
+        self.reconnect_delay = (self.reconnect_delay * 2).min(Duration::milliseconds(RECONNECT_DELAY_MAX));
+        self.update_message("Error: connection to MPD lost");
+      }
+    }
+  }
+
+  /// Status of the server at the last snapshot.
+  pub fn status(&self) -> &Status {
+    return &self.snapshot.status;
+  }
+
+  /// Songs of the queue at the last snapshot.
+  pub fn queue(&self) -> &[Song] {
+    return &self.snapshot.queue;
+  }
+
+  /// Size and duration of the queue at the last snapshot.
+  pub fn playlist_info(&self) -> &PlaylistInfo {
+    return &self.snapshot.pl_info;
+  }
+}
+
+#[test]
+fn test_song_priority() {
+  let mut song = Song::default();
+  assert_eq!(get_song_info(&song, &SongProperty::Priority), "");
+  song.place = Some(mpd::song::QueuePlace {
+    id: mpd::Id(3),
+    pos: 0,
+    prio: 42,
+  });
+  assert_eq!(get_song_info(&song, &SongProperty::Priority), "42");
+}
+
+#[test]
+fn test_song_time() {
+  let elapsed = Duration::seconds(62);
+  let total = Duration::seconds(562);
+  assert_eq!(format_song_time(elapsed, total, false), "[1:02/9:22]");
+  assert_eq!(format_song_time(elapsed, total, true), "[-8:20/9:22]");
+  // Streams only have an elapsed time
+  assert_eq!(format_song_time(elapsed, Duration::zero(), false), "[1:02]");
+  assert_eq!(format_song_time(elapsed, Duration::zero(), true), "[1:02]");
+
+  let mut queue = vec![Song::default(), Song::default(), Song::default()];
+  for (song, secs) in queue.iter_mut().zip(&[100, 200, 300]) {
+    song.duration = Some(Duration::seconds(*secs));
+  }
+  let mut status = Status::default();
+  assert_eq!(remaining_time(&queue, &status), Duration::zero());
+  status.song = Some(mpd::song::QueuePlace {
+    id: mpd::Id(2),
+    pos: 1,
+    prio: 0,
+  });
+  status.time = Some((Duration::seconds(50), Duration::seconds(200)));
+  assert_eq!(remaining_time(&queue, &status), Duration::seconds(450));
 }
@@ -11,7 +11,89 @@ pub use nom::types::CompleteStr as cstr;
 #[derive(Debug)]
 pub enum ParserError {
   Io(io::Error),
+  /// Parsing error, with the line where it occurred (from 1), 0 if unknown.
   Parse(u32),
+  /// Error in a song format, with the position of the character where it
+  /// occurred (from 1).
+  Format(u32),
+}
+
+/// Action read from a bindings file, with its arguments, e.g.:
+///   run_external_command "mpc update"
+#[derive(Clone, PartialEq, Debug)]
+pub struct ActionDef {
+  pub name: String,
+  pub args: Vec<String>,
+}
+
+/// Target of a binding.
+#[derive(Clone, PartialEq, Debug)]
+pub enum BindingTarget {
+  /// Key, e.g. `def_key "k"`.
+  Key(String),
+  /// Command and its mode, e.g. `def_command "quit" [deferred]`.
+  Command(String, String),
+}
+
+/// Key or command binding read from a bindings file.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Binding {
+  pub target: BindingTarget,
+  /// Screen a key binding is restricted to, e.g. `def_key "d d" [playlist]`.
+  pub screen: Option<String>,
+  /// Chain of actions.
+  pub actions: Vec<ActionDef>,
+  /// Line of the `def_key` or `def_command` statement.
+  pub line: u32,
+}
+
+/// Raw target, as parsed.
+#[derive(PartialEq, Debug)]
+enum Target<'a> {
+  Key(cstr<'a>, Option<cstr<'a>>),
+  Command(cstr<'a>, cstr<'a>),
+}
+
+impl ActionDef {
+  pub fn new(name: &str) -> ActionDef {
+    ActionDef {
+      name: String::from(name),
+      args: vec![],
+    }
+  }
+}
+
+/// Remove escape characters from a quoted string (`\"` and `\\`).
+fn unescape(s: &str) -> String {
+  let mut result = String::with_capacity(s.len());
+  let mut chars = s.chars().peekable();
+  while let Some(c) = chars.next() {
+    match (c, chars.peek()) {
+      ('\\', Some(&'"')) | ('\\', Some(&'\\')) => result.push(chars.next().unwrap()),
+      _ => result.push(c),
+    }
+  }
+  return result;
+}
+
+/// Take the content of a quoted string, until its closing (unescaped) quote.
+fn quoted_content(input: cstr) -> IResult<cstr, cstr> {
+  let mut escaped = false;
+  for (i, c) in input.char_indices() {
+    match c {
+      _ if escaped => escaped = false,
+      '\\' => escaped = true,
+      '"' => return Ok((cstr(&input[i..]), cstr(&input[..i]))),
+      _ => {}
+    }
+  }
+  return Err(Err::Error(error_position!(input, ErrorKind::TakeUntil)));
+}
+
+/// Get the line number (starting from 1) of a subslice of a string.
+fn line_of(input: &str, sub: &str) -> u32 {
+  let offset = sub.as_ptr() as usize - input.as_ptr() as usize;
+  1 + input[..offset].matches('\n').count() as u32
 }
 
 // Example: quit, do_something
@@ -46,16 +128,59 @@ named!(ignored_line<cstr,cstr>,
   alt!(multispace | do_parse!(opt!(space) >> line_ending_or_comment >> (cstr(""))))
 );
 
-named!(def_key<cstr,cstr>,
+// Example:
+//   "k"
+//   "echo \"x\""
+named!(quoted<cstr,cstr>,
   do_parse!(
-    tag_s!("def_key") >>
-    space >>
     tag_s!("\"") >>
-    val: take_until_s!("\"") >>
+    val: quoted_content >>
     tag_s!("\"") >>
+    (val)
+  )
+);
+
+// Example:
+//   [playlist]
+named!(bracketed<cstr,cstr>,
+  delimited!(tag_s!("["), take_until_s!("]"), tag_s!("]"))
+);
+
+// Example:
+//   def_key "k"
+//   def_key "g g" [playlist]
+named!(def_key<cstr,(cstr,Option<cstr>)>,
+  do_parse!(
+    tag_s!("def_key") >>
+    space >>
+    val: quoted >>
+    opt!(space) >>
+    screen: opt!(bracketed) >>
     opt!(space) >>
     line_ending_or_comment >>
-    (val)
+    (val, screen)
+  )
+);
+
+// Example:
+//   def_command "quit" [deferred]
+named!(def_command<cstr,(cstr,cstr)>,
+  do_parse!(
+    tag_s!("def_command") >>
+    space >>
+    name: quoted >>
+    opt!(space) >>
+    mode: opt!(bracketed) >>
+    opt!(space) >>
+    line_ending_or_comment >>
+    (name, mode.unwrap_or(cstr("deferred")))
+  )
+);
+
+named!(target<cstr,Target>,
+  alt!(
+    map!(def_key, |(key, screen)| Target::Key(key, screen)) |
+    map!(def_command, |(name, mode)| Target::Command(name, mode))
   )
 );
 
@@ -65,31 +190,33 @@ named!(action_name<cstr,cstr>,
 
 // Example:
 //   some_action
-named!(action<cstr,cstr>,
+//   some_action "argument"
+named!(action<cstr,(cstr,Option<cstr>)>,
   do_parse!(
     space >>
     val: action_name >>
+    arg: opt!(do_parse!(space >> a: quoted >> (a))) >>
     alt!(
       do_parse!(opt!(space) >> opt!(line_ending_or_comment) >> (cstr(""))) |
       eof!()
     ) >>
-    (val)
+    (val, arg)
   )
 );
 
 // Example:
 //   some_action
 //   some_other_action
-named!(actions_aggregator<cstr, Vec<cstr> >, many1!(action));
+named!(actions_aggregator<cstr, Vec<(cstr,Option<cstr>)> >, many1!(action));
 
 // Example:
 // def_key "f"
 //   some_action
 //   some_other_action
-named!(key_actions<cstr,(cstr,Vec<cstr>)>,
+named!(key_actions<cstr,(Target,Vec<(cstr,Option<cstr>)>)>,
   do_parse!(
     many0!(ignored_line) >>
-    key: def_key >>
+    key: target >>
     actions: actions_aggregator >>
     many0!(ignored_line) >>
     (key, actions)
@@ -103,32 +230,104 @@ named!(key_actions<cstr,(cstr,Vec<cstr>)>,
 //
 // def_key "j"
 //   do_something
-named!(key_actions_aggregator<cstr, Vec<(cstr,Vec<cstr>)> >, many0!(key_actions));
-
-/// Load bindings configuration from a given path.
-pub fn parse_bindings_configuration(path: &PathBuf) -> Result<Vec<(String, Vec<String>)>, ParserError> {
-  let mut f = r#try!(File::open(path).map_err(ParserError::Io));
-  let mut s = String::default();
-  r#try!(f.read_to_string(&mut s).map_err(ParserError::Io));
+named!(key_actions_aggregator<cstr, Vec<(Target,Vec<(cstr,Option<cstr>)>)> >, many0!(key_actions));
 
-  let data = key_actions_aggregator(cstr(s.as_str()));
+/// Parse bindings configuration.
+pub fn parse_bindings(s: &str) -> Result<Vec<Binding>, ParserError> {
+  let data = key_actions_aggregator(cstr(s));
   match data {
-    Ok((_, o)) => {
+    Ok((remaining, o)) => {
+      // Everything should have been consumed
+      if !remaining.trim().is_empty() {
+        let start = remaining.trim_start();
+        return Err(ParserError::Parse(line_of(s, start)));
+      }
+
       let res = o
         .iter()
         .map(|ref val| {
-          (
-            String::from(*val.0),
-            val.1.iter().map(|act| String::from(**act)).collect::<Vec<String>>(),
-          )
+          let (target, screen, line) = match val.0 {
+            Target::Key(k, screen) => (BindingTarget::Key(unescape(&k)), screen.map(|s| String::from(*s)), line_of(s, &k)),
+            Target::Command(n, m) => (BindingTarget::Command(unescape(&n), String::from(*m)), None, line_of(s, &n)),
+          };
+          Binding {
+            target: target,
+            screen: screen,
+            actions: val
+              .1
+              .iter()
+              .map(|&(name, arg)| ActionDef {
+                name: String::from(*name),
+                args: arg.iter().map(|a| unescape(a)).collect(),
+              })
+              .collect::<Vec<ActionDef>>(),
+            line: line,
+          }
         })
-        .collect::<Vec<(String, Vec<String>)>>();
+        .collect::<Vec<Binding>>();
       Ok(res)
     }
     _ => Err(ParserError::Parse(0)),
   }
 }
 
+/// Load bindings from a given path.
+pub fn parse_bindings_file(path: &PathBuf) -> Result<Vec<Binding>, ParserError> {
+  let mut f = r#try!(File::open(path).map_err(ParserError::Io));
+  let mut s = String::default();
+  r#try!(f.read_to_string(&mut s).map_err(ParserError::Io));
+
+  parse_bindings(&s)
+}
+
+/// Load key bindings configuration from a given path, as pairs of key and
+/// action names.
+pub fn parse_bindings_configuration(path: &PathBuf) -> Result<Vec<(String, Vec<String>)>, ParserError> {
+  let bindings = r#try!(parse_bindings_file(path));
+  Ok(
+    bindings
+      .into_iter()
+      .filter_map(|b| match b.target {
+        BindingTarget::Key(key) => Some((key, b.actions.into_iter().map(|a| a.name).collect())),
+        BindingTarget::Command(..) => None,
+      })
+      .collect(),
+  )
+}
+
+fn is_command_word_char(ch: char) -> bool {
+  !ch.is_whitespace() && ch != '"'
+}
+
+// Example:
+//   artist
+//   "Boards of Canada"
+named!(command_word<cstr,String>,
+  alt!(
+    map!(quoted, |w| unescape(&w)) |
+    map!(take_while1_s!(is_command_word_char), |w| String::from(*w))
+  )
+);
+
+// Example:
+//   add artist "Boards of Canada"
+named!(command_words<cstr, Vec<String> >,
+  do_parse!(
+    opt!(space) >>
+    words: many0!(terminated!(command_word, opt!(space))) >>
+    eof!() >>
+    (words)
+  )
+);
+
+/// Split a command line into words. Words containing spaces are quoted.
+pub fn parse_command_words(s: &str) -> Result<Vec<String>, ParserError> {
+  match command_words(cstr(s)) {
+    Ok((_, words)) => Ok(words),
+    _ => Err(ParserError::Parse(1)),
+  }
+}
+
 fn to_width(s: cstr) -> Result<(i32, bool), ParserError> {
   let is_fixed = s.chars().last().unwrap_or(' ') == 'f';
   let width = if is_fixed {
@@ -169,7 +368,7 @@ fn parse_def_key() {
   let file_remaining = "  scroll_up";
 
   let def_key_res = def_key(cstr(file));
-  assert_eq!(def_key_res, Ok((cstr(file_remaining), cstr("k"))));
+  assert_eq!(def_key_res, Ok((cstr(file_remaining), (cstr("k"), None))));
 }
 
 #[test]
@@ -179,7 +378,7 @@ fn parse_action() {
   let file_remaining = "  scroll_down";
 
   let action_res = action(cstr(file));
-  assert_eq!(action_res, Ok((cstr(file_remaining), cstr("scroll_up"))));
+  assert_eq!(action_res, Ok((cstr(file_remaining), (cstr("scroll_up"), None))));
 }
 
 #[test]
@@ -192,7 +391,13 @@ def_key \"j\"
   scroll_down";
 
   let action_res = key_actions(cstr(file));
-  assert_eq!(action_res, Ok((cstr(file_remaining), (cstr("k"), vec![cstr("scroll_up")]))));
+  assert_eq!(
+    action_res,
+    Ok((
+      cstr(file_remaining),
+      (Target::Key(cstr("k"), None), vec![(cstr("scroll_up"), None)])
+    ))
+  );
 }
 
 #[test]
@@ -208,7 +413,13 @@ def_key \"j\"
   let actions_res = key_actions(cstr(file));
   assert_eq!(
     actions_res,
-    Ok((cstr(file_remaining), (cstr("k"), vec![cstr("scroll_up"), cstr("scroll_down")])))
+    Ok((
+      cstr(file_remaining),
+      (
+        Target::Key(cstr("k"), None),
+        vec![(cstr("scroll_up"), None), (cstr("scroll_down"), None)]
+      )
+    ))
   );
 }
 
@@ -229,8 +440,11 @@ def_key \"j\"
     Ok((
       cstr(file_remaining),
       vec![
-        (cstr("k"), vec![cstr("scroll_up"), cstr("scroll_down")]),
-        (cstr("j"), vec![cstr("scroll_down")])
+        (
+          Target::Key(cstr("k"), None),
+          vec![(cstr("scroll_up"), None), (cstr("scroll_down"), None)]
+        ),
+        (Target::Key(cstr("j"), None), vec![(cstr("scroll_down"), None)])
       ]
     ))
   );
@@ -284,13 +498,105 @@ def_key \"j\"
     Ok((
       cstr(file_remaining),
       vec![
-        (cstr("k"), vec![cstr("scroll_up"), cstr("scroll_down")]),
-        (cstr("j"), vec![cstr("scroll_down")])
+        (
+          Target::Key(cstr("k"), None),
+          vec![(cstr("scroll_up"), None), (cstr("scroll_down"), None)]
+        ),
+        (Target::Key(cstr("j"), None), vec![(cstr("scroll_down"), None)])
       ]
     ))
   );
 }
 
+#[test]
+fn parse_bindings_lines() {
+  let file = "# Map k
+def_key \"k\"
+  scroll_up
+
+def_key \"j\"
+  scroll_down
+";
+
+  let bindings = parse_bindings(file).unwrap();
+  assert_eq!(bindings.len(), 2);
+  assert_eq!(bindings[0].target, BindingTarget::Key(String::from("k")));
+  assert_eq!(bindings[0].line, 2);
+  assert_eq!(bindings[1].target, BindingTarget::Key(String::from("j")));
+  assert_eq!(bindings[1].line, 5);
+}
+
+#[test]
+fn parse_bindings_commands_and_arguments() {
+  let file = "def_command \"update\" [immediate]
+  run_external_command \"mpc update\"
+
+def_key \"ctrl-l\"
+  require_screen \"playlist\"
+  push_characters \":\"
+  dummy
+
+def_key \"\\\\\"
+  toggle_display_mode
+
+def_key \"g g\" [playlist]
+  move_home
+";
+
+  let bindings = parse_bindings(file).unwrap();
+  assert_eq!(bindings.len(), 4);
+  assert_eq!(
+    bindings[0].target,
+    BindingTarget::Command(String::from("update"), String::from("immediate"))
+  );
+  assert_eq!(bindings[0].actions[0].name, "run_external_command");
+  assert_eq!(bindings[0].actions[0].args, vec![String::from("mpc update")]);
+  assert_eq!(bindings[1].line, 4);
+  assert_eq!(bindings[1].actions[1].args, vec![String::from(":")]);
+  assert_eq!(bindings[1].actions[2], ActionDef::new("dummy"));
+  assert_eq!(bindings[2].target, BindingTarget::Key(String::from("\\")));
+  assert_eq!(bindings[2].screen, None);
+  assert_eq!(bindings[3].target, BindingTarget::Key(String::from("g g")));
+  assert_eq!(bindings[3].screen, Some(String::from("playlist")));
+}
+
+#[test]
+fn parse_bindings_escaped_quotes() {
+  let file = r#"def_key "x"
+  run_external_command "echo \"x\" \\ 'a\.b'"
+"#;
+
+  let bindings = parse_bindings(file).unwrap();
+  assert_eq!(bindings[0].actions[0].args, vec![String::from(r#"echo "x" \ 'a\.b'"#)]);
+}
+
+#[test]
+fn parse_bindings_error_line() {
+  let file = "def_key \"k\"
+  scroll_up
+
+def_key k
+  scroll_down
+";
+
+  match parse_bindings(file) {
+    Err(ParserError::Parse(line)) => assert_eq!(line, 4),
+    _ => panic!("parsing should fail on line 4"),
+  }
+}
+
+#[test]
+fn parse_command_line_words() {
+  assert_eq!(parse_command_words("  volume 40 ").unwrap(), vec!["volume", "40"]);
+  assert_eq!(
+    parse_command_words("add artist \"Boards of Canada\"").unwrap(),
+    vec!["add", "artist", "Boards of Canada"]
+  );
+  assert_eq!(parse_command_words("").unwrap(), Vec::<String>::new());
+  assert_eq!(parse_command_words(r#"find "say \"hi\"""#).unwrap(), vec!["find", "say \"hi\""]);
+  assert!(parse_command_words("save \"my list").is_err());
+}
+
 #[test]
 fn parse_column() {
   let file = "(20)[yellow]{a}";
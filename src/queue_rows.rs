@@ -0,0 +1,122 @@
+extern crate mpd;
+
+use crate::model::SongId;
+
+/// Song displayed in a row of the queue.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct QueueRow {
+  /// Position of the song in the queue.
+  pub pos: u32,
+  pub id: Option<SongId>,
+}
+
+/// Rows of the displayed queue, which may only show some of its songs (e.g.
+/// when a filter is applied).
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct QueueRows {
+  rows: Vec<QueueRow>,
+}
+
+impl QueueRows {
+  pub fn new() -> QueueRows {
+    QueueRows { rows: Vec::new() }
+  }
+
+  /// Rows of the songs of the queue accepted by a predicate, given their
+  /// position.
+  pub fn build<F>(queue: &[mpd::Song], keep: F) -> QueueRows
+  where
+    F: Fn(usize) -> bool,
+  {
+    let rows = queue
+      .iter()
+      .enumerate()
+      .filter(|&(i, _)| keep(i))
+      .map(|(i, song)| QueueRow {
+        pos: song.place.as_ref().map_or(i as u32, |p| p.pos),
+        id: song.place.as_ref().map(|p| SongId::from(p.id)),
+      })
+      .collect();
+    return QueueRows { rows: rows };
+  }
+
+  pub fn len(&self) -> usize {
+    self.rows.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.rows.is_empty()
+  }
+
+  pub fn get(&self, row: usize) -> Option<&QueueRow> {
+    self.rows.get(row)
+  }
+
+  /// Positions in the queue of the displayed songs.
+  pub fn positions(&self) -> impl Iterator<Item = usize> + '_ {
+    self.rows.iter().map(|r| r.pos as usize)
+  }
+
+  /// Ids of the displayed songs.
+  pub fn ids(&self) -> Vec<Option<SongId>> {
+    self.rows.iter().map(|r| r.id).collect()
+  }
+
+  /// Sort the rows (stably) by a key computed from the position of their song.
+  pub fn sort_by_key<K, F>(&mut self, key: F)
+  where
+    K: Ord,
+    F: Fn(usize) -> K,
+  {
+    self.rows.sort_by_key(|r| key(r.pos as usize));
+  }
+
+  /// Row of the song at a given position in the queue, if displayed.
+  pub fn row_of_pos(&self, pos: u32) -> Option<usize> {
+    self.rows.iter().position(|r| r.pos == pos)
+  }
+
+  /// Row of a given song, if displayed.
+  pub fn row_of_id(&self, id: SongId) -> Option<usize> {
+    self.rows.iter().position(|r| r.id == Some(id))
+  }
+}
+
+#[cfg(test)]
+fn queue_song(pos: u32, id: u32) -> mpd::Song {
+  let mut song = mpd::Song::default();
+  song.place = Some(mpd::song::QueuePlace {
+    id: mpd::Id(id),
+    pos: pos,
+    prio: 0,
+  });
+  return song;
+}
+
+#[test]
+fn test_queue_rows() {
+  let queue = vec![queue_song(0, 10), queue_song(1, 11), queue_song(2, 12), queue_song(3, 13)];
+
+  let all = QueueRows::build(&queue, |_| true);
+  assert_eq!(all.len(), 4);
+  assert_eq!(all.row_of_pos(2), Some(2));
+
+  // Only show odd positions
+  let rows = QueueRows::build(&queue, |i| i % 2 == 1);
+  assert_eq!(rows.len(), 2);
+  assert_eq!(rows.positions().collect::<Vec<usize>>(), vec![1, 3]);
+  assert_eq!(rows.get(1).map(|r| r.pos), Some(3));
+  assert_eq!(rows.get(1).and_then(|r| r.id), Some(SongId::from(mpd::Id(13))));
+  assert_eq!(rows.row_of_pos(3), Some(1));
+  assert_eq!(rows.row_of_pos(2), None);
+  assert_eq!(rows.row_of_id(SongId::from(mpd::Id(11))), Some(0));
+  assert_eq!(rows.row_of_id(SongId::from(mpd::Id(10))), None);
+  assert!(QueueRows::build(&queue, |_| false).is_empty());
+
+  // Sorted rows still find their songs
+  let mut sorted = QueueRows::build(&queue, |_| true);
+  sorted.sort_by_key(|pos| pos % 2);
+  assert_eq!(sorted.positions().collect::<Vec<usize>>(), vec![0, 2, 1, 3]);
+  assert_eq!(sorted.row_of_pos(1), Some(2));
+  assert_eq!(sorted.row_of_id(SongId::from(mpd::Id(12))), Some(1));
+}
@@ -0,0 +1,71 @@
+extern crate mpd;
+
+use mpd::error::Error;
+use mpd::search::{Query, Term};
+use mpd::Client;
+use std::borrow::Cow;
+use std::io::{Read, Write};
+
+/// Kind of items added by `add_random_items`.
+#[derive(Clone, PartialEq, Debug)]
+pub enum RandomItems {
+  Songs,
+  /// Values of a tag (e.g. artists, albums), added with all their songs.
+  Tag(String),
+}
+
+impl RandomItems {
+  /// Parse the mode typed by the user: "songs", "artists", "albums" or a tag
+  /// name.
+  pub fn from_name(name: &str) -> RandomItems {
+    match name.to_lowercase().as_str() {
+      "songs" | "song" => RandomItems::Songs,
+      "artists" | "artist" => RandomItems::Tag(String::from("artist")),
+      "albums" | "album" => RandomItems::Tag(String::from("album")),
+      tag => RandomItems::Tag(String::from(tag)),
+    }
+  }
+
+  /// Name of the items, for messages.
+  pub fn name(&self) -> &str {
+    match self {
+      RandomItems::Songs => "songs",
+      RandomItems::Tag(tag) => tag,
+    }
+  }
+
+  /// Term matching the values of the items.
+  fn term(&self) -> Term<'_> {
+    match self {
+      RandomItems::Songs => Term::File,
+      RandomItems::Tag(tag) => Term::Tag(Cow::Borrowed(tag)),
+    }
+  }
+
+  /// Values of the items in the database (unsorted, possibly repeated).
+  pub fn candidates<S: Read + Write>(&self, client: &mut Client<S>) -> Result<Vec<String>, Error> {
+    match self {
+      // Every song was modified since the epoch ("list file" is deprecated)
+      RandomItems::Songs => {
+        let mut query = Query::new();
+        let query = query.and(Term::LastMod, "0");
+        return client.find(query, None).map(|songs| songs.into_iter().map(|s| s.file).collect());
+      }
+      RandomItems::Tag(_) => return client.list(&self.term(), &Query::new()),
+    }
+  }
+
+  /// Add an item to the queue, given its value.
+  pub fn add<S: Read + Write>(&self, client: &mut Client<S>, value: &str) -> Result<(), Error> {
+    let mut query = Query::new();
+    let query = query.and(self.term(), value);
+    return client.findadd(query);
+  }
+}
+
+#[test]
+fn test_random_items() {
+  assert_eq!(RandomItems::from_name("Albums"), RandomItems::Tag(String::from("album")));
+  assert_eq!(RandomItems::from_name("genre"), RandomItems::Tag(String::from("genre")));
+  assert_eq!(RandomItems::from_name("song").name(), "songs");
+}
@@ -0,0 +1,302 @@
+use crate::constants::Color;
+use crate::text;
+use ncurses as nc;
+
+/// Attributes of drawn text.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Style {
+  /// Color pair (0 for the default colors of the terminal).
+  pub color: Color,
+  pub bold: bool,
+  pub reverse: bool,
+  pub underline: bool,
+  /// Use the alternate character set of the terminal (e.g. for `$a` codes of
+  /// song formats).
+  pub alt_charset: bool,
+}
+
+impl Style {
+  pub fn color(color: Color) -> Style {
+    Style {
+      color: color,
+      ..Style::default()
+    }
+  }
+
+  pub fn bold(self) -> Style {
+    Style { bold: true, ..self }
+  }
+
+  pub fn reverse(self) -> Style {
+    Style { reverse: true, ..self }
+  }
+
+  pub fn underline(self) -> Style {
+    Style { underline: true, ..self }
+  }
+}
+
+/// Rendering backend of a window. Coordinates are relative to the window, and
+/// whatever is drawn out of it is clipped.
+pub trait Surface {
+  /// Size of the window, as (height, width).
+  fn size(&self) -> (i32, i32);
+
+  /// Print text at a position.
+  fn print(&mut self, y: i32, x: i32, text: &str, style: Style);
+
+  /// Draw a horizontal line of `n` columns, with the line-drawing character
+  /// of the terminal.
+  fn hline(&mut self, y: i32, x: i32, n: i32, style: Style);
+
+  /// Repeat a (single column) symbol `n` times.
+  fn fill(&mut self, y: i32, x: i32, symbol: &str, n: i32, style: Style);
+
+  /// Clear a row from a column to its end.
+  fn clear_to_eol(&mut self, y: i32, x: i32);
+
+  /// Clear the whole window.
+  fn erase(&mut self);
+
+  /// Change the style of a whole row, keeping its text.
+  fn set_row_style(&mut self, y: i32, style: Style);
+
+  /// Show what was drawn.
+  fn refresh(&mut self);
+}
+
+/// Surface drawing in an ncurses window (or pad).
+pub struct NcWindow(pub nc::WINDOW);
+
+impl NcWindow {
+  fn attrs(style: Style) -> nc::attr_t {
+    let mut attrs = nc::COLOR_PAIR(style.color) as nc::attr_t;
+    if style.bold {
+      attrs |= nc::A_BOLD() as nc::attr_t;
+    }
+    if style.reverse {
+      attrs |= nc::A_REVERSE() as nc::attr_t;
+    }
+    if style.underline {
+      attrs |= nc::A_UNDERLINE() as nc::attr_t;
+    }
+    if style.alt_charset {
+      attrs |= nc::A_ALTCHARSET() as nc::attr_t;
+    }
+    return attrs;
+  }
+}
+
+impl Surface for NcWindow {
+  fn size(&self) -> (i32, i32) {
+    let mut max_x = 0;
+    let mut max_y = 0;
+    nc::getmaxyx(self.0, &mut max_y, &mut max_x);
+    return (max_y, max_x);
+  }
+
+  fn print(&mut self, y: i32, x: i32, text: &str, style: Style) {
+    let attrs = NcWindow::attrs(style);
+    nc::wattron(self.0, attrs);
+    // Unlike mvwprintw, waddstr does not interpret '%' in the text
+    nc::wmove(self.0, y, x);
+    nc::waddstr(self.0, text);
+    nc::wattroff(self.0, attrs);
+  }
+
+  fn hline(&mut self, y: i32, x: i32, n: i32, style: Style) {
+    let attrs = NcWindow::attrs(style);
+    nc::wattron(self.0, attrs);
+    nc::mvwhline(self.0, y, x, nc::ACS_HLINE(), n);
+    nc::wattroff(self.0, attrs);
+  }
+
+  fn fill(&mut self, y: i32, x: i32, symbol: &str, n: i32, style: Style) {
+    let attrs = NcWindow::attrs(style);
+    nc::wattron(self.0, attrs);
+    nc::wmove(self.0, y, x);
+    for _ in 0..n {
+      nc::waddstr(self.0, symbol);
+    }
+    nc::wattroff(self.0, attrs);
+  }
+
+  fn clear_to_eol(&mut self, y: i32, x: i32) {
+    nc::wmove(self.0, y, x);
+    nc::wclrtoeol(self.0);
+  }
+
+  fn erase(&mut self) {
+    nc::werase(self.0);
+  }
+
+  fn set_row_style(&mut self, y: i32, style: Style) {
+    let attrs = NcWindow::attrs(Style { color: 0, ..style });
+    nc::mvwchgat(self.0, y, 0, -1, attrs, style.color);
+  }
+
+  fn refresh(&mut self) {
+    nc::wrefresh(self.0);
+  }
+}
+
+/// Column of a `CellBuffer`, with its style.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Cell {
+  /// Character shown in the column (with its combining marks), empty for the
+  /// second column of a wide character.
+  pub symbol: String,
+  pub style: Style,
+}
+
+impl Default for Cell {
+  fn default() -> Cell {
+    Cell {
+      symbol: String::from(" "),
+      style: Style::default(),
+    }
+  }
+}
+
+/// In-memory surface, to render windows without a terminal (e.g. in tests).
+#[derive(Clone, Debug)]
+pub struct CellBuffer {
+  height: i32,
+  width: i32,
+  cells: Vec<Cell>,
+}
+
+impl CellBuffer {
+  pub fn new(height: i32, width: i32) -> CellBuffer {
+    let (height, width) = (height.max(0), width.max(0));
+    CellBuffer {
+      height: height,
+      width: width,
+      cells: vec![Cell::default(); (height * width) as usize],
+    }
+  }
+
+  /// Cell at a position, if in the buffer.
+  pub fn cell(&self, y: i32, x: i32) -> Option<&Cell> {
+    if y < 0 || x < 0 || y >= self.height || x >= self.width {
+      return None;
+    }
+    return self.cells.get((y * self.width + x) as usize);
+  }
+
+  fn cell_mut(&mut self, y: i32, x: i32) -> Option<&mut Cell> {
+    if y < 0 || x < 0 || y >= self.height || x >= self.width {
+      return None;
+    }
+    return self.cells.get_mut((y * self.width + x) as usize);
+  }
+
+  /// Text of a row, without its trailing spaces.
+  pub fn row(&self, y: i32) -> String {
+    let text: String = (0..self.width).filter_map(|x| self.cell(y, x)).map(|c| c.symbol.as_str()).collect();
+    return String::from(text.trim_end());
+  }
+
+  /// Text of the whole buffer, one line per row.
+  pub fn text(&self) -> String {
+    let rows: Vec<String> = (0..self.height).map(|y| self.row(y)).collect();
+    return rows.join("\n");
+  }
+}
+
+impl Surface for CellBuffer {
+  fn size(&self) -> (i32, i32) {
+    return (self.height, self.width);
+  }
+
+  fn print(&mut self, y: i32, x: i32, text: &str, style: Style) {
+    let mut x = x;
+    for (g, w) in text::graphemes(text) {
+      // Like terminals, wide characters not fitting at the end of the row are
+      // not shown
+      if w > 1 && x + w as i32 > self.width {
+        break;
+      }
+      let symbol = String::from(g);
+      if let Some(cell) = self.cell_mut(y, x) {
+        *cell = Cell {
+          symbol: symbol,
+          style: style,
+        };
+      }
+      for i in 1..w as i32 {
+        if let Some(cell) = self.cell_mut(y, x + i) {
+          *cell = Cell {
+            symbol: String::new(),
+            style: style,
+          };
+        }
+      }
+      x += w as i32;
+    }
+  }
+
+  fn hline(&mut self, y: i32, x: i32, n: i32, style: Style) {
+    self.fill(y, x, "─", n, style);
+  }
+
+  fn fill(&mut self, y: i32, x: i32, symbol: &str, n: i32, style: Style) {
+    for i in 0..n.max(0) {
+      if let Some(cell) = self.cell_mut(y, x + i) {
+        *cell = Cell {
+          symbol: String::from(symbol),
+          style: style,
+        };
+      }
+    }
+  }
+
+  fn clear_to_eol(&mut self, y: i32, x: i32) {
+    for i in x.max(0)..self.width {
+      if let Some(cell) = self.cell_mut(y, i) {
+        *cell = Cell::default();
+      }
+    }
+  }
+
+  fn erase(&mut self) {
+    for cell in self.cells.iter_mut() {
+      *cell = Cell::default();
+    }
+  }
+
+  fn set_row_style(&mut self, y: i32, style: Style) {
+    for x in 0..self.width {
+      if let Some(cell) = self.cell_mut(y, x) {
+        cell.style = style;
+      }
+    }
+  }
+
+  fn refresh(&mut self) {}
+}
+
+#[test]
+fn test_cell_buffer() {
+  let mut buf = CellBuffer::new(3, 8);
+  buf.print(0, 2, "hello world", Style::color(3).bold());
+  buf.hline(1, 0, 3, Style::default());
+  buf.fill(1, 3, "=", 1, Style::default());
+  buf.print(2, -2, "100%", Style::default());
+  assert_eq!(buf.text(), "  hello\n───=\n0%");
+  assert_eq!(buf.cell(0, 2).unwrap().style, Style::color(3).bold());
+  assert_eq!(buf.cell(3, 0), None);
+
+  // Wide characters take two columns
+  let mut wide = CellBuffer::new(2, 5);
+  wide.print(0, 0, "東京事変", Style::default());
+  wide.print(1, 0, "Cafe\u{301}!", Style::default());
+  assert_eq!(wide.text(), "東京\nCafe\u{301}!");
+  assert_eq!(wide.cell(0, 1).unwrap().symbol, "");
+  assert_eq!(wide.cell(0, 4).unwrap().symbol, " ");
+
+  buf.clear_to_eol(0, 4);
+  buf.set_row_style(1, Style::default().reverse());
+  assert_eq!(buf.row(0), "  he");
+  assert!(buf.cell(1, 7).unwrap().style.reverse);
+}
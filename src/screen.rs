@@ -0,0 +1,209 @@
+use crate::model::Model;
+use crate::view::{ActiveWindow, MouseEvent};
+
+/// Screen shown in a pane of the main window.
+pub trait Screen: Sync {
+  fn window(&self) -> ActiveWindow;
+
+  /// Draw the screen in its pane.
+  fn draw(&self, model: &mut Model);
+
+  /// Whether a screen-specific action (see `SCREEN_ACTIONS`) can be run while
+  /// the screen is focused.
+  fn can_run(&self, _model: &Model, _action: &str) -> bool {
+    return false;
+  }
+
+  /// Handle a mouse event targeting the screen (wheel events excepted).
+  fn handle_mouse(&self, _model: &mut Model, _event: MouseEvent) {}
+
+  /// Scroll the list of the screen by some rows.
+  fn scroll(&self, _model: &mut Model, _offset: i32) {}
+
+  /// Number of rows scrolled by a page.
+  fn page_height(&self, model: &Model) -> i32 {
+    return model.pane_height();
+  }
+}
+
+/// Actions only run when the focused screen handles them.
+pub const SCREEN_ACTIONS: &[&str] = &[
+  "apply_filter",
+  "find",
+  "find_item_backward",
+  "find_item_forward",
+  "jump_to_playing_song",
+  "next_found_item",
+  "page_down",
+  "page_up",
+  "play_selected",
+  "playlist_delete_items",
+  "previous_found_item",
+  "scroll_down",
+  "scroll_up",
+  "set_selected_items_priority",
+];
+
+/// Actions moving in a list, or finding its items.
+const LIST_ACTIONS: &[&str] = &[
+  "find_item_backward",
+  "find_item_forward",
+  "next_found_item",
+  "page_down",
+  "page_up",
+  "previous_found_item",
+  "scroll_down",
+  "scroll_up",
+];
+
+struct HelpScreen;
+
+impl Screen for HelpScreen {
+  fn window(&self) -> ActiveWindow {
+    ActiveWindow::Help
+  }
+
+  fn draw(&self, model: &mut Model) {
+    model.update_help();
+  }
+
+  fn can_run(&self, _model: &Model, action: &str) -> bool {
+    return action == "find" || LIST_ACTIONS.contains(&action);
+  }
+
+  fn scroll(&self, model: &mut Model, offset: i32) {
+    model.scroll_help(offset);
+  }
+}
+
+struct PlaylistScreen;
+
+impl Screen for PlaylistScreen {
+  fn window(&self) -> ActiveWindow {
+    ActiveWindow::Playlist
+  }
+
+  fn draw(&self, model: &mut Model) {
+    model.update_playlist();
+  }
+
+  fn can_run(&self, model: &Model, action: &str) -> bool {
+    match action {
+      "play_selected" | "playlist_delete_items" | "set_selected_items_priority" => model.has_selected_song(),
+      "jump_to_playing_song" => model.has_playing_song(),
+      "apply_filter" => true,
+      _ => LIST_ACTIONS.contains(&action),
+    }
+  }
+
+  fn handle_mouse(&self, model: &mut Model, event: MouseEvent) {
+    match event {
+      MouseEvent::SetSelectedSong(row, id) => model.click_song(row, id),
+      MouseEvent::PlaySong(row, id) => model.play_clicked_song(row, id),
+      MouseEvent::SortBy(property) => model.sort_playlist_by(&property),
+      _ => {}
+    }
+  }
+
+  fn scroll(&self, model: &mut Model, offset: i32) {
+    model.scroll_playlist(offset);
+  }
+
+  fn page_height(&self, model: &Model) -> i32 {
+    return model.playlist_height();
+  }
+}
+
+struct ServerInfoScreen;
+
+impl Screen for ServerInfoScreen {
+  fn window(&self) -> ActiveWindow {
+    ActiveWindow::ServerInfo
+  }
+
+  fn draw(&self, model: &mut Model) {
+    model.update_server_info();
+  }
+}
+
+/// Registered screens, in the order of their header tabs.
+static SCREENS: &[&dyn Screen] = &[&HelpScreen, &PlaylistScreen, &ServerInfoScreen];
+
+/// All the screens, in the order of their header tabs.
+pub fn screens() -> &'static [&'static dyn Screen] {
+  return SCREENS;
+}
+
+/// Screen shown for a window.
+pub fn get_screen(window: ActiveWindow) -> &'static dyn Screen {
+  return *SCREENS.iter().find(|s| s.window() == window).unwrap();
+}
+
+/// Screens visited by `next_screen` and `previous_screen`
+/// (`screen_switcher_mode`).
+#[derive(Clone, PartialEq, Debug)]
+pub enum ScreenSwitcherMode {
+  /// Switch between the current and the previous screens.
+  Previous,
+  /// Cycle through a sequence of screens.
+  Sequence(Vec<ActiveWindow>),
+}
+
+impl ScreenSwitcherMode {
+  /// Parse "previous", or a comma-separated list of screens. Screens of
+  /// ncmpcpp that are not supported are ignored.
+  pub fn from_name(name: &str) -> ScreenSwitcherMode {
+    if name.trim() == "previous" {
+      return ScreenSwitcherMode::Previous;
+    }
+    let screens = name.split(',').filter_map(|s| ActiveWindow::from_name(s.trim())).collect();
+    return ScreenSwitcherMode::Sequence(screens);
+  }
+
+  /// Screen to switch to from the current one, going forward or backward.
+  /// Sequences of less than two screens cycle through all the screens.
+  pub fn switch(&self, current: ActiveWindow, previous: Option<ActiveWindow>, forward: bool) -> Option<ActiveWindow> {
+    let sequence = match self {
+      ScreenSwitcherMode::Previous => return previous,
+      ScreenSwitcherMode::Sequence(s) if s.len() >= 2 => s.as_slice(),
+      ScreenSwitcherMode::Sequence(_) => ActiveWindow::all(),
+    };
+    let n = sequence.len();
+    let idx = match sequence.iter().position(|w| *w == current) {
+      Some(i) if forward => (i + 1) % n,
+      Some(i) => (i + n - 1) % n,
+      None => 0,
+    };
+    return Some(sequence[idx]);
+  }
+}
+
+impl Default for ScreenSwitcherMode {
+  fn default() -> ScreenSwitcherMode {
+    ScreenSwitcherMode::Sequence(vec![ActiveWindow::Playlist])
+  }
+}
+
+#[test]
+fn test_screen_switcher() {
+  use crate::view::ActiveWindow::*;
+
+  assert!(screens().iter().map(|s| s.window()).eq(ActiveWindow::all().iter().cloned()));
+  assert_eq!(get_screen(ServerInfo).window().title(), "Server info");
+
+  let mode = ScreenSwitcherMode::from_name("previous");
+  assert_eq!(mode.switch(Help, Some(Playlist), true), Some(Playlist));
+  assert_eq!(mode.switch(Help, None, false), None);
+
+  let mode = ScreenSwitcherMode::from_name("server_info, browser, playlist");
+  assert_eq!(mode, ScreenSwitcherMode::Sequence(vec![ServerInfo, Playlist]));
+  assert_eq!(mode.switch(Playlist, None, true), Some(ServerInfo));
+  assert_eq!(mode.switch(ServerInfo, None, false), Some(Playlist));
+  // Screens out of the sequence go to its start
+  assert_eq!(mode.switch(Help, None, false), Some(ServerInfo));
+
+  // The default sequence has only one supported screen
+  let mode = ScreenSwitcherMode::from_name("playlist, browser");
+  assert_eq!(mode.switch(Playlist, None, true), Some(ServerInfo));
+  assert_eq!(mode.switch(Help, None, false), Some(ServerInfo));
+}
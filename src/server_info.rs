@@ -3,14 +3,17 @@ extern crate mpd;
 use chrono::{DateTime, Local, TimeZone};
 use crate::config::ParamConfig;
 use crate::constants::*;
+use crate::layout::Rect;
+use crate::text;
 use ncurses as nc;
+use std::cmp;
 use std::net::TcpStream;
 use time::{Duration, Timespec};
 use crate::view::bold;
 
 /// Format duration for server info, e.g.:
 /// 34d, 5h, 57m, 53s
-fn format_duration(duration: &Duration) -> String {
+pub fn format_duration(duration: &Duration) -> String {
   let mut s = String::from("");
   let days = duration.num_days();
   let hours = duration.num_hours() - days * 24;
@@ -66,6 +69,14 @@ fn format_date(ts: &Timespec) -> String {
   return date.format("%m/%d/%Y %I:%M:%S %p").to_string();
 }
 
+/// Size of the box showing the server info, given the size of its area.
+fn box_size(height: i32, width: i32) -> (i32, i32) {
+  return (
+    cmp::max(1, (0.8 * (height as f32)) as i32),
+    cmp::max(1, (0.6 * (width as f32)) as i32),
+  );
+}
+
 pub struct ServerInfo {
   border_pad: nc::WINDOW,
   pad: nc::WINDOW,
@@ -82,8 +93,7 @@ impl ServerInfo {
     let mut max_x = 0;
     let mut max_y = 0;
     nc::getmaxyx(win, &mut max_y, &mut max_x);
-    max_x = (0.6 * (max_x as f32)) as i32;
-    max_y = (0.8 * (max_y as f32)) as i32;
+    let (max_y, max_x) = box_size(max_y, max_x);
     ServerInfo {
       border_pad: nc::newpad(max_y + 2, max_x + 2),
       pad: nc::newpad(max_y, max_x),
@@ -96,6 +106,20 @@ impl ServerInfo {
     }
   }
 
+  /// Resize the box to fit in an area of the screen.
+  fn resize(&mut self, rect: Rect) {
+    let (max_y, max_x) = box_size(rect.height, rect.width);
+    if max_y != self.max_y || max_x != self.max_x {
+      nc::delwin(self.border_pad);
+      nc::delwin(self.pad);
+      self.border_pad = nc::newpad(max_y + 2, max_x + 2);
+      self.pad = nc::newpad(max_y, max_x);
+      self.max_x = max_x;
+      self.max_y = max_y;
+      self.total_rows = 0;
+    }
+  }
+
   fn newline(&mut self) {
     self.current_row += 1;
   }
@@ -117,7 +141,7 @@ impl ServerInfo {
   }
 
   fn entry(&mut self, name: &str, value: &str) {
-    let len = name.len() as i32;
+    let len = text::width(name) as i32;
     nc::wattron(self.pad, bold());
     nc::mvwprintw(self.pad, self.current_row, 2 * self.tab_size, &name);
     nc::mvwprintw(self.pad, self.current_row, 2 * self.tab_size + len, ": ");
@@ -126,7 +150,9 @@ impl ServerInfo {
     self.current_row += 1;
   }
 
-  pub fn print(&mut self, client: &mut mpd::Client<TcpStream>) {
+  /// Print the server info in a box, centered in an area of the screen.
+  pub fn print(&mut self, client: &mut mpd::Client<TcpStream>, rect: Rect) {
+    self.resize(rect);
     nc::wclear(self.pad);
     self.current_row = 0;
 
@@ -169,8 +195,8 @@ impl ServerInfo {
       self.total_rows = self.current_row;
     }
 
-    let x_offset = (0.30 * (self.max_x as f32)) as i32;
-    let y_offset = (0.20 * (self.max_y as f32)) as i32;
+    let x_offset = rect.x + (rect.width - self.max_x) / 2;
+    let y_offset = rect.y + (rect.height - self.max_y) / 2;
     nc::prefresh(
       self.border_pad,
       0,
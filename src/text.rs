@@ -0,0 +1,99 @@
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Character shown at the end of truncated text.
+pub const ELLIPSIS: &str = "…";
+
+/// Number of columns taken by text on the terminal (e.g. 2 for each CJK
+/// character, 0 for combining marks).
+pub fn width(s: &str) -> usize {
+  return UnicodeWidthStr::width(s);
+}
+
+/// Split text into the characters shown on the terminal (extended grapheme
+/// clusters, e.g. a letter with its combining marks, or a flag), with their
+/// widths.
+pub fn graphemes(s: &str) -> Vec<(&str, usize)> {
+  return s.graphemes(true).map(|g| (g, width(g))).collect();
+}
+
+/// Longest start of the text taking at most `max_width` columns, without
+/// splitting its characters.
+pub fn truncate(s: &str, max_width: usize) -> &str {
+  let mut used = 0;
+  let mut end = 0;
+  for (g, w) in graphemes(s) {
+    if used + w > max_width {
+      break;
+    }
+    used += w;
+    end += g.len();
+  }
+  return &s[..end];
+}
+
+/// Text fitting in `max_width` columns, ending with an ellipsis if it had to
+/// be truncated.
+pub fn fit(s: &str, max_width: usize) -> Cow<'_, str> {
+  if width(s) <= max_width {
+    return Cow::Borrowed(s);
+  }
+  if max_width == 0 {
+    return Cow::Borrowed("");
+  }
+  return Cow::Owned(format!("{}{}", truncate(s, max_width - 1), ELLIPSIS));
+}
+
+/// Text fitted in exactly `width` columns, padded with spaces.
+pub fn pad(s: &str, width: usize) -> String {
+  let mut text = fit(s, width).into_owned();
+  let free = width - self::width(&text);
+  text.extend((0..free).map(|_| ' '));
+  return text;
+}
+
+#[test]
+fn test_width() {
+  assert_eq!(width("So What"), 7);
+  assert_eq!(width("東京事変"), 8);
+  assert_eq!(width("e\u{301}te\u{301}"), 3);
+  assert_eq!(width(""), 0);
+}
+
+#[test]
+fn test_graphemes() {
+  let g = |s| graphemes(s).iter().map(|&(g, w)| (g.to_string(), w)).collect::<Vec<_>>();
+  let owned = |v: &[(&str, usize)]| v.iter().map(|&(g, w)| (g.to_string(), w)).collect::<Vec<_>>();
+
+  assert_eq!(g("ab"), owned(&[("a", 1), ("b", 1)]));
+  assert_eq!(g("日本"), owned(&[("日", 2), ("本", 2)]));
+  // Combining marks and variation selectors
+  assert_eq!(g("e\u{301}x"), owned(&[("e\u{301}", 1), ("x", 1)]));
+  assert_eq!(g("\u{2764}\u{fe0f}!"), owned(&[("\u{2764}\u{fe0f}", 2), ("!", 1)]));
+  // Emoji sequences and flags
+  let family = "\u{1f469}\u{200d}\u{1f467}";
+  assert_eq!(g(family).len(), 1);
+  assert_eq!(g("\u{1f1eb}\u{1f1f7}\u{1f1ef}\u{1f1f5}").len(), 2);
+  assert!(g("").is_empty());
+}
+
+#[test]
+fn test_truncate() {
+  assert_eq!(truncate("Naima", 3), "Nai");
+  assert_eq!(truncate("Naima", 10), "Naima");
+  // Wide characters are never split
+  assert_eq!(truncate("東京事変", 5), "東京");
+  assert_eq!(truncate("Cafe\u{301} Blue", 4), "Cafe\u{301}");
+
+  assert_eq!(fit("Kind of Blue", 12), "Kind of Blue");
+  assert_eq!(fit("Kind of Blue", 8), "Kind of…");
+  assert_eq!(fit("東京事変", 6), "東京…");
+  assert_eq!(fit("東京事変", 5), "東京…");
+  assert_eq!(fit("Blue", 1), "…");
+  assert_eq!(fit("Blue", 0), "");
+
+  assert_eq!(pad("Blue", 6), "Blue  ");
+  assert_eq!(pad("東京事変", 6), "東京… ");
+  assert_eq!(width(&pad("東京事変", 4)), 4);
+}
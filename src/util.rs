@@ -1,3 +1,4 @@
+use crate::text;
 use std::ops::Deref;
 use time::{get_time, Duration, Timespec};
 
@@ -64,6 +65,22 @@ impl<T> CachedValue<T> {
     return &self.value;
   }
 
+  /// Get the current cached value, or evaluate it. The cached value is kept
+  /// if the evaluation fails.
+  pub fn try_get_or<F, E>(&mut self, f: F) -> Result<&T, E>
+  where
+    F: FnOnce() -> Result<T, E>,
+  {
+    let ts = get_time();
+
+    if ts > self.timestamp + self.max_duration {
+      self.value = f()?;
+      self.timestamp = ts;
+    }
+
+    return Ok(&self.value);
+  }
+
   /// Whether the current cached value is valid.
   #[inline]
   pub fn is_cached(&self) -> bool {
@@ -83,6 +100,12 @@ impl<T> CachedValue<T> {
     self.value = value;
     self.timestamp = get_time();
   }
+
+  /// Let the cached value expire, to evaluate it again at the next access.
+  #[inline]
+  pub fn invalidate(&mut self) {
+    self.timestamp = Timespec::new(0, 0);
+  }
 }
 
 impl<T> Deref for CachedValue<T> {
@@ -141,56 +164,85 @@ impl Scroller {
   }
 
   pub fn display(&mut self) -> &str {
+    // Case 1: we can simply return the full text.
+    if self.width >= text::width(&self.text) {
+      return &self.text;
+    }
+
+    // Case 2: show `width` columns of the text followed by the separator,
+    // looping, and starting from the `pos`-th character.
+    let looped = format!("{}{}", self.text, self.separator);
+    let graphemes = text::graphemes(&looped);
+
     // Update pos.
     let current_t = get_time();
     if self.pos_update_time + self.dt < current_t {
-      self.pos = if 1 + self.pos < self.text.len() + self.separator.len() {
-        self.pos + 1
-      } else {
-        0
-      };
+      self.pos = (self.pos + 1) % graphemes.len();
       self.pos_update_time = current_t;
     }
-    // Case 1: we can simply return the full text.
-    if self.width >= self.text.len() {
-      return &self.text;
-    }
-    // Case 2: the partial text does not reach the end of the text.
-    else if self.pos + self.width <= self.text.len() {
-      // We can simply return the full text.
-      return &self.text[self.pos..self.pos + self.width];
-    }
-    // Case 3: we need to print both the end and the beginning of the string,
-    // with a separator.
-    else {
-      self.temp.clear();
-
-      // End of the text
-      if self.pos < self.text.len() {
-        self.temp.push_str(&self.text[self.pos..]);
-      }
 
-      // Separator
-      let mut free_len: i32 = self.width as i32 - self.temp.len() as i32;
-      let start_sep: i32 = if self.pos >= self.text.len() {
-        self.pos as i32 - self.text.len() as i32
-      } else {
-        0
-      };
-      if free_len < self.separator.len() as i32 - start_sep as i32 {
-        self.temp.push_str(&self.separator[start_sep as usize..free_len as usize]);
-      } else {
-        self.temp.push_str(&self.separator[start_sep as usize..]);
+    self.temp.clear();
+    let mut used = 0;
+    for &(g, w) in graphemes.iter().cycle().skip(self.pos % graphemes.len()) {
+      if used + w > self.width {
+        break;
       }
+      self.temp.push_str(g);
+      used += w;
+    }
+    // Fill the column of a wide character that did not fit
+    self.temp.extend((used..self.width).map(|_| ' '));
+    return &self.temp;
+  }
+}
 
-      // Beginning of the text
-      free_len = self.width as i32 - self.temp.len() as i32;
-      if free_len >= 0 {
-        self.temp.push_str(&self.text[..free_len as usize]);
-      }
-      assert!(self.temp.len() == self.width, "error in scrolled message size");
-      return &self.temp;
+/// Small pseudo-random number generator (xorshift64*), reproducible from a
+/// seed.
+#[derive(Clone, Debug)]
+pub struct Rng {
+  state: u64,
+}
+
+impl Rng {
+  pub fn new(seed: u64) -> Rng {
+    // Scramble the seed (splitmix64) so that small seeds give a non-zero state
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+    return Rng {
+      state: if z == 0 { 1 } else { z },
+    };
+  }
+
+  /// Generator seeded with the current time.
+  pub fn from_time() -> Rng {
+    let t = get_time();
+    Rng::new((t.sec as u64).wrapping_mul(1_000_000_000).wrapping_add(t.nsec as u64))
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    self.state ^= self.state >> 12;
+    self.state ^= self.state << 25;
+    self.state ^= self.state >> 27;
+    return self.state.wrapping_mul(0x2545_f491_4f6c_dd1d);
+  }
+
+  /// Random number in `0..n` (`n` must not be 0).
+  pub fn below(&mut self, n: usize) -> usize {
+    (self.next_u64() % n as u64) as usize
+  }
+
+  /// Pick up to `count` distinct items, in random order.
+  pub fn sample<T: Clone>(&mut self, items: &[T], count: usize) -> Vec<T> {
+    let mut idx: Vec<usize> = (0..items.len()).collect();
+    let count = count.min(idx.len());
+    // Partial Fisher-Yates shuffle
+    for i in 0..count {
+      let j = i + self.below(idx.len() - i);
+      idx.swap(i, j);
     }
+    return idx[..count].iter().map(|&i| items[i].clone()).collect();
   }
 }
 
@@ -205,4 +257,51 @@ fn check_cached_value() {
   assert_eq!(*v.get(), 1);
   assert_eq!(*v.get_or(|| 2), 2);
   assert_eq!(*v.get(), 2);
+
+  // Failed evaluations keep the cached value
+  thread::sleep(time::Duration::from_millis(200));
+  assert_eq!(v.try_get_or(|| Err("error")), Err("error"));
+  assert_eq!(v.try_get_or(|| Ok::<_, ()>(3)), Ok(&3));
+
+  v.invalidate();
+  assert_eq!(*v.get_or(|| 4), 4);
+  assert_eq!(*v.get_or(|| 5), 4);
+}
+
+#[test]
+fn check_scroller() {
+  let mut s = Scroller::new(10);
+  s.set_text("Short");
+  assert_eq!(s.display(), "Short");
+
+  s.set_text("Miles Davis - So What");
+  assert_eq!(s.display(), "Miles Davi");
+  s.pos = 17;
+  assert_eq!(s.display(), "What ** Mi");
+
+  // Multi-byte and wide characters are never split
+  s.resize(7);
+  s.set_text("Café 東京事変");
+  s.pos = 3;
+  assert_eq!(s.display(), "é 東京 ");
+  s.pos = 7;
+  assert_eq!(s.display(), "事変 **");
+  assert_eq!(text::width(s.display()), 7);
+}
+
+#[test]
+fn check_rng_sample() {
+  let items: Vec<u32> = (0..50).collect();
+
+  // Same seed, same items
+  let a = Rng::new(42).sample(&items, 10);
+  assert_eq!(a, Rng::new(42).sample(&items, 10));
+  assert_ne!(a, Rng::new(43).sample(&items, 10));
+
+  // Distinct items, at most all of them
+  let mut b = Rng::new(7).sample(&items, 100);
+  assert_eq!(b.len(), 50);
+  b.sort();
+  assert_eq!(b, items);
+  assert!(Rng::new(7).sample(&Vec::<u32>::new(), 3).is_empty());
 }
@@ -6,18 +6,30 @@ use crate::config::{ColorConfig, Config, ParamConfig};
 use crate::constants::*;
 use crate::format::*;
 use crate::help::*;
+use crate::hit_test::{Area, HitMap, Target};
+use crate::layout::{Layout, Rect};
+use crate::model::SongId;
+use crate::render::{NcWindow, Style, Surface};
+#[cfg(test)]
+use crate::render::CellBuffer;
+use crate::screen::screens;
 use crate::server_info::*;
+use crate::text;
 use ncurses as nc;
 
 use crate::util::{Scroller, TimedValue};
+use std::env;
+use std::io::{self, Write};
 use std::fmt::{self, Display, Formatter};
 use std::net::TcpStream;
-use std::{char, cmp, mem};
+use std::{char, cmp};
 use time::{get_time, Duration, Timespec};
 
 pub struct PlaylistInfo {
   pub size: u32,
   pub duration: Duration,
+  /// Time left until the end of the playlist, from the playing song.
+  pub remaining: Duration,
 }
 
 impl PlaylistInfo {
@@ -25,8 +37,43 @@ impl PlaylistInfo {
     PlaylistInfo {
       size: 0,
       duration: Duration::seconds(0),
+      remaining: Duration::seconds(0),
     }
   }
+
+  /// Describe the playlist, e.g. "4 items, length: 30m, 11s :: remaining:
+  /// 12m, 5s" with the remaining time and shortened times.
+  pub fn describe(&self, show_remaining: bool, shorten: bool) -> String {
+    if self.size == 0 {
+      return String::from("0 item");
+    }
+
+    // Streams have no length
+    let mut s = format!("{} items", self.size);
+    if self.duration > Duration::zero() {
+      s.push_str(&format!(", length: {}", format_length(&self.duration, shorten)));
+    }
+    if show_remaining && self.remaining > Duration::zero() {
+      s.push_str(&format!(" :: remaining: {}", format_length(&self.remaining, shorten)));
+    }
+    return s;
+  }
+}
+
+/// Format the length of a playlist, e.g. "1 hour, 5 minutes, 3 seconds", or
+/// "1h, 5m, 3s" when shortened.
+fn format_length(duration: &Duration, shorten: bool) -> String {
+  if shorten {
+    return format_duration(duration);
+  }
+
+  let n_h = duration.num_hours();
+  let n_min = duration.num_minutes() % 60;
+  let n_sec = duration.num_seconds() % 60;
+  let s_h = if n_h > 1 { "s" } else { "" };
+  let s_min = if n_min > 1 { "s" } else { "" };
+  let s_sec = if n_sec > 1 { "s" } else { "" };
+  return format!("{} hour{}, {} minute{}, {} second{}", n_h, s_h, n_min, s_min, n_sec, s_sec);
 }
 
 impl Default for PlaylistInfo {
@@ -37,40 +84,38 @@ impl Default for PlaylistInfo {
 
 impl Display for PlaylistInfo {
   fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-    if self.size == 0 {
-      write!(f, "0 item")
-    } else {
-      let n_h = self.duration.num_hours();
-      let n_min = self.duration.num_minutes() % 60;
-      let n_sec = self.duration.num_seconds() % 60;
-      let s_h = if n_h > 1 { "s" } else { "" };
-      let s_min = if n_min > 1 { "s" } else { "" };
-      let s_sec = if n_sec > 1 { "s" } else { "" };
-      write!(
-        f,
-        "{} items, length: {} hour{}, {} minute{}, {} second{}",
-        self.size, n_h, s_h, n_min, s_min, n_sec, s_sec
-      )
-    }
+    write!(f, "{}", self.describe(false, false))
   }
 }
 
 pub enum MouseEvent {
   /// Set the song progress (percentage).
   SetProgress(f32),
-  /// Set the selected song (TUI).
-  SetSelectedSong(u32),
+  /// Set the volume.
+  SetVolume(i8),
+  /// Set the selected song, given its row (TUI).
+  SetSelectedSong(u32, Option<SongId>),
+  /// Play the pointed song, given its row (TUI).
+  PlaySong(u32, Option<SongId>),
+  /// Sort the displayed playlist by a column.
+  SortBy(SongProperty),
+  /// Show a screen.
+  ShowScreen(ActiveWindow),
   /// Scroll down.
   ScrollDown,
   /// Scroll up.
   ScrollUp,
+  /// Raise the volume.
+  VolumeUp,
+  /// Lower the volume.
+  VolumeDown,
   /// Wake up click (re-highlight selected song).
   WakeUp,
   /// Do nothing.
   Nothing,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ActiveWindow {
   /// Displaying help window.
   Help,
@@ -80,19 +125,141 @@ pub enum ActiveWindow {
   ServerInfo,
 }
 
+impl ActiveWindow {
+  /// Name of the screen, as used by ncmpcpp (e.g. in `require_screen`).
+  pub fn name(&self) -> &'static str {
+    match *self {
+      ActiveWindow::Help => "help",
+      ActiveWindow::Playlist => "playlist",
+      ActiveWindow::ServerInfo => "server_info",
+    }
+  }
+
+  /// All the screens.
+  pub fn all() -> &'static [ActiveWindow] {
+    &[ActiveWindow::Help, ActiveWindow::Playlist, ActiveWindow::ServerInfo]
+  }
+
+  /// Title of the screen, shown in its header tab.
+  pub fn title(&self) -> &'static str {
+    match *self {
+      ActiveWindow::Help => "Help",
+      ActiveWindow::Playlist => "Playlist",
+      ActiveWindow::ServerInfo => "Server info",
+    }
+  }
+
+  /// Screen given its name.
+  pub fn from_name(name: &str) -> Option<ActiveWindow> {
+    return ActiveWindow::all().iter().find(|w| w.name() == name).cloned();
+  }
+}
+
+impl Default for ActiveWindow {
+  fn default() -> ActiveWindow {
+    ActiveWindow::Playlist
+  }
+}
+
+/// Layout of the screen (`user_interface`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UserInterface {
+  /// One-line header, with the screen tabs.
+  Classic,
+  /// Two-line header, showing the playing song.
+  Alternative,
+}
+
+impl UserInterface {
+  pub fn from_name(name: &str) -> Option<UserInterface> {
+    match name {
+      "classic" => Some(UserInterface::Classic),
+      "alternative" => Some(UserInterface::Alternative),
+      _ => None,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      UserInterface::Classic => "classic",
+      UserInterface::Alternative => "alternative",
+    }
+  }
+
+  pub fn toggle(self) -> UserInterface {
+    match self {
+      UserInterface::Classic => UserInterface::Alternative,
+      UserInterface::Alternative => UserInterface::Classic,
+    }
+  }
+}
+
+impl Default for UserInterface {
+  fn default() -> UserInterface {
+    UserInterface::Classic
+  }
+}
+
+/// Part of the main window showing a screen.
+struct Pane {
+  win: nc::WINDOW,
+  screen: ActiveWindow,
+}
+
+impl Pane {
+  fn new(screen: ActiveWindow) -> Pane {
+    let win = nc::newwin(1, 1, 0, 0);
+    nc::keypad(win, true);
+    return Pane { win: win, screen: screen };
+  }
+}
+
 pub struct View {
   header: nc::WINDOW,
   header_scroller: Scroller,
   state: nc::WINDOW,
-  main_win: nc::WINDOW,
+  /// Panes of the main window, the first one showing the locked screen (if
+  /// any) and the second one the slave screen.
+  panes: Vec<Pane>,
+  /// Index of the focused pane, whose screen receives the keys.
+  focused: usize,
+  /// Width of the locked screen (in %), when the screen is locked.
+  locked_width_part: Option<i32>,
+  /// Area of the main window, shared by the panes.
+  main_rect: Rect,
   progressbar: nc::WINDOW,
   progressbar_look: Vec<String>,
   statusbar: nc::WINDOW,
-  statusbar_input: Vec<String>,
+  /// Whether the cursor is visible (when typing a command).
+  cursor_visible: bool,
   pub help: Help,
+  /// Pad holding the whole help, shown in its pane from its first row.
+  help_pad: NcWindow,
   server_info: ServerInfo,
   status_scroller: Scroller,
-  static_rows: i32,
+  /// Layout of the screen.
+  interface: UserInterface,
+  /// Whether the header and the state line are shown.
+  header_visible: bool,
+  /// Whether the statusbar is shown (messages are shown over the progressbar
+  /// otherwise).
+  statusbar_visible: bool,
+  /// Whether a command is being typed, which shows the statusbar even when it
+  /// is hidden.
+  command_line_visible: bool,
+  /// Whether the column titles of the playlist are shown.
+  titles_visible: bool,
+  empty_tag_marker: String,
+  /// Whether the header shows the time left in the playlist.
+  show_remaining_time: bool,
+  /// Whether the lengths shown in the header are shortened.
+  shorten_total_times: bool,
+  /// Clickable regions of the screen.
+  hit_map: HitMap,
+  /// Terminal opened by the caller, if not the real one.
+  terminal: Option<nc::SCREEN>,
+  /// Whether the title of the terminal was saved before being changed.
+  window_title_saved: bool,
 }
 
 fn init_colors(colors: &ColorConfig, params: &ParamConfig) {
@@ -125,21 +292,36 @@ fn init_colors(colors: &ColorConfig, params: &ParamConfig) {
   nc::init_pair(COLOR_PAIR_STATE_FLAGS, colors.state_flags, color_bg);
   nc::init_pair(COLOR_PAIR_TRACK, nc::COLOR_BLACK, color_bg);
   nc::init_pair(COLOR_PAIR_WINDOW_BORDER, colors.window_border, color_bg);
+  nc::init_pair(COLOR_PAIR_ACTIVE_WINDOW_BORDER, colors.active_window_border, color_bg);
+  nc::init_pair(COLOR_PAIR_MAIN_WINDOW, colors.main_window, color_bg);
+  nc::init_pair(COLOR_PAIR_MAIN_WINDOW_HIGHLIGHT, colors.main_window_highlight, color_bg);
+  nc::init_pair(COLOR_PAIR_EMPTY_TAG, colors.empty_tag, color_bg);
+  nc::init_pair(COLOR_PAIR_ACTIVE_COLUMN, colors.active_column, color_bg);
+  nc::init_pair(COLOR_PAIR_ALTERNATIVE_UI_SEPARATOR, colors.alternative_ui_separator, color_bg);
+  nc::init_pair(COLOR_PAIR_COLOR1, colors.color1, color_bg);
+  nc::init_pair(COLOR_PAIR_COLOR2, colors.color2, color_bg);
 
   let columns_fmt = &params.song_columns_list_format;
   assert!(columns_fmt.len() <= MAX_NUM_COLUMNS);
   for (i, col) in columns_fmt.iter().enumerate() {
-    nc::init_pair(COLOR_PAIR_COLUMNS[i as usize], col.color, color_bg);
+    // Columns without a color use the main window color
+    let color = if col.color == COLOR_DEFAULT { colors.main_window } else { col.color };
+    nc::init_pair(COLOR_PAIR_COLUMNS[i as usize], color, color_bg);
   }
 }
 
-fn init_ncurses(config: &Config) {
+/// Start ncurses, on the given terminal if any (the real one otherwise).
+fn init_ncurses(config: &Config, terminal: Option<nc::SCREEN>) {
   // Set locale for unicode support.
   let locale_conf = nc::LcCategory::all;
   nc::setlocale(locale_conf, "en_US.UTF-8");
 
   // Start ncurses.
-  nc::initscr();
+  if let Some(t) = terminal {
+    nc::set_term(t);
+  } else {
+    nc::initscr();
+  }
 
   // Initialize colors.
   init_colors(&config.colors, &config.params);
@@ -160,14 +342,21 @@ fn init_ncurses(config: &Config) {
 
   // Enable mouse events.
   nc::mouseinterval(0);
-  nc::mousemask(
-    (nc::BUTTON1_CLICKED | nc::BUTTON4_PRESSED | nc::BUTTON5_PRESSED) as nc::mmask_t,
-    None,
-  );
+  set_mouse_support(config.params.mouse_support);
 
   nc::clear();
 }
 
+/// Enable or disable mouse events.
+pub fn set_mouse_support(enabled: bool) {
+  let mask = if enabled {
+    nc::BUTTON1_PRESSED | nc::BUTTON1_CLICKED | nc::BUTTON3_PRESSED | nc::BUTTON4_PRESSED | nc::BUTTON5_PRESSED
+  } else {
+    0
+  };
+  nc::mousemask(mask as nc::mmask_t, None);
+}
+
 pub fn get_color(c: Color) -> nc::attr_t {
   return nc::COLOR_PAIR(c) as nc::attr_t;
 }
@@ -176,8 +365,44 @@ pub fn bold() -> nc::attr_t {
   return nc::A_BOLD() as nc::attr_t;
 }
 
-fn reverse() -> nc::attr_t {
-  return nc::A_REVERSE() as nc::attr_t;
+/// Print text with the colors and attributes of a rendered song format.
+fn print_expressions(s: &mut dyn Surface, y: i32, x: i32, expressions: &[Expression]) {
+  let mut x = x;
+  let mut style = Style::default();
+  for e in expressions {
+    match e {
+      Expression::String(t) => {
+        s.print(y, x, t, style);
+        x += text::width(t) as i32;
+      }
+      Expression::Color(c) => style.color = if *c < 0 { COLOR_PAIR_DEFAULT } else { *c + COLOR_PAIR_BLACK },
+      Expression::Format(f) => match f {
+        Format::Bold => style.bold = true,
+        Format::NoBold => style.bold = false,
+        Format::Underline => style.underline = true,
+        Format::NoUnderline => style.underline = false,
+        Format::Reverse => style.reverse = true,
+        Format::NoReverse => style.reverse = false,
+        Format::AltCharset => style.alt_charset = true,
+        Format::NoAltCharset => style.alt_charset = false,
+        Format::None => {}
+      },
+      Expression::SongProperty(_) => {}
+    }
+  }
+}
+
+/// Split the progressbar look into its (elapsed, tip, remaining) parts.
+fn split_progressbar_look(look: &str) -> Vec<String> {
+  let mut iter = look.chars();
+  let mut ar = vec![String::default(); 3];
+  for elt in ar.iter_mut().take(3) {
+    *elt = match iter.next() {
+      Some(c) => c.to_string(),
+      None => String::default(),
+    }
+  }
+  ar
 }
 
 fn deinit_ncurses() {
@@ -185,6 +410,24 @@ fn deinit_ncurses() {
   nc::endwin();
 }
 
+/// Escape sequence pushing the title of the terminal on its title stack.
+const SAVE_WINDOW_TITLE: &str = "\x1b[22;0t";
+/// Escape sequence restoring the title pushed on the title stack.
+const RESTORE_WINDOW_TITLE: &str = "\x1b[23;0t";
+
+/// OSC escape sequence setting the title (and icon name) of the terminal.
+/// Control characters are removed so that tags cannot end the sequence.
+pub fn window_title_sequence(title: &str) -> String {
+  let title: String = title.chars().filter(|c| !c.is_control()).collect();
+  return format!("\x1b]0;{}\x07", title);
+}
+
+/// Write escape sequences to the terminal, bypassing ncurses.
+fn write_to_terminal(s: &str) {
+  let mut out = io::stdout();
+  let _res = out.write_all(s.as_bytes()).and_then(|_| out.flush());
+}
+
 fn destroy_win(win: nc::WINDOW) {
   let ch = ' ' as nc::chtype;
   nc::wborder(win, ch, ch, ch, ch, ch, ch, ch, ch);
@@ -194,44 +437,67 @@ fn destroy_win(win: nc::WINDOW) {
 
 impl View {
   pub fn new(config: &Config) -> View {
-    init_ncurses(config);
+    return View::start(config, None);
+  }
+
+  /// View drawing on a terminal opened with `newterm` instead of the real one
+  /// (e.g. a fake terminal, to run the model in tests). The terminal is not
+  /// deleted with the view.
+  pub fn on_terminal(config: &Config, terminal: nc::SCREEN) -> View {
+    return View::start(config, Some(terminal));
+  }
+
+  fn start(config: &Config, terminal: Option<nc::SCREEN>) -> View {
+    init_ncurses(config, terminal);
 
     let mut max_x = 0;
     let mut max_y = 0;
     nc::getmaxyx(nc::stdscr(), &mut max_y, &mut max_x);
-    let static_rows = 4;
-
-    let main_win = nc::newwin(max_y - static_rows, max_x, 2, 0);
-    let view = View {
+    // The windows are laid out by `set_layout`
+    let main_win = nc::newwin(max_y - 4, max_x, 2, 0);
+    let mut view = View {
       header: nc::newwin(1, max_x, 0, 0),
       header_scroller: Scroller::new(max_x as usize),
       state: nc::newwin(1, max_x, 1, 0),
-      main_win: main_win,
-      progressbar: nc::newwin(1, max_x, max_y - 2, 0),
-      progressbar_look: {
-        let mut iter = config.params.progressbar_look.chars();
-        let mut ar = vec![String::default(); 3];
-        for elt in ar.iter_mut().take(3) {
-          *elt = match iter.next() {
-            Some(c) => c.to_string(),
-            None => String::default(),
-          }
-        }
-        ar
+      panes: vec![Pane {
+        win: main_win,
+        screen: ActiveWindow::Playlist,
+      }],
+      focused: 0,
+      locked_width_part: None,
+      main_rect: Rect {
+        y: 2,
+        x: 0,
+        height: max_y - 4,
+        width: max_x,
       },
+      progressbar: nc::newwin(1, max_x, max_y - 2, 0),
+      progressbar_look: split_progressbar_look(&config.params.progressbar_look),
       statusbar: nc::newwin(1, max_x, max_y - 1, 0),
-      statusbar_input: vec![],
-      help: Help::new(main_win, config),
+      cursor_visible: false,
+      help: Help::new(config),
+      help_pad: NcWindow(nc::newpad(500, max_x)),
       server_info: ServerInfo::new(main_win, &config.params),
       status_scroller: Scroller::new(max_x as usize),
-      static_rows: static_rows,
+      interface: UserInterface::Classic,
+      header_visible: true,
+      statusbar_visible: true,
+      command_line_visible: false,
+      titles_visible: true,
+      empty_tag_marker: config.params.empty_tag_marker.clone(),
+      show_remaining_time: config.params.playlist_show_remaining_time,
+      shorten_total_times: config.params.playlist_shorten_total_times,
+      hit_map: HitMap::new(),
+      terminal: terminal,
+      window_title_saved: false,
     };
+    view.set_layout(&config.params);
     nc::wrefresh(view.header);
     nc::wrefresh(view.state);
-    nc::wrefresh(view.main_win);
+    nc::wrefresh(main_win);
     nc::wrefresh(view.progressbar);
     nc::wrefresh(view.statusbar);
-    nc::keypad(view.main_win, true);
+    nc::keypad(main_win, true);
 
     // Set colors
     nc::wbkgd(view.header, nc::COLOR_PAIR(COLOR_PAIR_DEFAULT) as nc::chtype);
@@ -240,122 +506,324 @@ impl View {
     return view;
   }
 
+  /// Apply a new configuration (e.g. after a reload).
+  pub fn set_config(&mut self, config: &Config) {
+    self.progressbar_look = split_progressbar_look(&config.params.progressbar_look);
+    self.empty_tag_marker = config.params.empty_tag_marker.clone();
+    self.show_remaining_time = config.params.playlist_show_remaining_time;
+    self.shorten_total_times = config.params.playlist_shorten_total_times;
+    self.help.set_config(config);
+    nc::wclear(self.help_pad.0);
+    set_mouse_support(config.params.mouse_support);
+    self.set_layout(&config.params);
+    self.set_colors(&config.colors, &config.params);
+  }
+
+  /// Change the user interface, show or hide the header, the statusbar and
+  /// the column titles, and lay out the windows again if needed.
+  pub fn set_layout(&mut self, params: &ParamConfig) {
+    let changed = self.interface != params.user_interface
+      || self.header_visible != params.header_visibility
+      || self.statusbar_visible != params.statusbar_visibility
+      || self.titles_visible != params.titles_visibility;
+    self.interface = params.user_interface;
+    self.header_visible = params.header_visibility;
+    self.statusbar_visible = params.statusbar_visibility;
+    self.titles_visible = params.titles_visibility;
+
+    if changed {
+      self.hit_map.clear(Area::Header);
+      self.resize_windows();
+      nc::clear();
+    }
+  }
+
+  /// Change the colors, and repaint the whole screen.
+  pub fn set_colors(&mut self, colors: &ColorConfig, params: &ParamConfig) {
+    init_colors(colors, params);
+
+    let panes = self.panes.iter().map(|p| p.win);
+    for win in [self.header, self.state, self.progressbar, self.statusbar].iter().cloned().chain(panes) {
+      nc::wclear(win);
+    }
+    nc::clear();
+    nc::refresh();
+  }
+
   pub fn display_header(&mut self, active_window: &ActiveWindow, pl_info: &PlaylistInfo, volume: Option<i8>) {
-    let mut max_x = 0;
-    let mut max_y = 0;
-    nc::getmaxyx(self.header, &mut max_y, &mut max_x);
+    let mut win = NcWindow(self.header);
+    let (_, max_x) = win.size();
     let mut free_size = max_x;
 
-    // Clear
-    nc::wmove(self.header, 0, 0);
-    nc::wclrtoeol(self.header);
-
-    // Start of the header
-    let title = format!("{:?}", active_window);
-    let pl_color = get_color(COLOR_PAIR_HEADER);
-    nc::wattron(self.header, pl_color);
-    nc::wattron(self.header, bold());
-    nc::mvwprintw(self.header, 0, 0, &title);
-    nc::wattroff(self.header, bold());
-    nc::wattroff(self.header, pl_color);
-    free_size -= title.len() as i32;
+    self.hit_map.clear(Area::Header);
+    if !self.header_visible {
+      return;
+    }
 
-    // Volume
-    if volume.is_some() {
-      let vol_color = get_color(COLOR_PAIR_VOLUME);
-      nc::wattron(self.header, vol_color);
-      let vol_value = volume.unwrap();
-      let s: String = if vol_value >= 0 {
-        format!(" Volume: {}%%", volume.unwrap())
-      } else {
-        String::from(" Volume: n/a")
+    // Clear
+    win.clear_to_eol(0, 0);
+
+    // Screen tabs, the active one being highlighted
+    let (header_y, header_x) = self.window_origin(self.header);
+    let mut tabs_width = 0;
+    for screen in screens() {
+      let window = screen.window();
+      let tab = format!(" {} ", window.title());
+      let is_active = window == *active_window;
+      // Screens shown next to the active one (with a locked screen) are bold
+      let is_visible = self.pane_of(window).is_some();
+      let style = Style {
+        bold: is_active || is_visible,
+        reverse: is_active,
+        ..Style::color(COLOR_PAIR_HEADER)
       };
+      win.print(0, tabs_width, &tab, style);
+      let width = text::width(&tab) as i32;
+      self.hit_map.add(
+        Area::Header,
+        header_y,
+        header_x + tabs_width,
+        1,
+        width,
+        Target::Screen(window),
+      );
+      tabs_width += width;
+    }
+    free_size -= tabs_width;
 
-      nc::mvwprintw(self.header, 0, 1 + max_x - 1 - s.len() as i32, s.as_str());
-      nc::wattroff(self.header, vol_color);
-      free_size -= s.len() as i32;
+    // Volume
+    if let Some(vol) = volume {
+      free_size -= self.display_volume(&mut win, vol);
     }
 
     if active_window == &ActiveWindow::Playlist {
       // Playlist details
-      let s = format!("({})", pl_info);
+      let s = format!("({})", pl_info.describe(self.show_remaining_time, self.shorten_total_times));
       // TODO: only change text on playlist change
       self.header_scroller.set_text(&s);
       self.header_scroller.resize(free_size);
-      nc::wattron(self.header, pl_color);
-      nc::wattron(self.header, bold());
-      nc::mvwprintw(self.header, 0, 1 + title.len() as i32, self.header_scroller.display());
-      nc::wattroff(self.header, bold());
-      nc::wattroff(self.header, pl_color);
+      win.print(0, tabs_width + 1, self.header_scroller.display(), Style::color(COLOR_PAIR_HEADER).bold());
     }
 
-    nc::wrefresh(self.header);
+    win.refresh();
+  }
+
+  /// Display the volume at the end of the first line of the header, and
+  /// return its width.
+  fn display_volume(&mut self, win: &mut NcWindow, volume: i8) -> i32 {
+    let (vol_x, width) = draw_volume(win, volume);
+    let (header_y, header_x) = self.window_origin(self.header);
+    self
+      .hit_map
+      .add(Area::Header, header_y, header_x + vol_x, 1, width, Target::Volume);
+    return width;
+  }
+
+  /// Display the header of the alternative interface: the playing song,
+  /// described by two centered lines, and the volume.
+  pub fn display_alternative_header(&mut self, lines: &[Vec<Expression>], volume: Option<i8>) {
+    self.hit_map.clear(Area::Header);
+    if !self.header_visible {
+      return;
+    }
+
+    let mut win = NcWindow(self.header);
+    let (max_y, max_x) = win.size();
+    win.erase();
+
+    for (y, line) in lines.iter().enumerate().take(max_y as usize) {
+      let width: usize = line
+        .iter()
+        .map(|e| match e {
+          Expression::String(s) => text::width(s),
+          _ => 0,
+        })
+        .sum();
+      let x = cmp::max(0, (max_x - width as i32) / 2);
+      print_expressions(&mut win, y as i32, x, line);
+    }
+
+    if let Some(vol) = volume {
+      self.display_volume(&mut win, vol);
+    }
+
+    win.refresh();
   }
 
   pub fn display_help(&mut self) {
-    self.help.print();
+    let idx = self.screen_pane(ActiveWindow::Help);
+    let rect = self.pane_rect(idx);
+    self.help.print(&mut self.help_pad, rect.height);
+    let (_, pad_width) = self.help_pad.size();
+    nc::prefresh(
+      self.help_pad.0,
+      self.help.first_row(),
+      0,
+      rect.y,
+      rect.x,
+      rect.y + rect.height - 1,
+      rect.x + cmp::min(rect.width, pad_width) - 1,
+    );
+    self.set_pane_content(idx);
   }
 
   pub fn display_server_info(&mut self, client: &mut mpd::Client<TcpStream>) {
-    self.server_info.print(client);
+    let idx = self.screen_pane(ActiveWindow::ServerInfo);
+    let rect = self.pane_rect(idx);
+    self.server_info.print(client, rect);
+    self.set_pane_content(idx);
   }
 
-  pub fn display_main_playlist(
-    &self,
-    desc: &[Column],
-    data: &[&[String]],
-    current_song: Option<u32>,
-    selected_song: &Option<TimedValue<u32>>,
-  ) {
-    // Get the screen bounds.
-    let mut max_x = 0;
-    let mut max_y = 0;
-    nc::getmaxyx(self.main_win, &mut max_y, &mut max_x);
-
-    // Evaluate absolute width of each column
-    // First pass: look for fixed-width columns
-    let mut widths = vec![0; desc.len()];
-    let mut free_space = max_x;
-    let mut relative_width = 0;
-    for (i, c) in desc.iter().enumerate() {
-      if c.is_fixed {
-        widths[i] = c.width;
-        free_space -= c.width;
-      } else {
-        relative_width += c.width;
-      }
+  /// Screen of the focused pane.
+  pub fn active_window(&self) -> ActiveWindow {
+    return self.panes[self.focused].screen;
+  }
+
+  /// Screens shown in the panes, from left to right.
+  pub fn visible_screens(&self) -> Vec<ActiveWindow> {
+    return self.panes.iter().map(|p| p.screen).collect();
+  }
+
+  /// Whether the screen is locked, new screens being shown next to it.
+  pub fn is_screen_locked(&self) -> bool {
+    return self.locked_width_part.is_some();
+  }
+
+  /// Show a screen in the focused pane, or focus the pane already showing it.
+  /// When the screen is locked, other screens are shown in the slave pane.
+  pub fn show_screen(&mut self, screen: ActiveWindow) {
+    if let Some(idx) = self.pane_of(screen) {
+      self.focused = idx;
+      return;
     }
-    // Second pass: use relative width for remaining space
-    for (i, c) in desc.iter().enumerate() {
-      if !c.is_fixed {
-        widths[i] = c.width * free_space / relative_width;
+
+    if self.is_screen_locked() {
+      if self.panes.len() < 2 {
+        self.panes.push(Pane::new(screen));
+        self.layout_panes();
       }
+      self.focused = 1;
     }
+    let idx = self.focused;
+    self.set_pane_screen(idx, screen);
+  }
 
-    let mut color = get_color(COLOR_PAIR_DEFAULT);
+  /// Lock the active screen, on the left of the main window.
+  pub fn lock_screen(&mut self, width_part: i32) {
+    self.locked_width_part = Some(width_part);
+    self.layout_panes();
+  }
 
-    nc::wattron(self.main_win, bold());
-    nc::wattron(self.main_win, color);
+  /// Unlock the screen, the screen of the focused pane taking the whole main
+  /// window.
+  pub fn unlock_screen(&mut self) {
+    let screen = self.active_window();
+    self.locked_width_part = None;
+    for pane in self.panes.drain(1..) {
+      destroy_win(pane.win);
+    }
+    self.focused = 0;
+    self.set_pane_screen(0, screen);
+    self.hit_map.clear(Area::Pane(1));
+    self.layout_panes();
+    nc::clear();
+  }
 
-    // Header
-    let mut x = 0;
-    for (i, col) in desc.iter().enumerate() {
-      nc::wmove(self.main_win, 0, cmp::max(0, x - 1));
-      nc::wclrtoeol(self.main_win);
-      nc::mvwprintw(self.main_win, 0, x, &format!("{}", col.column_type));
-      x += widths[i] as i32;
+  /// Focus a pane (0 for the locked screen, 1 for the slave screen). Returns
+  /// false if there is no such pane.
+  pub fn focus_pane(&mut self, idx: usize) -> bool {
+    if idx >= self.panes.len() {
+      return false;
     }
+    self.focused = idx;
+    return true;
+  }
 
-    // Separator
-    nc::wmove(self.main_win, 1, 0);
-    nc::whline(self.main_win, nc::ACS_HLINE(), max_x);
+  /// Change the screen of a pane, and clear it.
+  fn set_pane_screen(&mut self, idx: usize, screen: ActiveWindow) {
+    if self.panes[idx].screen != screen {
+      self.panes[idx].screen = screen;
+      nc::werase(self.panes[idx].win);
+      nc::wrefresh(self.panes[idx].win);
+      self.hit_map.clear(Area::Pane(idx));
+    }
+  }
 
-    nc::wattroff(self.main_win, color);
-    nc::wattroff(self.main_win, bold());
+  /// Pane showing a screen, if any.
+  fn pane_of(&self, screen: ActiveWindow) -> Option<usize> {
+    return self.panes.iter().position(|p| p.screen == screen);
+  }
 
-    // Playlist data
-    let pl_start_row = 2;
-    let pl_max_row = max_y;
+  /// Pane where a screen is drawn: the one showing it, or the focused one.
+  fn screen_pane(&self, screen: ActiveWindow) -> usize {
+    return self.pane_of(screen).unwrap_or(self.focused);
+  }
+
+  /// Area of the screen covered by a pane.
+  fn pane_rect(&self, idx: usize) -> Rect {
+    let win = self.panes[idx].win;
+    let (y, x) = self.window_origin(win);
+    let mut height = 0;
+    let mut width = 0;
+    nc::getmaxyx(win, &mut height, &mut width);
+    return Rect {
+      y: y,
+      x: x,
+      height: height,
+      width: width,
+    };
+  }
+
+  /// Arrangement of the panes in the main window.
+  fn pane_layout(&self) -> Layout {
+    match self.locked_width_part {
+      Some(part) if self.panes.len() > 1 => Layout::locked(part),
+      _ => Layout::Pane(0),
+    }
+  }
+
+  /// Move and resize the panes to fill the main window.
+  fn layout_panes(&mut self) {
+    for (idx, rect) in self.pane_layout().rects(self.main_rect) {
+      let win = self.panes[idx].win;
+      nc::wresize(win, cmp::max(1, rect.height), cmp::max(1, rect.width));
+      nc::mvwin(win, rect.y, rect.x);
+      nc::werase(win);
+      self.hit_map.clear(Area::Pane(idx));
+    }
+  }
+
+  /// Position of a window on the screen.
+  fn window_origin(&self, win: nc::WINDOW) -> (i32, i32) {
+    let mut y = 0;
+    let mut x = 0;
+    nc::getbegyx(win, &mut y, &mut x);
+    return (y, x);
+  }
+
+  /// Make a whole pane a single scrollable region.
+  fn set_pane_content(&mut self, idx: usize) {
+    let rect = self.pane_rect(idx);
+    self.hit_map.clear(Area::Pane(idx));
+    self
+      .hit_map
+      .add(Area::Pane(idx), rect.y, rect.x, rect.height, rect.width, Target::Content);
+  }
+
+  pub fn display_main_playlist(
+    &mut self,
+    desc: &[Column],
+    data: &[&[String]],
+    ids: &[Option<SongId>],
+    current_song: Option<u32>,
+    selected_song: &Option<TimedValue<u32>>,
+    found: &[bool],
+    first_row: &mut usize,
+    centered_cursor: bool,
+  ) {
+    let pane = self.screen_pane(ActiveWindow::Playlist);
+    let mut win = NcWindow(self.panes[pane].win);
+    let (max_y, max_x) = win.size();
 
     let highlight_ts: Timespec = match *selected_song {
       Some(s) => s.timestamp + Duration::seconds(5),
@@ -363,411 +831,504 @@ impl View {
     };
     let highlighting: bool = get_time() < highlight_ts;
 
-    // Total number of songs
-    let n = data.len() as i32;
-    // Index of the selected song
-    let selected_idx = if selected_song.is_some() {
-      selected_song.unwrap().value as i32
-    } else {
-      -1 as i32
-    };
     // Maximum number of displayed song rows
-    let max_height = pl_max_row - pl_start_row;
-    // Number of displayed song rows
-    let height = cmp::min(max_height, n);
-    // Index of the song serving as the first displayed row
-    let start_idx: i32 = if selected_idx < max_height / 2 {
-      0
-    } else if selected_idx < (n - max_height / 2) {
-      selected_idx - max_height / 2
-    } else {
-      n - max_height
+    let max_height = (max_y - self.title_rows()).max(0) as usize;
+    let selected = selected_song.as_ref().map(|s| s.value as usize);
+    *first_row = first_visible_row(data.len(), max_height, selected, *first_row, centered_cursor);
+
+    let rows = PlaylistRows {
+      columns: desc,
+      data: data,
+      current_song: current_song,
+      highlighted: if highlighting { selected } else { None },
+      found: found,
+      first_row: *first_row,
+      titles_visible: self.titles_visible,
+      empty_tag_marker: &self.empty_tag_marker,
     };
-
-    // For each song
-    for (urow, ref item) in data.iter().skip(start_idx as usize).take(height as usize).enumerate() {
-      let row: i32 = urow as i32;
-      let idx: i32 = start_idx + row;
-      // For each column
-      x = 0;
-      for i in 0..desc.len() as usize {
-        nc::wmove(self.main_win, pl_start_row + row, x);
-        nc::wclrtoeol(self.main_win);
-
-        // Set column color
-        color = get_color(COLOR_PAIR_COLUMNS[i]);
-        nc::wattron(self.main_win, color);
-
-        // Highlight current song
-        let is_current = current_song.is_some() && current_song.unwrap() == idx as u32;
-        if is_current {
-          nc::wattron(self.main_win, bold());
-        }
-
-        // Highlight selected song
-        let is_selected = highlighting && selected_idx == idx;
-        if is_selected {
-          nc::wattron(self.main_win, reverse());
-        }
-
-        // Print song
-        nc::mvwprintw(self.main_win, pl_start_row + row, x, &item[i as usize]);
-
-        // If it's not the last column
-        if i < desc.len() - 1 {
-          // Add whitespace before the next column
-          nc::mvwaddch(self.main_win, pl_start_row + row, x + widths[i] - 1, ' ' as nc::chtype);
-        }
-
-        if is_selected {
-          // Fill with whitespace for ncmpcpp-style highlighting
-          let len = item[i as usize].chars().count() as i32;
-          nc::mvwhline(self.main_win, pl_start_row + row, x + len, ' ' as nc::chtype, widths[i] - len);
-
-          // Stop highlighting
-          nc::wattroff(self.main_win, reverse());
-        }
-
-        // Stop highlighting current song
-        if is_current {
-          nc::wattroff(self.main_win, bold());
-        }
-
-        // Disable column color
-        nc::wattroff(self.main_win, color);
-
-        // TODO: handle variable width
-        x += widths[i] as i32;
+    draw_playlist(&mut win, &rows);
+
+    // Clickable regions
+    self.set_pane_content(pane);
+    let (win_y, win_x) = self.window_origin(win.0);
+    if self.titles_visible {
+      let mut x = 0;
+      for (col, width) in desc.iter().zip(column_widths(desc, max_x)) {
+        let target = Target::ColumnHeader(col.column_type.clone());
+        self.hit_map.add(Area::Pane(pane), win_y, win_x + x, 1, width, target);
+        x += width;
       }
     }
-
-    // Clear the rest of the lines
-    for y in height..max_height {
-      nc::wmove(self.main_win, pl_start_row + y, 0);
-      nc::wclrtoeol(self.main_win);
+    let height = cmp::min(max_height, data.len() - *first_row);
+    for row in 0..height {
+      let idx = *first_row + row;
+      self.hit_map.add(
+        Area::Pane(pane),
+        win_y + self.title_rows() + row as i32,
+        win_x,
+        1,
+        max_x,
+        Target::Song {
+          row: idx as u32,
+          id: ids.get(idx).cloned().unwrap_or(None),
+        },
+      );
     }
 
-    nc::wrefresh(self.main_win);
+    win.refresh();
   }
 
   pub fn display_progressbar(&mut self, pct: f32) {
-    let mut max_x = 0;
-    let mut max_y = 0;
-    nc::getmaxyx(self.progressbar, &mut max_y, &mut max_x);
+    let mut win = NcWindow(self.progressbar);
+    let (_, max_x) = win.size();
 
-    let tip_x: i32 = (pct / 100. * (max_x as f32)) as i32;
+    let (bar_y, bar_x) = self.window_origin(self.progressbar);
+    self.hit_map.clear(Area::Progressbar);
+    self.hit_map.add(Area::Progressbar, bar_y, bar_x, 1, max_x, Target::Progress);
 
-    // Start of the bar
-    let len_start = tip_x;
-    let mut color = get_color(COLOR_PAIR_PROGRESSBAR_ELAPSED);
-    nc::wattron(self.progressbar, color);
-    // TODO: find why using mvwhline fails with ─
-    if self.progressbar_look[0] == "─" {
-      nc::mvwhline(self.progressbar, 0, 0, nc::ACS_HLINE(), len_start);
-    } else {
-      nc::wmove(self.progressbar, 0, 0);
-      for _i in 0..len_start {
-        nc::waddstr(self.progressbar, &self.progressbar_look[0]);
-      }
+    draw_progressbar(&mut win, pct, &self.progressbar_look);
+    win.refresh();
+  }
+
+  pub fn display_stateline(&mut self, flags: &[char]) {
+    if !self.header_visible {
+      return;
     }
 
-    if pct > 0. {
-      // Tip of the bar
-      nc::mvwprintw(self.progressbar, 0, tip_x, &self.progressbar_look[1]);
-      nc::wattroff(self.progressbar, color);
+    let color = match self.interface {
+      UserInterface::Classic => COLOR_PAIR_STATE_LINE,
+      UserInterface::Alternative => COLOR_PAIR_ALTERNATIVE_UI_SEPARATOR,
+    };
+    let mut win = NcWindow(self.state);
+    draw_stateline(&mut win, &flags.iter().collect::<String>(), color);
+    win.refresh();
+  }
+
+  pub fn display_statusbar(&mut self, mode: &str, msg: &str, track: &str) {
+    self.set_command_line_visible(false);
+    if !self.statusbar_visible {
+      self.set_cursor_visible(false);
+      return;
     }
 
-    // End of the bar
-    let len_end = max_x - tip_x;
-    color = get_color(COLOR_PAIR_PROGRESSBAR);
-    nc::wattron(self.progressbar, color);
-    if self.progressbar_look[2] == "─" {
-      nc::mvwhline(self.progressbar, 0, if tip_x > 0 { tip_x + 1 } else { 0 }, nc::ACS_HLINE(), len_end);
-    } else if self.progressbar_look[2] != "" {
-      nc::wmove(self.progressbar, 0, if tip_x > 0 { tip_x + 1 } else { 0 });
-      for _i in 0..len_end {
-        nc::waddstr(self.progressbar, &self.progressbar_look[2]);
-      }
+    self.set_cursor_visible(false);
+    let mut win = NcWindow(self.statusbar);
+    draw_statusbar(&mut win, mode, msg, track, &mut self.status_scroller);
+    win.refresh();
+  }
+
+  pub fn display_statusbar_msg(&mut self, msg: &str) {
+    self.set_command_line_visible(false);
+    self.set_cursor_visible(false);
+    let mut win = NcWindow(self.statusbar);
+    win.clear_to_eol(0, 0);
+    win.print(0, 0, msg, Style::default());
+    win.refresh();
+  }
+
+  /// Set the title of the terminal window, saving the original one first.
+  pub fn set_window_title(&mut self, title: &str) {
+    // Headless views and the Linux console have no window title
+    if self.terminal.is_some() || env::var("TERM").map(|t| t == "linux").unwrap_or(true) {
+      return;
+    }
+    if !self.window_title_saved {
+      write_to_terminal(SAVE_WINDOW_TITLE);
+      self.window_title_saved = true;
     }
-    nc::wattroff(self.progressbar, color);
+    write_to_terminal(&window_title_sequence(title));
+  }
 
-    nc::wrefresh(self.progressbar);
+  /// Restore the title of the terminal window saved by `set_window_title`.
+  pub fn restore_window_title(&mut self) {
+    if self.window_title_saved {
+      write_to_terminal(RESTORE_WINDOW_TITLE);
+      self.window_title_saved = false;
+    }
   }
 
-  pub fn display_stateline(&mut self, flags: &[char]) {
-    // Clear line.
-    nc::wmove(self.state, 0, 0);
-    nc::wclrtoeol(self.state);
+  /// Display the command being typed, and the cursor.
+  pub fn display_command_line(&mut self, prompt: &str, text: &str, cursor: usize) {
+    self.set_command_line_visible(true);
+    let mut win = NcWindow(self.statusbar);
+    win.clear_to_eol(0, 0);
+    win.print(0, 0, &format!("{}{}", prompt, text), Style::color(COLOR_PAIR_DEFAULT));
+
+    let before_cursor: String = text.chars().take(cursor).collect();
+    nc::wmove(self.statusbar, 0, (text::width(prompt) + text::width(&before_cursor)) as i32);
+    self.set_cursor_visible(true);
+    win.refresh();
+  }
 
-    let mut max_x = 0;
-    let mut max_y = 0;
-    nc::getmaxyx(self.state, &mut max_y, &mut max_x);
-
-    // Print the bar
-    let mut color = get_color(COLOR_PAIR_STATE_LINE);
-    nc::wattron(self.state, color);
-    nc::whline(self.state, nc::ACS_HLINE(), max_x);
-    nc::wattroff(self.state, color);
-
-    if !flags.is_empty() {
-      let s: String = flags.iter().fold("".to_string(), |mut vec, val| {
-        vec.push(*val);
-        vec
+  /// Give the statusbar a row while a command is being typed, if it is hidden.
+  fn set_command_line_visible(&mut self, visible: bool) {
+    if visible != self.command_line_visible {
+      self.command_line_visible = visible;
+      if !self.statusbar_visible {
+        self.resize_windows();
+      }
+    }
+  }
+
+  fn set_cursor_visible(&mut self, visible: bool) {
+    if visible != self.cursor_visible {
+      nc::curs_set(if visible {
+        nc::CURSOR_VISIBILITY::CURSOR_VISIBLE
+      } else {
+        nc::CURSOR_VISIBILITY::CURSOR_INVISIBLE
       });
+      self.cursor_visible = visible;
+    }
+  }
+
+  /// Display the keys of the key sequence being typed, at the end of the
+  /// statusbar.
+  pub fn display_pending_keys(&mut self, keys: &str) {
+    if keys.is_empty() {
+      return;
+    }
 
-      // Print the brackets
-      nc::wattron(self.state, color);
-      nc::mvwprintw(self.state, 0, max_x - 3 - s.len() as i32, "[");
-      nc::mvwprintw(self.state, 0, max_x - 2, "]");
-      nc::wattroff(self.state, color);
+    let mut win = NcWindow(self.statusbar);
+    let (_, max_x) = win.size();
+    let style = Style::color(COLOR_PAIR_STATUSBAR).bold();
+    let offset = max_x - text::width(keys) as i32 - 1;
+    win.clear_to_eol(0, offset - 1);
+    win.print(0, offset - 1, " ", style);
+    win.print(0, offset, keys, style);
+    win.refresh();
+  }
 
-      // Print the flags
-      color = get_color(COLOR_PAIR_STATE_FLAGS);
-      nc::wattron(self.state, color);
-      nc::wattron(self.state, bold());
-      nc::mvwprintw(self.state, 0, max_x - 2 - s.len() as i32, &s);
-      nc::wattroff(self.state, bold());
-      nc::wattroff(self.state, color);
+  pub fn process_mouse(&mut self) -> MouseEvent {
+    let mut event = nc::MEVENT {
+      id: 0,
+      x: 0,
+      y: 0,
+      z: 0,
+      bstate: 0,
+    };
+    if nc::getmouse(&mut event) != nc::OK {
+      return MouseEvent::Nothing;
     }
 
-    nc::wrefresh(self.state);
+    let hit = match self.hit_map.hit(event.y, event.x) {
+      Some(h) => h,
+      None => return MouseEvent::WakeUp,
+    };
+    // Clicked or scrolled panes get the focus
+    if let Area::Pane(idx) = hit.area {
+      self.focus_pane(idx);
+    }
+    let pressed = |button: i32| (event.bstate & (button as nc::mmask_t)) != 0;
+    let left_click = pressed(nc::BUTTON1_PRESSED);
+    let right_click = pressed(nc::BUTTON3_PRESSED);
+    let wheel_up = pressed(nc::BUTTON4_PRESSED);
+    let wheel_down = pressed(nc::BUTTON5_PRESSED);
+
+    return match hit.target {
+      Target::Volume if wheel_up => MouseEvent::VolumeUp,
+      Target::Volume if wheel_down => MouseEvent::VolumeDown,
+      Target::Volume if left_click => MouseEvent::SetVolume((hit.fraction * 100.) as i8),
+      Target::Song { .. } | Target::Content if wheel_up => MouseEvent::ScrollUp,
+      Target::Song { .. } | Target::Content if wheel_down => MouseEvent::ScrollDown,
+      Target::Song { row, id } if left_click => MouseEvent::SetSelectedSong(row, id),
+      Target::Song { row, id } if right_click => MouseEvent::PlaySong(row, id),
+      Target::ColumnHeader(property) if left_click => MouseEvent::SortBy(property),
+      Target::Screen(window) if left_click => MouseEvent::ShowScreen(window),
+      Target::Progress if left_click => MouseEvent::SetProgress(hit.fraction),
+      _ => MouseEvent::WakeUp,
+    };
   }
 
-  pub fn display_statusbar(&mut self, mode: &str, msg: &str, track: &str) {
+  pub fn resize_windows(&mut self) {
     let mut max_x = 0;
     let mut max_y = 0;
-    nc::getmaxyx(self.statusbar, &mut max_y, &mut max_x);
+    nc::getmaxyx(nc::stdscr(), &mut max_y, &mut max_x);
 
-    let mut free_size = max_x;
+    // The main window takes the space left by the windows above and below it.
+    // Hidden windows (with no rows) are kept, but not drawn: without
+    // statusbar, messages are shown over the progressbar.
+    let (above, below) = self.layout();
+    let static_rows: i32 = above.iter().chain(below.iter()).map(|&(_, rows)| rows).sum();
+    let main_height = cmp::max(1, max_y - static_rows);
 
-    // Clear line.
-    nc::wmove(self.statusbar, 0, 0);
-    nc::wclrtoeol(self.statusbar);
-
-    if !self.statusbar_input.is_empty() && !self.statusbar_input.last().unwrap().is_empty() {
-      let color = get_color(COLOR_PAIR_DEFAULT);
-      nc::wattron(self.statusbar, color);
-      nc::mvwprintw(
-        self.statusbar,
-        0,
-        0,
-        format!(":{}", self.statusbar_input.last().unwrap().as_str()).as_str(),
-      );
-      nc::wattroff(self.statusbar, color);
-    } else {
-      // Print mode.
-      if !mode.is_empty() {
-        let color = get_color(COLOR_PAIR_STATUSBAR);
-        nc::wattron(self.statusbar, color);
-        nc::wattron(self.statusbar, bold());
-        nc::mvwprintw(self.statusbar, 0, 0, &format!("{}:", mode));
-        nc::wattroff(self.statusbar, bold());
-        nc::wattroff(self.statusbar, color);
-
-        free_size -= mode.len() as i32 + 2;
-      }
+    let mut row = 0;
+    for &(win, rows) in above.iter() {
+      nc::wresize(win, cmp::max(1, rows), max_x);
+      nc::mvwin(win, cmp::min(row, max_y - 1), 0);
+      row += rows;
+    }
+    self.main_rect = Rect {
+      y: row,
+      x: 0,
+      height: main_height,
+      width: max_x,
+    };
+    self.layout_panes();
+    row += main_height;
+    for &(win, rows) in below.iter() {
+      nc::wresize(win, cmp::max(1, rows), max_x);
+      nc::mvwin(win, cmp::min(row, max_y - 1), 0);
+      row += rows;
+    }
 
-      // Print track (time, bitrate, etc.)
-      if !track.is_empty() {
-        let color = get_color(COLOR_PAIR_TRACK);
-        nc::wattron(self.statusbar, color);
-        nc::wattron(self.statusbar, bold());
-        let offset = max_x - track.len() as i32;
-        nc::mvwprintw(self.statusbar, 0, offset - 1, " ");
-        nc::mvwprintw(self.statusbar, 0, offset, track);
-        nc::wattroff(self.statusbar, bold());
-        nc::wattroff(self.statusbar, color);
-
-        free_size -= track.len() as i32 + 1;
-      }
+    // TODO: resize scrollers?
+    // self.header_scroller.resize();
+
+    nc::refresh();
+  }
+
+  /// Windows above and below the main window, with their number of rows.
+  fn layout(&self) -> ([(nc::WINDOW, i32); 2], [(nc::WINDOW, i32); 2]) {
+    let header_rows = match (self.header_visible, self.interface) {
+      (false, _) => 0,
+      (true, UserInterface::Classic) => 1,
+      (true, UserInterface::Alternative) => 2,
+    };
+    let state_rows = if self.header_visible { 1 } else { 0 };
+    let statusbar_rows = if self.statusbar_visible || self.command_line_visible { 1 } else { 0 };
+    return (
+      [(self.header, header_rows), (self.state, state_rows)],
+      [(self.progressbar, 1), (self.statusbar, statusbar_rows)],
+    );
+  }
+
+  /// Number of lines of the focused pane.
+  pub fn get_main_window_height(&self) -> i32 {
+    return self.pane_rect(self.focused).height;
+  }
+
+  /// Number of songs shown in the playlist, below its header rows.
+  pub fn get_main_playlist_height(&self) -> i32 {
+    return (self.get_main_window_height() - self.title_rows()).max(1);
+  }
 
-      // Print message.
-      // TODO: only change text on song change
-      self.status_scroller.set_text(msg);
-      self.status_scroller.resize(free_size);
-      let color = get_color(COLOR_PAIR_DEFAULT);
-      nc::wattron(self.statusbar, color);
-      let offset = mode.len() + 2;
-      nc::mvwprintw(self.statusbar, 0, offset as i32, self.status_scroller.display());
-      nc::wattroff(self.statusbar, color);
+  /// Number of rows used by the column titles of the playlist.
+  fn title_rows(&self) -> i32 {
+    if self.titles_visible {
+      2
+    } else {
+      0
     }
+  }
+}
 
-    nc::wrefresh(self.statusbar);
+/// Index of the first displayed row of a list of `n` rows shown on `height`
+/// lines, keeping the selected row visible. Unless `centered` is set, the list
+/// only scrolls when the selected row would leave the screen.
+pub fn first_visible_row(n: usize, height: usize, selected: Option<usize>, previous: usize, centered: bool) -> usize {
+  if n <= height {
+    return 0;
   }
+  let start = match selected {
+    Some(s) if centered => s.saturating_sub(height / 2),
+    Some(s) if s < previous => s,
+    Some(s) if s >= previous + height => s + 1 - height,
+    _ => previous,
+  };
+  return start.min(n - height);
+}
 
-  pub fn display_statusbar_msg(&mut self, msg: &str) {
-    nc::wmove(self.statusbar, 0, 0);
-    nc::wclrtoeol(self.statusbar);
-    nc::mvwprintw(self.statusbar, 0, 0, msg);
-    nc::wrefresh(self.statusbar);
+/// Width of each column of the playlist, on a window of `width` columns.
+/// Fixed-width columns keep their width, and the others share the remaining
+/// space.
+pub fn column_widths(columns: &[Column], width: i32) -> Vec<i32> {
+  let free_space = width - columns.iter().filter(|c| c.is_fixed).map(|c| c.width).sum::<i32>();
+  let relative_width: i32 = columns.iter().filter(|c| !c.is_fixed).map(|c| c.width).sum();
+  return columns
+    .iter()
+    .map(|c| if c.is_fixed { c.width } else { c.width * free_space / relative_width })
+    .collect();
+}
+
+/// Rows of the playlist to draw, with the state of its songs.
+pub struct PlaylistRows<'a> {
+  pub columns: &'a [Column],
+  pub data: &'a [&'a [String]],
+  pub current_song: Option<u32>,
+  /// Selected song, while it is highlighted.
+  pub highlighted: Option<usize>,
+  /// Whether each song matches the pattern being searched.
+  pub found: &'a [bool],
+  /// Index of the song shown on the first row.
+  pub first_row: usize,
+  pub titles_visible: bool,
+  pub empty_tag_marker: &'a str,
+}
+
+/// Draw the playlist on a surface: the column titles (if visible), then the
+/// songs from `first_row`.
+pub fn draw_playlist(s: &mut dyn Surface, p: &PlaylistRows) {
+  let (max_y, max_x) = s.size();
+  let widths = column_widths(p.columns, max_x);
+
+  // Header
+  let start_row = if p.titles_visible { 2 } else { 0 };
+  if p.titles_visible {
+    let style = Style::color(COLOR_PAIR_DEFAULT).bold();
+    let mut x = 0;
+    for (col, width) in p.columns.iter().zip(&widths) {
+      s.clear_to_eol(0, cmp::max(0, x - 1));
+      s.print(0, x, &format!("{}", col.column_type), style);
+      x += width;
+    }
+
+    // Separator
+    s.hline(1, 0, max_x, style);
   }
 
-  pub fn process_mouse(&mut self) -> MouseEvent {
-    let mut event: nc::MEVENT = unsafe { mem::uninitialized() };
-    if nc::getmouse(&mut event) == nc::OK {
-      let mut max_x = 0;
-      let mut max_y = 0;
-      let mut win_x = 0;
-      let mut win_y = 0;
-
-      // Check playlist event
-      nc::getbegyx(self.main_win, &mut win_y, &mut win_x);
-      nc::getmaxyx(self.main_win, &mut max_y, &mut max_x);
-      if event.y >= win_y + 2 && event.y < win_y + max_y {
-        // Click
-        if (event.bstate & (nc::BUTTON1_PRESSED as nc::mmask_t)) != 0 {
-          return MouseEvent::SetSelectedSong((event.y - win_y) as u32 - 2);
-        }
-        // Mouse wheel up
-        else if (event.bstate & (nc::BUTTON4_PRESSED as nc::mmask_t)) != 0 {
-          return MouseEvent::ScrollUp;
-        }
-        // Mouse wheel down
-        else if (event.bstate & (nc::BUTTON5_PRESSED as nc::mmask_t)) != 0 {
-          return MouseEvent::ScrollDown;
-        }
+  // Playlist data
+  let max_height = (max_y - start_row).max(0) as usize;
+  let mut y = start_row;
+  for (idx, item) in p.data.iter().enumerate().skip(p.first_row).take(max_height) {
+    // Highlight the selected song, make the current one bold and underline
+    // found ones
+    let is_selected = p.highlighted == Some(idx);
+    let is_current = p.current_song == Some(idx as u32);
+    let is_found = p.found.get(idx).cloned().unwrap_or(false);
+
+    let mut x = 0;
+    for (i, &width) in widths.iter().enumerate() {
+      s.clear_to_eol(y, x);
+
+      let is_empty = item[i].is_empty();
+      let color = if is_selected {
+        COLOR_PAIR_MAIN_WINDOW_HIGHLIGHT
+      } else if is_empty {
+        COLOR_PAIR_EMPTY_TAG
+      } else {
+        COLOR_PAIR_COLUMNS[i]
+      };
+      let style = Style {
+        color: color,
+        bold: is_current,
+        reverse: is_selected,
+        underline: is_found,
+        ..Style::default()
+      };
+
+      // Print song, truncated before the space separating it from the next
+      // column
+      let is_last = i == widths.len() - 1;
+      let max_width = if is_last { width } else { width - 1 };
+      let shown = text::fit(if is_empty { p.empty_tag_marker } else { &item[i] }, max_width.max(0) as usize);
+      s.print(y, x, &shown, style);
+
+      // If it's not the last column
+      if !is_last {
+        // Add whitespace before the next column
+        s.print(y, x + width - 1, " ", style);
       }
 
-      // Check progressbar event
-      nc::getbegyx(self.progressbar, &mut win_y, &mut win_x);
-      if event.y == win_y {
-        nc::getmaxyx(self.progressbar, &mut max_y, &mut max_x);
-        return MouseEvent::SetProgress(event.x as f32 / max_x as f32);
+      if is_selected {
+        // Fill with whitespace for ncmpcpp-style highlighting
+        let len = text::width(&shown) as i32;
+        s.fill(y, x + len, " ", width - len, style);
       }
 
-      return MouseEvent::WakeUp;
+      // TODO: handle variable width
+      x += width;
     }
-    return MouseEvent::Nothing;
+    y += 1;
   }
 
-  pub fn resize_windows(&mut self) {
-    let mut max_x = 0;
-    let mut max_y = 0;
-    nc::getmaxyx(nc::stdscr(), &mut max_y, &mut max_x);
+  // Clear the rest of the lines
+  for y in y..max_y {
+    s.clear_to_eol(y, 0);
+  }
+}
 
-    let mut row = 0;
-    nc::wresize(self.header, 1, max_x);
-    nc::mvwin(self.header, row, 0);
-    row += 1;
+/// Draw the statusbar on a surface: the mode on the left, the track on the
+/// right, and the message scrolling between them.
+pub fn draw_statusbar(s: &mut dyn Surface, mode: &str, msg: &str, track: &str, scroller: &mut Scroller) {
+  let (_, max_x) = s.size();
+  let mut free_size = max_x;
 
-    nc::wresize(self.state, 1, max_x);
-    nc::mvwin(self.state, row, 0);
-    row += 1;
+  // Clear line.
+  s.clear_to_eol(0, 0);
 
-    nc::wresize(self.main_win, max_y - self.static_rows, max_x);
-    nc::mvwin(self.main_win, row, 0);
-    row += max_y - self.static_rows;
+  // Print mode.
+  if !mode.is_empty() {
+    s.print(0, 0, &format!("{}:", mode), Style::color(COLOR_PAIR_STATUSBAR).bold());
+    free_size -= text::width(mode) as i32 + 2;
+  }
 
-    nc::wresize(self.progressbar, 1, max_x);
-    nc::mvwin(self.progressbar, row, 0);
-    row += 1;
+  // Print track (time, bitrate, etc.)
+  if !track.is_empty() {
+    let style = Style::color(COLOR_PAIR_TRACK).bold();
+    let offset = max_x - text::width(track) as i32;
+    s.print(0, offset - 1, " ", style);
+    s.print(0, offset, track, style);
+    free_size -= text::width(track) as i32 + 1;
+  }
 
-    nc::wresize(self.statusbar, 1, max_x);
-    nc::mvwin(self.statusbar, row, 0);
+  // Print message.
+  // TODO: only change text on song change
+  scroller.set_text(msg);
+  scroller.resize(free_size);
+  let offset = text::width(mode) + 2;
+  s.print(0, offset as i32, scroller.display(), Style::color(COLOR_PAIR_DEFAULT));
+}
 
-    // TODO: resize scrollers?
-    // self.header_scroller.resize();
+/// Draw the volume at the end of the first row, and return its column and
+/// width.
+pub fn draw_volume(s: &mut dyn Surface, volume: i8) -> (i32, i32) {
+  let (_, max_x) = s.size();
+  let v: String = if volume >= 0 {
+    format!(" Volume: {}%", volume)
+  } else {
+    String::from(" Volume: n/a")
+  };
+
+  let width = text::width(&v) as i32;
+  let vol_x = max_x - width;
+  s.print(0, vol_x, &v, Style::color(COLOR_PAIR_VOLUME));
+  return (vol_x, width);
+}
 
-    nc::refresh();
+/// Draw a part of the progressbar: `n` times its symbol, or a line.
+fn draw_progressbar_part(s: &mut dyn Surface, x: i32, n: i32, symbol: &str, style: Style) {
+  if symbol == "─" {
+    s.hline(0, x, n, style);
+  } else if !symbol.is_empty() {
+    s.fill(0, x, symbol, n, style);
   }
+}
 
-  pub fn read_input_command(&mut self) -> String {
-    // Clear status bar
-    nc::wmove(self.statusbar, 0, 0);
-    nc::wclrtoeol(self.statusbar);
-
-    // Add new empty input
-    self.statusbar_input.push(String::new());
-
-    // Number of inputs in vector
-    let n_inputs = self.statusbar_input.len();
-    let mut cur_input = n_inputs - 1;
-
-    // While Enter was not pressed
-    // FIXME: this blocks the rest of the UI
-    loop {
-      let color = get_color(COLOR_PAIR_DEFAULT);
-      nc::wattron(self.statusbar, color);
-      nc::mvwprintw(
-        self.statusbar,
-        0,
-        0,
-        format!(":{}", self.statusbar_input.last().unwrap().as_str()).as_str(),
-      );
-      nc::wclrtoeol(self.statusbar);
-      nc::wattroff(self.statusbar, color);
-      nc::wrefresh(self.statusbar);
+/// Draw the progressbar of the playing song, at `pct` %, with the (elapsed,
+/// tip, remaining) parts of its look.
+pub fn draw_progressbar(s: &mut dyn Surface, pct: f32, look: &[String]) {
+  let (_, max_x) = s.size();
+  let tip_x: i32 = (pct / 100. * (max_x as f32)) as i32;
+
+  // Start of the bar, and its tip
+  let style = Style::color(COLOR_PAIR_PROGRESSBAR_ELAPSED);
+  draw_progressbar_part(s, 0, tip_x, &look[0], style);
+  if pct > 0. {
+    s.print(0, tip_x, &look[1], style);
+  }
 
-      let ch = nc::getch() as i32;
+  // End of the bar
+  let end_x = if tip_x > 0 { tip_x + 1 } else { 0 };
+  draw_progressbar_part(s, end_x, max_x - tip_x, &look[2], Style::color(COLOR_PAIR_PROGRESSBAR));
+}
 
-      // If Enter is preseed
-      if ch == '\n' as i32 {
-        return self.statusbar_input.last().unwrap().clone();
-      }
-      // If Backspace is pressed
-      else if ch == KEY_BACKSPACE {
-        let mut new_len = 0;
-        if let Some(last_elem) = self.statusbar_input.get_mut(n_inputs - 1) {
-          // Remove last character from string
-          last_elem.pop();
-          new_len = last_elem.len();
-        }
-
-        // Clear statusbar
-        nc::wmove(self.statusbar, 0, new_len as i32);
-        nc::wclrtoeol(self.statusbar);
-        nc::wrefresh(self.statusbar);
-      }
-      // If Up is pressed
-      else if ch == nc::KEY_UP {
-        if cur_input > 0 {
-          cur_input -= 1;
-        }
-
-        // Copy content of previous input to last input
-        if cur_input < n_inputs - 1 {
-          let cmd = self.statusbar_input[cur_input].clone();
-          if let Some(last_elem) = self.statusbar_input.get_mut(n_inputs - 1) {
-            *last_elem = cmd;
-          }
-        }
-        // If back to last input: clear string
-        else if cur_input == n_inputs - 1 {
-          if let Some(last_elem) = self.statusbar_input.get_mut(n_inputs - 1) {
-            last_elem.clear();
-          }
-        }
-      }
-      // If Down is pressed
-      else if ch == nc::KEY_DOWN {
-        if cur_input < n_inputs - 1 {
-          cur_input += 1;
-        }
-
-        // Copy content of next input to last input
-        if cur_input < n_inputs - 1 {
-          let cmd = self.statusbar_input[cur_input].clone();
-          if let Some(last_elem) = self.statusbar_input.get_mut(n_inputs - 1) {
-            *last_elem = cmd;
-          }
-        }
-        // If back to last input: clear string
-        else if cur_input == n_inputs - 1 {
-          if let Some(last_elem) = self.statusbar_input.get_mut(n_inputs - 1) {
-            last_elem.clear();
-          }
-        }
-      } else if let Some(c) = char::from_u32(ch as u32) {
-        self.statusbar_input[n_inputs - 1].push(c);
-      }
-    }
-  }
+/// Draw the line below the header, with the flags of the playback modes at its
+/// end.
+pub fn draw_stateline(s: &mut dyn Surface, flags: &str, color: Color) {
+  let (_, max_x) = s.size();
+  let style = Style::color(color);
+  s.clear_to_eol(0, 0);
+  s.hline(0, 0, max_x, style);
 
-  pub fn get_main_playlist_height(&self) -> i32 {
-    let mut max_x = 0;
-    let mut max_y = 0;
-    nc::getmaxyx(nc::stdscr(), &mut max_y, &mut max_x);
-    return (max_y - self.static_rows - 2) as i32;
+  if !flags.is_empty() {
+    let width = text::width(flags) as i32;
+
+    // Print the brackets
+    s.print(0, max_x - 3 - width, "[", style);
+    s.print(0, max_x - 2, "]", style);
+
+    // Print the flags
+    s.print(0, max_x - 2 - width, flags, Style::color(COLOR_PAIR_STATE_FLAGS).bold());
   }
 }
 
@@ -775,9 +1336,226 @@ impl Drop for View {
   fn drop(&mut self) {
     destroy_win(self.header);
     destroy_win(self.state);
-    destroy_win(self.main_win);
+    for pane in &self.panes {
+      destroy_win(pane.win);
+    }
     destroy_win(self.progressbar);
     destroy_win(self.statusbar);
+    nc::delwin(self.help_pad.0);
     deinit_ncurses();
+    self.restore_window_title();
   }
 }
+
+#[test]
+fn test_window_title_sequence() {
+  assert_eq!(window_title_sequence("Miles Davis - So What"), "\x1b]0;Miles Davis - So What\x07");
+  assert_eq!(window_title_sequence("東京事変 - 群青日和"), "\x1b]0;東京事変 - 群青日和\x07");
+  // Tags cannot inject escape sequences
+  assert_eq!(window_title_sequence("So What\x07\x1b]0;pwned\n"), "\x1b]0;So What]0;pwned\x07");
+}
+
+#[test]
+fn test_playlist_info() {
+  let mut info = PlaylistInfo::new();
+  assert_eq!(info.describe(true, true), "0 item");
+
+  info.size = 4;
+  info.duration = Duration::seconds(3 * 3600 + 60 + 11);
+  info.remaining = Duration::seconds(12 * 60 + 5);
+  assert_eq!(info.to_string(), "4 items, length: 3 hours, 1 minute, 11 seconds");
+  assert_eq!(
+    info.describe(true, false),
+    "4 items, length: 3 hours, 1 minute, 11 seconds :: remaining: 0 hour, 12 minutes, 5 seconds"
+  );
+  assert_eq!(info.describe(true, true), "4 items, length: 3h, 1m, 11s :: remaining: 12m, 5s");
+
+  // Streams have no length
+  info.duration = Duration::zero();
+  info.remaining = Duration::zero();
+  assert_eq!(info.describe(true, true), "4 items");
+}
+
+#[test]
+fn test_first_visible_row() {
+  // Short lists are never scrolled
+  assert_eq!(first_visible_row(5, 10, Some(4), 3, false), 0);
+
+  // The list only scrolls when the selection leaves the screen
+  assert_eq!(first_visible_row(100, 10, Some(5), 0, false), 0);
+  assert_eq!(first_visible_row(100, 10, Some(10), 0, false), 1);
+  assert_eq!(first_visible_row(100, 10, Some(12), 8, false), 8);
+  assert_eq!(first_visible_row(100, 10, Some(3), 8, false), 3);
+  assert_eq!(first_visible_row(100, 10, Some(99), 0, false), 90);
+  assert_eq!(first_visible_row(100, 10, None, 8, false), 8);
+
+  // Centered cursor
+  assert_eq!(first_visible_row(100, 10, Some(50), 0, true), 45);
+  assert_eq!(first_visible_row(100, 10, Some(2), 40, true), 0);
+  assert_eq!(first_visible_row(100, 10, Some(98), 0, true), 90);
+}
+
+#[cfg(test)]
+fn playlist_snapshot(height: i32, width: i32, first_row: usize, titles_visible: bool) -> CellBuffer {
+  let columns = ParamConfig::new().song_columns_list_format;
+  let songs = vec![
+    vec!["Miles Davis", "1", "So What", "Kind of Blue", "9:22"],
+    vec!["Miles Davis", "2", "Freddie Freeloader", "Kind of Blue", "9:46"],
+    vec!["John Coltrane", "", "Naima", "", "4:21"],
+    vec!["Bill Evans", "4", "Peace Piece", "Everybody Digs Bill Evans", "6:42"],
+  ];
+  let songs: Vec<Vec<String>> = songs.iter().map(|s| s.iter().map(|t| t.to_string()).collect()).collect();
+  let data: Vec<&[String]> = songs.iter().map(|s| s.as_slice()).collect();
+  let mut buf = CellBuffer::new(height, width);
+  let rows = PlaylistRows {
+    columns: &columns,
+    data: &data,
+    current_song: Some(1),
+    highlighted: Some(2),
+    found: &[false, false, false, true],
+    first_row: first_row,
+    titles_visible: titles_visible,
+    empty_tag_marker: "<empty>",
+  };
+  draw_playlist(&mut buf, &rows);
+  return buf;
+}
+
+#[test]
+fn test_draw_playlist() {
+  let columns = ParamConfig::new().song_columns_list_format;
+  assert_eq!(column_widths(&columns, 80), vec![14, 6, 37, 14, 7]);
+  assert_eq!(column_widths(&columns, 33), vec![4, 6, 11, 4, 7]);
+
+  let buf = playlist_snapshot(8, 80, 0, true);
+  let expected = [
+    "Artist        Full  Title/Filename                       Album         Time",
+    "────────────────────────────────────────────────────────────────────────────────",
+    "Miles Davis   1     So What                              Kind of Blue  9:22",
+    "Miles Davis   2     Freddie Freeloader                   Kind of Blue  9:46",
+    "John Coltrane <emp… Naima                                <empty>       4:21",
+    "Bill Evans    4     Peace Piece                          Everybody Di… 6:42",
+    "",
+    "",
+  ];
+  assert_eq!(buf.text(), expected.join("\n"));
+
+  // Current song in bold, highlighted song reversed and found song underlined
+  assert!(buf.cell(3, 0).unwrap().style.bold);
+  assert_eq!(buf.cell(4, 70).unwrap().style, Style::color(COLOR_PAIR_MAIN_WINDOW_HIGHLIGHT).reverse());
+  assert!(buf.cell(5, 0).unwrap().style.underline);
+  assert_eq!(buf.cell(2, 14).unwrap().style.color, COLOR_PAIR_COLUMNS[1]);
+
+  // Small terminal, scrolled to the second song
+  let buf = playlist_snapshot(3, 40, 1, true);
+  let expected = [
+    "Artis Full  Title/Filename Album Time",
+    "────────────────────────────────────────",
+    "Mile… 2     Freddie Freel… Kind… 9:46",
+  ];
+  assert_eq!(buf.text(), expected.join("\n"));
+
+  // Hidden column titles
+  let buf = playlist_snapshot(3, 33, 1, false);
+  let expected = [
+    "Mi… 2     Freddie F… Ki… 9:46",
+    "Jo… <emp… Naima      <e… 4:21",
+    "Bi… 4     Peace Pie… Ev… 6:42",
+  ];
+  assert_eq!(buf.text(), expected.join("\n"));
+}
+
+#[test]
+fn test_draw_playlist_wide_text() {
+  let columns = ParamConfig::new().song_columns_list_format;
+  let songs = vec![
+    vec!["東京事変", "1", "群青日和", "教育", "3:41"],
+    vec!["Sigur Ro\u{301}s", "2", "Hoppípolla", "Takk…", "4:28"],
+    vec!["\u{1f3b7}\u{1f3b7}\u{1f3b7}", "3", "Saxophone \u{1f3b7}", "", "2:05"],
+  ];
+  let songs: Vec<Vec<String>> = songs.iter().map(|s| s.iter().map(|t| t.to_string()).collect()).collect();
+  let data: Vec<&[String]> = songs.iter().map(|s| s.as_slice()).collect();
+  let mut buf = CellBuffer::new(3, 40);
+  let rows = PlaylistRows {
+    columns: &columns,
+    data: &data,
+    current_song: None,
+    highlighted: Some(0),
+    found: &[],
+    first_row: 0,
+    titles_visible: false,
+    empty_tag_marker: "<empty>",
+  };
+  draw_playlist(&mut buf, &rows);
+
+  // Wide characters are never split, and columns stay aligned
+  let expected = [
+    "東京… 1     群青日和       教育  3:41",
+    "Sigu… 2     Hoppípolla     Takk… 4:28",
+    "\u{1f3b7}\u{1f3b7}… 3     Saxophone \u{1f3b7}   <emp… 2:05",
+  ];
+  assert_eq!(buf.text(), expected.join("\n"));
+  // The highlighted row is filled up to the next column
+  assert!(buf.cell(0, 5).unwrap().style.reverse);
+  assert!(buf.cell(0, 25).unwrap().style.reverse);
+}
+
+#[test]
+fn test_draw_statusbar() {
+  let mut scroller = Scroller::new(0);
+  let mut buf = CellBuffer::new(1, 40);
+  draw_statusbar(&mut buf, "Playing", "Miles Davis - So What", "[1:02/9:22]", &mut scroller);
+  assert_eq!(buf.text(), "Playing: Miles Davis - So Wh [1:02/9:22]");
+  assert_eq!(buf.cell(0, 0).unwrap().style, Style::color(COLOR_PAIR_STATUSBAR).bold());
+  assert_eq!(buf.cell(0, 30).unwrap().style, Style::color(COLOR_PAIR_TRACK).bold());
+
+  // The previous content is cleared
+  draw_statusbar(&mut buf, "", "Playlist cleared", "", &mut scroller);
+  assert_eq!(buf.text(), "  Playlist cleared");
+
+  let mut buf = CellBuffer::new(1, 30);
+  draw_statusbar(&mut buf, "Playing", "Miles Davis - So What", "[1:02/9:22]", &mut scroller);
+  assert_eq!(buf.text(), "Playing: Miles Dav [1:02/9:22]");
+
+  // Wide characters take two columns
+  let mut buf = CellBuffer::new(1, 30);
+  draw_statusbar(&mut buf, "再生中", "東京事変 - 群青日和", "[1:02/3:41]", &mut scroller);
+  assert_eq!(buf.text(), "再生中: 東京事変 - [1:02/3:41]");
+}
+
+#[test]
+fn test_draw_header_parts() {
+  let mut buf = CellBuffer::new(1, 30);
+  assert_eq!(draw_volume(&mut buf, 85), (18, 12));
+  assert_eq!(buf.text(), "                   Volume: 85%");
+  assert_eq!(buf.cell(0, 20).unwrap().style, Style::color(COLOR_PAIR_VOLUME));
+
+  let mut buf = CellBuffer::new(1, 30);
+  draw_stateline(&mut buf, "rz", COLOR_PAIR_STATE_LINE);
+  assert_eq!(buf.text(), "─────────────────────────[rz]─");
+  assert_eq!(buf.cell(0, 27).unwrap().style, Style::color(COLOR_PAIR_STATE_FLAGS).bold());
+
+  let mut buf = CellBuffer::new(1, 20);
+  assert_eq!(draw_volume(&mut buf, -1), (8, 12));
+  let format = SongFormat::parse("$b%a$/b - $2%t").unwrap();
+  let line = format.render(|p| String::from(if *p == SongProperty::Artist { "Air" } else { "Alone in Kyoto" }));
+  print_expressions(&mut buf, 0, 0, &line);
+  assert_eq!(buf.text(), "Air - Alone in Kyoto");
+  assert!(buf.cell(0, 0).unwrap().style.bold);
+  assert_eq!(buf.cell(0, 6).unwrap().style, Style::color(COLOR_PAIR_BLACK + 1));
+}
+
+#[test]
+fn test_draw_progressbar() {
+  let look = split_progressbar_look("=>");
+  let mut buf = CellBuffer::new(1, 10);
+  draw_progressbar(&mut buf, 50., &look);
+  assert_eq!(buf.text(), "=====>");
+  assert_eq!(buf.cell(0, 5).unwrap().style, Style::color(COLOR_PAIR_PROGRESSBAR_ELAPSED));
+
+  // Lines are drawn with the line-drawing characters of the terminal
+  let look = split_progressbar_look("─╼─");
+  draw_progressbar(&mut buf, 30., &look);
+  assert_eq!(buf.text(), "───╼──────");
+  assert_eq!(buf.cell(0, 4).unwrap().style, Style::color(COLOR_PAIR_PROGRESSBAR));
+}
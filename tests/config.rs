@@ -31,13 +31,14 @@ fn get_config_path(name: &str) -> PathBuf {
 fn load_default_config() {
   before_each();
 
-  use ncmpc::{ConfigLoader, ParamConfig};
+  use ncmpc::{ColorConfig, ConfigLoader, ParamConfig};
   let config_loader = ConfigLoader::new();
   let config_path = get_config_path("default_config");
   let config = config_loader.load(&Some(config_path), &None);
 
   let params = ParamConfig::new();
   assert_eq!(config.params, params);
+  assert_eq!(config.colors, ColorConfig::new());
 
   after_each();
 }
@@ -77,6 +78,156 @@ fn load_custom_config() {
   after_each();
 }
 
+/// Test used to check theme files.
+#[test]
+fn load_custom_theme() {
+  before_each();
+
+  use ncmpc::{ColorConfig, ConfigLoader};
+  let config_loader = ConfigLoader::new();
+  let theme_path = get_config_path("custom_theme");
+  let colors = config_loader.load_theme(theme_path.to_str().unwrap(), &ColorConfig::new()).unwrap();
+
+  let mut expected = ColorConfig::new();
+  expected.main_window = nc::COLOR_BLUE;
+  expected.main_window_highlight = nc::COLOR_RED;
+  expected.empty_tag = nc::COLOR_MAGENTA;
+  expected.active_column = nc::COLOR_CYAN;
+  expected.alternative_ui_separator = nc::COLOR_WHITE;
+  expected.color1 = nc::COLOR_YELLOW;
+  assert_eq!(colors, expected);
+
+  // Colors not set by the theme are kept
+  let mut base = ColorConfig::new();
+  base.header_window = nc::COLOR_GREEN;
+  base.main_window = nc::COLOR_WHITE;
+  let colors = config_loader.load_theme(theme_path.to_str().unwrap(), &base).unwrap();
+  expected.header_window = nc::COLOR_GREEN;
+  assert_eq!(colors, expected);
+
+  assert!(config_loader.load_theme("missing_theme", &base).is_none());
+
+  after_each();
+}
+
+/// Test used to check the diagnostics of invalid configurations.
+#[test]
+fn check_invalid_config() {
+  before_each();
+
+  use ncmpc::{ConfigLoader, Severity};
+  let config_loader = ConfigLoader::new();
+  let config_path = get_config_path("invalid_config");
+  let bindings_path = get_config_path("invalid_bindings");
+  let (config, diagnostics) = config_loader.load_with_diagnostics(&Some(config_path.clone()), &Some(bindings_path.clone()));
+
+  let found: Vec<(PathBuf, Option<u32>, &str, Severity)> = diagnostics
+    .iter()
+    .map(|d| (d.file.clone(), d.line, d.key.as_str(), d.severity))
+    .collect();
+  let expected = vec![
+    (config_path.clone(), Some(2), "cyclic_scrolling", Severity::Error),
+    (config_path.clone(), Some(3), "mpd_port", Severity::Error),
+    (config_path.clone(), Some(5), "main_window_color", Severity::Error),
+    (config_path.clone(), Some(6), "unknown_option", Severity::Warning),
+    (config_path.clone(), Some(7), "progressbar_look", Severity::Error),
+    (config_path.clone(), Some(8), "song_columns_list_format", Severity::Error),
+    (config_path.clone(), Some(9), "startup_screen", Severity::Error),
+    (config_path.clone(), Some(10), "lines_scrolled", Severity::Error),
+    (config_path.clone(), Some(11), "volume_change_step", Severity::Error),
+    (config_path.clone(), Some(12), "key_sequence_timeout", Severity::Error),
+    (bindings_path.clone(), Some(4), "unknown_key", Severity::Error),
+    (bindings_path.clone(), Some(7), "j", Severity::Error),
+    (bindings_path.clone(), Some(10), "update", Severity::Error),
+    (bindings_path.clone(), Some(13), "x", Severity::Error),
+  ];
+  assert_eq!(found, expected);
+
+  // Chains with an unknown action are ignored
+  assert!(!config.keys.custom.contains_key(&(None, vec!['x' as i32])));
+
+  // Invalid values are ignored
+  assert_eq!(config.params.mpd_port, 6600);
+  assert_eq!(config.params.progressbar_look, "=>");
+  assert_eq!(config.params.volume_change_step, 2);
+  // Options set twice take the last valid value
+  assert_eq!(config.params.lines_scrolled, 3);
+
+  after_each();
+}
+
+/// Test that the default configuration does not trigger any diagnostic.
+#[test]
+fn check_default_config() {
+  before_each();
+
+  use ncmpc::ConfigLoader;
+  let config_loader = ConfigLoader::new();
+  let config_path = get_config_path("default_config");
+  let (_, diagnostics) = config_loader.load_with_diagnostics(&Some(config_path), &None);
+  assert_eq!(diagnostics, vec![]);
+
+  after_each();
+}
+
+/// Test that ncmpcpp's default bindings are mapped to ncmpc's actions.
+#[test]
+fn check_default_bindings() {
+  before_each();
+
+  use ncmpc::{ConfigLoader, Severity};
+  let config_loader = ConfigLoader::new();
+  let bindings_path = get_config_path("default_bindings");
+  let (config, diagnostics) = config_loader.load_with_diagnostics(&None, &Some(bindings_path));
+
+  // Every key is valid, only unsupported actions are reported
+  assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+  assert!(diagnostics.iter().any(|d| d.message == "unsupported action \"select_item\""));
+  assert!(!diagnostics.iter().any(|d| d.message.contains("play_item")));
+  assert!(!diagnostics.iter().any(|d| d.message.starts_with("unknown action")));
+
+  let keycodes = |keys: &Vec<ncmpc::config::ControlKey>| {
+    use ncmpc::config::ToKeyCode;
+    keys.iter().map(|k| k.keycode()).collect::<Vec<i32>>()
+  };
+  assert!(keycodes(&config.keys.play_pause).contains(&('p' as i32)));
+  assert!(keycodes(&config.keys.press_enter).contains(&('\n' as i32)));
+  assert!(keycodes(&config.keys.clear).contains(&('c' as i32)));
+  assert!(keycodes(&config.keys.delete).contains(&nc::KEY_DC));
+  assert!(config.keys.custom.contains_key(&(None, vec![nc::KEY_MOUSE])));
+  // Chains with unsupported actions are ignored as a whole
+  assert!(!config.keys.custom.contains_key(&(None, vec![nc::KEY_SR])));
+  assert!(keycodes(&config.keys.previous_screen).contains(&nc::KEY_BTAB));
+
+  after_each();
+}
+
+/// Test options changed while running (e.g. with `:set`).
+#[test]
+fn set_config_options() {
+  before_each();
+
+  use ncmpc::ConfigLoader;
+  let config_loader = ConfigLoader::new();
+  let mut config = config_loader.load(&Some(get_config_path("default_config")), &None);
+
+  assert_eq!(config.set("cyclic_scrolling", "yes"), Ok(()));
+  assert!(config.params.cyclic_scrolling);
+  assert_eq!(config.set("volume_change_step", "5"), Ok(()));
+  assert_eq!(config.params.volume_change_step, 5);
+  assert_eq!(config.set("main_window_color", "red"), Ok(()));
+  assert_eq!(config.colors.main_window, nc::COLOR_RED);
+  assert_eq!(config.base_colors.main_window, nc::COLOR_RED);
+
+  assert_eq!(
+    config.set("cyclic_scrolling", "maybe"),
+    Err(String::from("invalid boolean \"maybe\" (expected \"yes\" or \"no\")"))
+  );
+  assert_eq!(config.set("unknown", "yes"), Err(String::from("unknown option \"unknown\"")));
+
+  after_each();
+}
+
 /// Test MPD socket address.
 #[test]
 fn mpd_socket_addr() {
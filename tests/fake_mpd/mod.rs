@@ -0,0 +1,608 @@
+//! In-process fake MPD server, speaking enough of the protocol to run the
+//! model in tests.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+/// Song of the database.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FakeSong {
+  pub file: String,
+  pub artist: String,
+  pub title: String,
+  pub album: String,
+  /// Duration in seconds.
+  pub time: u32,
+}
+
+impl FakeSong {
+  pub fn new(file: &str, artist: &str, title: &str, album: &str, time: u32) -> FakeSong {
+    FakeSong {
+      file: file.to_string(),
+      artist: artist.to_string(),
+      title: title.to_string(),
+      album: album.to_string(),
+      time: time,
+    }
+  }
+
+  fn tag(&self, tag: &str) -> Option<&str> {
+    match tag.to_lowercase().as_str() {
+      "file" => Some(&self.file),
+      "artist" => Some(&self.artist),
+      "title" => Some(&self.title),
+      "album" => Some(&self.album),
+      _ => None,
+    }
+  }
+
+  fn pairs(&self) -> Vec<(String, String)> {
+    return vec![
+      pair("file", &self.file),
+      pair("Artist", &self.artist),
+      pair("Title", &self.title),
+      pair("Album", &self.album),
+      pair("Time", self.time),
+    ];
+  }
+}
+
+/// Song of the queue.
+#[derive(Clone, Debug)]
+pub struct QueueEntry {
+  pub song: FakeSong,
+  pub id: u32,
+  pub prio: u8,
+}
+
+/// Playback state.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlayState {
+  Play,
+  Pause,
+  Stop,
+}
+
+/// State of the server, which tests can change or check at any time.
+pub struct ServerState {
+  pub database: Vec<FakeSong>,
+  pub queue: Vec<QueueEntry>,
+  pub state: PlayState,
+  /// Position of the current song in the queue.
+  pub current: Option<usize>,
+  /// Elapsed time of the current song, in seconds.
+  pub elapsed: u32,
+  pub volume: i8,
+  pub repeat: bool,
+  pub random: bool,
+  /// Version of the queue, incremented on each change.
+  pub version: u32,
+  next_id: u32,
+  /// Commands received (out of command list delimiters), in order.
+  pub commands: Vec<String>,
+}
+
+type Reply = Result<Vec<(String, String)>, String>;
+
+fn pair<T: ToString>(key: &str, value: T) -> (String, String) {
+  return (key.to_string(), value.to_string());
+}
+
+/// Parse a queue position or range ("3", "1:4" or "2:").
+fn parse_range(arg: &str, len: usize) -> Result<(usize, usize), String> {
+  let bad = || format!("Bad song index \"{}\"", arg);
+  let mut parts = arg.splitn(2, ':');
+  let start = parts.next().unwrap_or("").parse::<usize>().map_err(|_| bad())?;
+  let end = match parts.next() {
+    None => start + 1,
+    Some("") => len,
+    Some(end) => end.parse::<usize>().map_err(|_| bad())?,
+  };
+  if start >= end || end > len {
+    return Err(bad());
+  }
+  return Ok((start, end));
+}
+
+impl ServerState {
+  fn new(database: Vec<FakeSong>) -> ServerState {
+    ServerState {
+      database: database,
+      queue: Vec::new(),
+      state: PlayState::Stop,
+      current: None,
+      elapsed: 0,
+      volume: 50,
+      repeat: false,
+      random: false,
+      version: 1,
+      next_id: 1,
+      commands: Vec::new(),
+    }
+  }
+
+  /// Add a song to the queue, returning its id.
+  pub fn push(&mut self, song: FakeSong) -> u32 {
+    let id = self.next_id;
+    self.next_id += 1;
+    self.queue.push(QueueEntry {
+      song: song,
+      id: id,
+      prio: 0,
+    });
+    self.version += 1;
+    return id;
+  }
+
+  /// Add the songs of the database whose files are given to the queue.
+  pub fn add_files(&mut self, files: &[&str]) {
+    for file in files {
+      let song = self.database.iter().find(|s| s.file == *file).cloned();
+      self.push(song.unwrap_or_else(|| panic!("no song \"{}\" in the database", file)));
+    }
+  }
+
+  /// Files of the queue, in order.
+  pub fn queue_files(&self) -> Vec<String> {
+    return self.queue.iter().map(|e| e.song.file.clone()).collect();
+  }
+
+  fn position_of_id(&self, arg: &str) -> Result<usize, String> {
+    let id = arg.parse::<u32>().map_err(|_| format!("Bad song id \"{}\"", arg))?;
+    return self
+      .queue
+      .iter()
+      .position(|e| e.id == id)
+      .ok_or_else(|| String::from("No such song"));
+  }
+
+  fn entry_pairs(&self, pos: usize) -> Vec<(String, String)> {
+    let entry = &self.queue[pos];
+    let mut pairs = entry.song.pairs();
+    pairs.push(pair("Pos", pos));
+    pairs.push(pair("Id", entry.id));
+    if entry.prio > 0 {
+      pairs.push(pair("Prio", entry.prio));
+    }
+    return pairs;
+  }
+
+  fn status(&self) -> Vec<(String, String)> {
+    let state = match self.state {
+      PlayState::Play => "play",
+      PlayState::Pause => "pause",
+      PlayState::Stop => "stop",
+    };
+    let mut pairs = vec![
+      pair("volume", self.volume),
+      pair("repeat", self.repeat as u8),
+      pair("random", self.random as u8),
+      pair("single", 0),
+      pair("consume", 0),
+      pair("playlist", self.version),
+      pair("playlistlength", self.queue.len()),
+      pair("state", state),
+    ];
+    if let Some(pos) = self.current {
+      pairs.push(pair("song", pos));
+      pairs.push(pair("songid", self.queue[pos].id));
+      if self.state != PlayState::Stop {
+        pairs.push(pair("time", format!("{}:{}", self.elapsed, self.queue[pos].song.time)));
+        pairs.push(pair("bitrate", 320));
+      }
+    }
+    return pairs;
+  }
+
+  fn play(&mut self, pos: Option<usize>) -> Reply {
+    match pos.or(self.current).or(if self.queue.is_empty() { None } else { Some(0) }) {
+      Some(pos) if pos < self.queue.len() => {
+        self.current = Some(pos);
+        self.state = PlayState::Play;
+        self.elapsed = 0;
+        Ok(vec![])
+      }
+      Some(_) => Err(String::from("Bad song index")),
+      None => Ok(vec![]),
+    }
+  }
+
+  /// Remove songs from the queue, stopping if the current one is removed.
+  fn remove(&mut self, start: usize, end: usize) {
+    self.queue.drain(start..end);
+    self.current = match self.current {
+      Some(pos) if pos >= end => Some(pos - (end - start)),
+      Some(pos) if pos >= start => None,
+      current => current,
+    };
+    if self.current.is_none() {
+      self.state = PlayState::Stop;
+    }
+    self.version += 1;
+  }
+
+  fn set_flag(arg: Option<&String>) -> Result<bool, String> {
+    match arg.map(|s| s.as_str()) {
+      Some("0") => Ok(false),
+      Some("1") => Ok(true),
+      _ => Err(String::from("Boolean (0/1) expected")),
+    }
+  }
+
+  fn matching_songs(&self, filters: &[String]) -> Vec<FakeSong> {
+    let filters: Vec<&[String]> = filters.chunks(2).collect();
+    return self
+      .database
+      .iter()
+      .filter(|s| {
+        filters.iter().all(|f| match (f.first(), f.get(1)) {
+          // Songs of the fake database have no modification time
+          (Some(tag), Some(_)) if tag == "modified-since" => true,
+          (Some(tag), Some(value)) if tag == "any" => ["artist", "title", "album"].iter().any(|t| s.tag(t) == Some(value.as_str())),
+          (Some(tag), Some(value)) => s.tag(tag) == Some(value.as_str()),
+          _ => false,
+        })
+      })
+      .cloned()
+      .collect();
+  }
+
+  /// Run a command, given its name and arguments.
+  fn execute(&mut self, args: &[String]) -> Reply {
+    let arg = |i: usize| args.get(i).cloned().unwrap_or_default();
+    match args[0].as_str() {
+      "ping" | "crossfade" | "save" => Ok(vec![]),
+      "replay_gain_status" => Ok(vec![pair("replay_gain_mode", "off")]),
+      "status" => Ok(self.status()),
+      "stats" => Ok(vec![
+        pair("artists", 3),
+        pair("albums", 3),
+        pair("songs", self.database.len()),
+        pair("uptime", 60),
+        pair("playtime", 30),
+        pair("db_playtime", self.database.iter().map(|s| s.time).sum::<u32>()),
+        pair("db_update", 0),
+      ]),
+      "tagtypes" => Ok(vec![pair("tagtype", "Artist"), pair("tagtype", "Album"), pair("tagtype", "Title")]),
+      "urlhandlers" => Ok(vec![pair("handler", "http://")]),
+      "playlistinfo" => Ok((0..self.queue.len()).flat_map(|pos| self.entry_pairs(pos)).collect()),
+      "currentsong" => Ok(self.current.map(|pos| self.entry_pairs(pos)).unwrap_or_default()),
+      "play" if args.len() > 1 => {
+        let pos = arg(1).parse::<usize>().map_err(|_| String::from("Bad song index"))?;
+        self.play(Some(pos))
+      }
+      "play" => self.play(None),
+      "playid" => {
+        let pos = self.position_of_id(&arg(1))?;
+        self.play(Some(pos))
+      }
+      "pause" => {
+        self.state = match (self.state, args.get(1).map(|s| s.as_str())) {
+          (PlayState::Stop, _) => PlayState::Stop,
+          (_, Some("1")) => PlayState::Pause,
+          (_, Some("0")) => PlayState::Play,
+          (PlayState::Play, None) => PlayState::Pause,
+          _ => PlayState::Play,
+        };
+        Ok(vec![])
+      }
+      "stop" => {
+        self.state = PlayState::Stop;
+        self.elapsed = 0;
+        Ok(vec![])
+      }
+      "next" | "previous" => {
+        if let Some(pos) = self.current {
+          let next = if args[0] == "next" { pos + 1 } else { pos.saturating_sub(1) };
+          if next < self.queue.len() {
+            self.current = Some(next);
+          } else {
+            self.current = None;
+            self.state = PlayState::Stop;
+          }
+          self.elapsed = 0;
+        }
+        Ok(vec![])
+      }
+      "seekcur" => {
+        self.elapsed = arg(1).parse::<f64>().map_err(|_| String::from("Bad time"))? as u32;
+        Ok(vec![])
+      }
+      "setvol" => match arg(1).parse::<i8>() {
+        Ok(vol) if (0..=100).contains(&vol) => {
+          self.volume = vol;
+          Ok(vec![])
+        }
+        _ => Err(String::from("Invalid volume value")),
+      },
+      "repeat" => {
+        self.repeat = ServerState::set_flag(args.get(1))?;
+        Ok(vec![])
+      }
+      "random" => {
+        self.random = ServerState::set_flag(args.get(1))?;
+        Ok(vec![])
+      }
+      "clear" => {
+        let len = self.queue.len();
+        self.remove(0, len);
+        Ok(vec![])
+      }
+      "delete" => {
+        let (start, end) = parse_range(&arg(1), self.queue.len())?;
+        self.remove(start, end);
+        Ok(vec![])
+      }
+      "deleteid" => {
+        let pos = self.position_of_id(&arg(1))?;
+        self.remove(pos, pos + 1);
+        Ok(vec![])
+      }
+      "move" => {
+        let (start, end) = parse_range(&arg(1), self.queue.len())?;
+        let to = arg(2).parse::<usize>().map_err(|_| String::from("Bad position"))?;
+        let moved: Vec<QueueEntry> = self.queue.drain(start..end).collect();
+        let to = to.min(self.queue.len());
+        for (i, entry) in moved.into_iter().enumerate() {
+          self.queue.insert(to + i, entry);
+        }
+        self.version += 1;
+        Ok(vec![])
+      }
+      "prio" | "prioid" => {
+        let prio = arg(1).parse::<u8>().map_err(|_| String::from("Bad priority"))?;
+        for target in &args[2..] {
+          let (start, end) = if args[0] == "prio" {
+            parse_range(target, self.queue.len())?
+          } else {
+            let pos = self.position_of_id(target)?;
+            (pos, pos + 1)
+          };
+          for entry in &mut self.queue[start..end] {
+            entry.prio = prio;
+          }
+        }
+        self.version += 1;
+        Ok(vec![])
+      }
+      "add" => {
+        let uri = arg(1);
+        let songs: Vec<FakeSong> = self.database.iter().filter(|s| s.file.starts_with(&uri)).cloned().collect();
+        if songs.is_empty() {
+          return Err(String::from("No such directory"));
+        }
+        for song in songs {
+          self.push(song);
+        }
+        Ok(vec![])
+      }
+      "find" => Ok(self.matching_songs(&args[1..]).iter().flat_map(|s| s.pairs()).collect()),
+      "findadd" => {
+        for song in self.matching_songs(&args[1..]) {
+          self.push(song);
+        }
+        Ok(vec![])
+      }
+      "list" => {
+        let tag = arg(1);
+        let mut values: Vec<String> = Vec::new();
+        for song in self.matching_songs(&args[2..]) {
+          let value = song.tag(&tag).unwrap_or("").to_string();
+          if !values.contains(&value) {
+            values.push(value);
+          }
+        }
+        Ok(values.into_iter().map(|v| pair(&tag, v)).collect())
+      }
+      _ => Err(String::from("unknown command")),
+    }
+  }
+}
+
+/// Split a command line into its words, unquoting quoted ones.
+fn split_words(line: &str) -> Vec<String> {
+  let mut words = Vec::new();
+  let mut chars = line.chars().peekable();
+  loop {
+    while chars.peek() == Some(&' ') {
+      chars.next();
+    }
+    let mut word = String::new();
+    match chars.peek() {
+      None => break,
+      Some('"') => {
+        chars.next();
+        while let Some(c) = chars.next() {
+          match c {
+            '"' => break,
+            '\\' => word.extend(chars.next()),
+            c => word.push(c),
+          }
+        }
+      }
+      Some(_) => {
+        while let Some(&c) = chars.peek() {
+          if c == ' ' {
+            break;
+          }
+          word.push(c);
+          chars.next();
+        }
+      }
+    }
+    words.push(word);
+  }
+  return words;
+}
+
+/// Run commands, stopping at the first failing one, and format the reply.
+fn run_commands(state: &Mutex<ServerState>, commands: &[Vec<String>], list_ok: bool) -> String {
+  let mut state = state.lock().unwrap();
+  let mut out = String::new();
+  for (i, args) in commands.iter().enumerate() {
+    state.commands.push(args.join(" "));
+    match state.execute(args) {
+      Ok(pairs) => {
+        for (key, value) in pairs {
+          out.push_str(&format!("{}: {}\n", key, value));
+        }
+        if list_ok {
+          out.push_str("list_OK\n");
+        }
+      }
+      Err(msg) => {
+        out.push_str(&format!("ACK [5@{}] {{{}}} {}\n", i, args[0], msg));
+        return out;
+      }
+    }
+  }
+  out.push_str("OK\n");
+  return out;
+}
+
+/// Serve a client until it disconnects.
+fn serve(stream: TcpStream, state: Arc<Mutex<ServerState>>) {
+  let mut writer = match stream.try_clone() {
+    Ok(s) => s,
+    Err(_) => return,
+  };
+  if writer.write_all(b"OK MPD 0.21.0\n").is_err() {
+    return;
+  }
+
+  // Commands of the command list being received, and whether each command
+  // of the list is acknowledged
+  let mut list: Option<(Vec<Vec<String>>, bool)> = None;
+  for line in BufReader::new(stream).lines() {
+    let words = match line {
+      Ok(ref l) => split_words(l),
+      Err(_) => break,
+    };
+    if words.is_empty() {
+      continue;
+    }
+    let reply = match (words[0].as_str(), list.as_mut()) {
+      ("command_list_begin", _) => {
+        list = Some((Vec::new(), false));
+        continue;
+      }
+      ("command_list_ok_begin", _) => {
+        list = Some((Vec::new(), true));
+        continue;
+      }
+      ("command_list_end", _) => {
+        let (commands, list_ok) = list.take().unwrap_or_default();
+        run_commands(&state, &commands, list_ok)
+      }
+      (_, Some((commands, _))) => {
+        commands.push(words);
+        continue;
+      }
+      (_, None) => run_commands(&state, &[words], false),
+    };
+    if writer.write_all(reply.as_bytes()).is_err() {
+      break;
+    }
+  }
+}
+
+/// Fake MPD server listening on a local port.
+pub struct FakeMpd {
+  addr: SocketAddr,
+  state: Arc<Mutex<ServerState>>,
+  /// Open client connections.
+  connections: Arc<Mutex<Vec<TcpStream>>>,
+  /// Whether the server stops accepting connections.
+  stopped: Arc<AtomicBool>,
+}
+
+impl FakeMpd {
+  /// Start a server on a free local port, with a database and an empty queue.
+  pub fn start(database: Vec<FakeSong>) -> FakeMpd {
+    let state = Arc::new(Mutex::new(ServerState::new(database)));
+    return FakeMpd::listen("127.0.0.1:0", state);
+  }
+
+  fn listen(addr: &str, state: Arc<Mutex<ServerState>>) -> FakeMpd {
+    // The port of a stopped server is freed once its thread wakes up
+    let mut listener = TcpListener::bind(addr);
+    for _ in 0..100 {
+      if listener.is_ok() {
+        break;
+      }
+      thread::sleep(Duration::from_millis(10));
+      listener = TcpListener::bind(addr);
+    }
+    let listener = listener.expect("cannot start the fake MPD server");
+    let server = FakeMpd {
+      addr: listener.local_addr().unwrap(),
+      state: state,
+      connections: Arc::new(Mutex::new(Vec::new())),
+      stopped: Arc::new(AtomicBool::new(false)),
+    };
+
+    let (state, connections, stopped) = (server.state.clone(), server.connections.clone(), server.stopped.clone());
+    thread::spawn(move || {
+      for stream in listener.incoming() {
+        if stopped.load(Ordering::SeqCst) {
+          break;
+        }
+        if let Ok(stream) = stream {
+          if let Ok(s) = stream.try_clone() {
+            connections.lock().unwrap().push(s);
+          }
+          let state = state.clone();
+          thread::spawn(move || serve(stream, state));
+        }
+      }
+    });
+    return server;
+  }
+
+  pub fn port(&self) -> u16 {
+    return self.addr.port();
+  }
+
+  /// State of the server (locked until the guard is dropped).
+  pub fn state(&self) -> MutexGuard<'_, ServerState> {
+    return self.state.lock().unwrap();
+  }
+
+  /// Commands received so far, forgetting them.
+  pub fn take_commands(&self) -> Vec<String> {
+    let mut state = self.state();
+    return state.commands.drain(..).collect();
+  }
+
+  /// Close the connections of the clients, which can connect again.
+  pub fn disconnect(&self) {
+    for stream in self.connections.lock().unwrap().drain(..) {
+      let _ = stream.shutdown(Shutdown::Both);
+    }
+  }
+
+  /// Stop the server: close the connections and stop listening.
+  pub fn stop(&self) {
+    if !self.stopped.swap(true, Ordering::SeqCst) {
+      // Wake up the listening thread
+      let _ = TcpStream::connect(self.addr);
+    }
+    self.disconnect();
+  }
+
+  /// Start the stopped server again, on the same port and with the same
+  /// state.
+  pub fn restart(&self) -> FakeMpd {
+    return FakeMpd::listen(&self.addr.to_string(), self.state.clone());
+  }
+}
+
+impl Drop for FakeMpd {
+  fn drop(&mut self) {
+    self.stop();
+  }
+}
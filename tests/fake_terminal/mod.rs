@@ -0,0 +1,51 @@
+//! Fake terminal reading from and writing to /dev/null, to run the model
+//! without the real terminal.
+
+extern crate ncurses;
+
+use ncurses as nc;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+
+extern "C" {
+  fn fopen(path: *const c_char, mode: *const c_char) -> nc::ll::FILE_p;
+  fn fclose(file: nc::ll::FILE_p) -> c_int;
+}
+
+/// Fake terminal, deleted (and its files closed) when dropped, which must
+/// happen after the views drawing on it are dropped.
+pub struct NullTerminal {
+  screen: nc::SCREEN,
+  output: nc::ll::FILE_p,
+  input: nc::ll::FILE_p,
+}
+
+impl NullTerminal {
+  pub fn open() -> NullTerminal {
+    let path = CString::new("/dev/null").unwrap();
+    let (read, write) = (CString::new("r").unwrap(), CString::new("w").unwrap());
+    let (output, input) = unsafe { (fopen(path.as_ptr(), write.as_ptr()), fopen(path.as_ptr(), read.as_ptr())) };
+    assert!(!output.is_null() && !input.is_null(), "cannot open /dev/null");
+    let screen = nc::newterm(Some("xterm"), output, input);
+    assert!(!screen.is_null(), "cannot open a fake terminal");
+    return NullTerminal {
+      screen: screen,
+      output: output,
+      input: input,
+    };
+  }
+
+  pub fn screen(&self) -> nc::SCREEN {
+    return self.screen;
+  }
+}
+
+impl Drop for NullTerminal {
+  fn drop(&mut self) {
+    nc::delscreen(self.screen);
+    unsafe {
+      fclose(self.output);
+      fclose(self.input);
+    }
+  }
+}
@@ -0,0 +1,394 @@
+extern crate mpd;
+extern crate ncmpc;
+extern crate ncurses;
+
+mod fake_mpd;
+mod fake_terminal;
+
+use fake_mpd::{FakeMpd, FakeSong, PlayState};
+use fake_terminal::NullTerminal;
+use mpd::status::State;
+use ncmpc::config::Config;
+use ncmpc::constants::KEY_ESCAPE;
+use ncmpc::format::SongProperty;
+use ncmpc::view::ActiveWindow;
+use ncmpc::{Controller, Model, View};
+use ncurses as nc;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::sleep;
+use std::time::Duration;
+
+/// ncurses is global, so the tests create their views one at a time.
+static NCURSES: Mutex<()> = Mutex::new(());
+
+fn lock_ncurses() -> MutexGuard<'static, ()> {
+  return NCURSES.lock().unwrap_or_else(|e| e.into_inner());
+}
+
+fn database() -> Vec<FakeSong> {
+  vec![
+    FakeSong::new("jazz/so_what.flac", "Miles Davis", "So What", "Kind of Blue", 562),
+    FakeSong::new("jazz/freddie.flac", "Miles Davis", "Freddie Freeloader", "Kind of Blue", 586),
+    FakeSong::new("jazz/naima.flac", "John Coltrane", "Naima", "Giant Steps", 261),
+    FakeSong::new("jazz/peace.flac", "Bill Evans", "Peace Piece", "Everybody Digs Bill Evans", 402),
+  ]
+}
+
+/// Server with the first three songs of the database in its queue.
+fn start_server() -> FakeMpd {
+  let server = FakeMpd::start(database());
+  server
+    .state()
+    .add_files(&["jazz/so_what.flac", "jazz/freddie.flac", "jazz/naima.flac"]);
+  return server;
+}
+
+fn get_config(server: &FakeMpd) -> Config {
+  let mut config = Config::new();
+  config.params.mpd_host = String::from("127.0.0.1");
+  config.params.mpd_port = server.port();
+  return config;
+}
+
+#[test]
+fn playback_actions() {
+  let _lock = lock_ncurses();
+  let server = start_server();
+  let config = get_config(&server);
+  let terminal = NullTerminal::open();
+  let mut view = View::on_terminal(&config, terminal.screen());
+  let mut model = Model::new(&mut view, &config);
+  model.take_snapshot();
+  assert_eq!(model.status().state, State::Stop);
+
+  model.playlist_play();
+  assert_eq!(server.state().state, PlayState::Play);
+  assert_eq!(server.state().current, Some(0));
+
+  model.playlist_next();
+  model.take_snapshot();
+  assert_eq!(model.status().state, State::Play);
+  assert_eq!(model.status().song.map(|s| s.pos), Some(1));
+  assert!(model.has_playing_song());
+
+  // Pausing depends on the state of the last snapshot
+  model.playlist_pause();
+  assert_eq!(server.state().state, PlayState::Pause);
+  model.take_snapshot();
+  model.playlist_pause();
+  assert_eq!(server.state().state, PlayState::Play);
+
+  model.playlist_previous();
+  assert_eq!(server.state().current, Some(0));
+  model.playlist_stop();
+  assert_eq!(server.state().state, PlayState::Stop);
+
+  // The volume is kept in [0, 100]
+  server.take_commands();
+  model.set_volume(120);
+  assert_eq!(server.take_commands(), vec!["setvol 100"]);
+  model.take_snapshot();
+  assert_eq!(model.get_volume(), 100);
+
+  model.toggle_repeat();
+  model.toggle_random();
+  model.take_snapshot();
+  assert!(model.status().repeat && model.status().random);
+  model.toggle_repeat();
+  assert!(!server.state().repeat);
+}
+
+#[test]
+fn queue_edits() {
+  let _lock = lock_ncurses();
+  let server = start_server();
+  let config = get_config(&server);
+  let terminal = NullTerminal::open();
+  let mut view = View::on_terminal(&config, terminal.screen());
+  let mut model = Model::new(&mut view, &config);
+  model.take_snapshot();
+  model.update_main_window();
+
+  // Select the second song, delete it, then play the new second one
+  model.scroll_down();
+  model.scroll_down();
+  model.playlist_delete_items();
+  assert_eq!(server.state().queue_files(), vec!["jazz/so_what.flac", "jazz/naima.flac"]);
+  model.play_selected();
+  assert_eq!(server.state().current, Some(1));
+
+  model.run_command("add artist \"Bill Evans\"");
+  assert_eq!(server.state().queue.len(), 3);
+  assert_eq!(server.state().queue[2].song.title, "Peace Piece");
+
+  // The rows of the playlist follow the queue of the server
+  model.refresh_queue();
+  model.take_snapshot();
+  model.update_main_window();
+  model.run_command("set_selected_items_priority 42");
+  assert_eq!(server.state().queue[1].prio, 42);
+
+  model.playlist_clear();
+  assert!(server.state().queue.is_empty());
+  assert_eq!(server.state().state, PlayState::Stop);
+
+  // Random items are added with the songs of random values of a tag
+  server.take_commands();
+  model.add_random_items(3, "artists", Some(1));
+  let mut commands = server.take_commands();
+  commands.sort();
+  assert_eq!(
+    commands,
+    vec![
+      "findadd artist Bill Evans",
+      "findadd artist John Coltrane",
+      "findadd artist Miles Davis",
+      "list artist"
+    ]
+  );
+  assert_eq!(server.state().queue.len(), 4);
+  model.playlist_clear();
+  assert!(server.state().queue.is_empty());
+
+  // Random songs are picked among all the songs of the database
+  server.take_commands();
+  model.add_random_items(2, "songs", Some(1));
+  let commands = server.take_commands();
+  assert_eq!(commands[0], "find modified-since 0");
+  assert_eq!(commands.iter().filter(|c| c.starts_with("findadd file jazz/")).count(), 2);
+  assert_eq!(server.state().queue.len(), 2);
+}
+
+#[test]
+fn snapshot_updates() {
+  let _lock = lock_ncurses();
+  let server = start_server();
+  let config = get_config(&server);
+  let terminal = NullTerminal::open();
+  let mut view = View::on_terminal(&config, terminal.screen());
+  let mut model = Model::new(&mut view, &config);
+  model.take_snapshot();
+  assert_eq!(model.queue().len(), 3);
+  assert_eq!(model.queue()[2].title.as_deref(), Some("Naima"));
+  assert_eq!(model.playlist_info().size, 3);
+  assert_eq!(model.playlist_info().duration.num_seconds(), 562 + 586 + 261);
+
+  // Changes made by other clients show up once the cached queue expires
+  {
+    let mut state = server.state();
+    state.add_files(&["jazz/peace.flac"]);
+    state.volume = 80;
+  }
+  model.take_snapshot();
+  assert_eq!(model.get_volume(), 80);
+  model.refresh_queue();
+  model.take_snapshot();
+  assert_eq!(model.queue().len(), 4);
+  assert_eq!(model.playlist_info().size, 4);
+  assert_eq!(model.playlist_info().duration.num_seconds(), 562 + 586 + 261 + 402);
+
+  // The playing song is drawn without error
+  server.state().current = Some(3);
+  server.state().state = PlayState::Play;
+  model.take_snapshot();
+  model.update_main_window();
+  model.update_statusbar();
+  assert_eq!(model.status().song.map(|s| s.id.0), Some(server.state().queue[3].id));
+
+  // The time left in the queue follows the playing song
+  server.state().current = Some(1);
+  server.state().elapsed = 86;
+  model.take_snapshot();
+  assert_eq!(model.playlist_info().remaining.num_seconds(), 586 + 261 + 402 - 86);
+}
+
+#[test]
+fn window_title() {
+  let _lock = lock_ncurses();
+  let server = start_server();
+  let config = get_config(&server);
+  let terminal = NullTerminal::open();
+  let mut view = View::on_terminal(&config, terminal.screen());
+  let mut model = Model::new(&mut view, &config);
+  model.take_snapshot();
+  assert_eq!(model.window_title_text(), "ncmpc");
+
+  model.playlist_play();
+  model.take_snapshot();
+  assert_eq!(model.window_title_text(), "Miles Davis - So What");
+  model.update_window_title();
+
+  // Groups with an unset property ("{%a - }") are skipped
+  server.state().queue[1].song.artist = String::new();
+  model.refresh_queue();
+  model.playlist_next();
+  model.take_snapshot();
+  assert_eq!(model.window_title_text(), "Freddie Freeloader");
+
+  // Songs without title fall back to the alternative of "{%t}"
+  server.state().queue[1].song.title = String::new();
+  model.refresh_queue();
+  model.take_snapshot();
+  assert_eq!(model.window_title_text(), "freddie.flac");
+
+  model.run_command("set enable_window_title no");
+  model.update_window_title();
+}
+
+#[test]
+fn count_prefix() {
+  let _lock = lock_ncurses();
+  let server = start_server();
+  let mut config = get_config(&server);
+  config.params.key_sequence_timeout = 50;
+  let terminal = NullTerminal::open();
+  let mut view = View::on_terminal(&config, terminal.screen());
+  let mut model = Arc::new(Mutex::new(Model::new(&mut view, &config)));
+  let shared = model.clone();
+  shared.lock().unwrap().take_snapshot();
+  shared.lock().unwrap().update_main_window();
+  let mut controller = Controller::new(&mut model, &config);
+  let mut type_key = |key: i32| {
+    nc::ungetch(key);
+    controller.process_input();
+  };
+
+  // "1" is bound to show_playlist, but it starts a count when other keys
+  // follow it
+  type_key(nc::KEY_DOWN);
+  type_key('1' as i32);
+  type_key('0' as i32);
+  type_key(nc::KEY_DOWN);
+  shared.lock().unwrap().play_selected();
+  assert_eq!(server.state().current, Some(2));
+
+  // Alone, it runs its action after the timeout
+  shared.lock().unwrap().show_screen(ActiveWindow::Help);
+  type_key('1' as i32);
+  assert_eq!(shared.lock().unwrap().active_screen(), "help");
+  sleep(Duration::from_millis(100));
+  controller.process_input();
+  assert_eq!(shared.lock().unwrap().active_screen(), "playlist");
+}
+
+#[test]
+fn sort_by_column() {
+  let _lock = lock_ncurses();
+  let server = start_server();
+  let config = get_config(&server);
+  let terminal = NullTerminal::open();
+  let mut view = View::on_terminal(&config, terminal.screen());
+  let mut model = Model::new(&mut view, &config);
+  model.take_snapshot();
+  model.update_main_window();
+
+  // Only the displayed rows are sorted, the first one being "Freddie Freeloader"
+  model.sort_playlist_by(&SongProperty::Title);
+  model.update_main_window();
+  model.scroll_down();
+  model.play_selected();
+  assert_eq!(server.state().current, Some(1));
+  assert_eq!(
+    server.state().queue_files(),
+    vec!["jazz/so_what.flac", "jazz/freddie.flac", "jazz/naima.flac"]
+  );
+
+  // Sorting again by the same column shows the queue order, keeping the
+  // same song selected
+  model.sort_playlist_by(&SongProperty::Title);
+  model.update_main_window();
+  model.scroll_down();
+  model.play_selected();
+  assert_eq!(server.state().current, Some(2));
+}
+
+#[test]
+fn set_options() {
+  let _lock = lock_ncurses();
+  let server = start_server();
+  let config = get_config(&server);
+  let terminal = NullTerminal::open();
+  let mut view = View::on_terminal(&config, terminal.screen());
+  let mut model = Model::new(&mut view, &config);
+  assert_eq!(model.key_sequence_timeout(), 1000);
+  model.run_command("set key_sequence_timeout 250");
+  assert_eq!(model.key_sequence_timeout(), 250);
+
+  // Without statusbar, the command line takes a row of the main window
+  model.run_command("set statusbar_visibility no");
+  let height = model.playlist_height();
+  model.execute_command();
+  model.update_statusbar();
+  assert_eq!(model.playlist_height(), height - 1);
+  model.process_command_line_key(KEY_ESCAPE);
+  model.update_statusbar();
+  assert_eq!(model.playlist_height(), height);
+
+  // Options changed while running are kept when the configuration is reloaded
+  let mut reloaded = get_config(&server);
+  reloaded.params.key_sequence_timeout = 500;
+  reloaded.params.header_visibility = false;
+  model.set_config(reloaded);
+  assert_eq!(model.key_sequence_timeout(), 250);
+  assert!(model.playlist_height() > height);
+}
+
+#[test]
+fn prompt_histories() {
+  let _lock = lock_ncurses();
+  let server = start_server();
+  let config = get_config(&server);
+  let terminal = NullTerminal::open();
+  let mut view = View::on_terminal(&config, terminal.screen());
+  let mut model = Model::new(&mut view, &config);
+  let commands = model.command_history().len();
+
+  // Patterns to find are not commands
+  model.find();
+  for ch in "Naima\n".chars() {
+    model.process_command_line_key(ch as i32);
+  }
+  model.execute_command();
+  model.process_command_line_key(KEY_ESCAPE);
+  assert_eq!(model.command_history().len(), commands);
+  assert!(!model.command_history().iter().any(|c| c == "Naima"));
+}
+
+#[test]
+fn reconnect() {
+  let _lock = lock_ncurses();
+  let server = start_server();
+  let config = get_config(&server);
+  let terminal = NullTerminal::open();
+  let mut view = View::on_terminal(&config, terminal.screen());
+  let mut model = Model::new(&mut view, &config);
+  model.take_snapshot();
+
+  // Closed connections are opened again at the next snapshot
+  server.disconnect();
+  model.take_snapshot();
+  model.take_snapshot();
+  model.playlist_play();
+  assert_eq!(server.state().state, PlayState::Play);
+  assert_eq!(model.queue().len(), 3);
+
+  // The status is reset while the server is unreachable
+  server.stop();
+  model.take_snapshot();
+  assert_eq!(model.status().state, State::Stop);
+  model.playlist_next();
+  model.update_statusbar();
+
+  // Then the model connects to the restarted server, after a delay
+  let server = server.restart();
+  server.state().volume = 30;
+  model.take_snapshot();
+  assert_eq!(model.status().state, State::Stop);
+  sleep(Duration::from_millis(300));
+  model.take_snapshot();
+  model.take_snapshot();
+  assert_eq!(model.get_volume(), 30);
+  assert_eq!(model.status().state, State::Play);
+  model.playlist_stop();
+  assert_eq!(server.state().state, PlayState::Stop);
+}